@@ -0,0 +1,46 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use engine2d::objects::{Rect, Vec2};
+use engine2d::screen::Screen;
+use engine2d::texture::Texture;
+
+mod common;
+use common::{background, noise_texture, portrait_texture, reference_bitblt};
+
+const WIDTH: usize = 1280;
+const HEIGHT: usize = 720;
+
+fn blit_with(c: &mut Criterion, name: &str, tex: &Texture) {
+    let (tw, th) = tex.size();
+    let from = Rect::new(0.0, 0.0, tw as f32, th as f32);
+    let to = Vec2::new(100.0, 40.0);
+    let bg = background(WIDTH, HEIGHT, 1);
+
+    // the whole point is that the fast path draws exactly what the old one did
+    let mut expected = bg.clone();
+    reference_bitblt(&mut expected, WIDTH, HEIGHT, tex, from, to);
+    let mut actual = bg.clone();
+    Screen::wrap(&mut actual, WIDTH, HEIGHT).bitblt(tex, from, to);
+    assert!(expected == actual, "{}: new blitter output differs", name);
+
+    let mut group = c.benchmark_group(name);
+    let mut fb = bg.clone();
+    group.bench_function("reference", |b| {
+        b.iter(|| reference_bitblt(&mut fb, WIDTH, HEIGHT, tex, black_box(from), black_box(to)))
+    });
+    let mut fb = bg;
+    group.bench_function("bitblt", |b| {
+        b.iter(|| Screen::wrap(&mut fb, WIDTH, HEIGHT).bitblt(tex, black_box(from), black_box(to)))
+    });
+    group.finish();
+}
+
+fn portrait(c: &mut Criterion) {
+    blit_with(c, "portrait", &portrait_texture(600, 640, 2));
+}
+
+fn noise(c: &mut Criterion) {
+    blit_with(c, "noise", &noise_texture(600, 640, 3));
+}
+
+criterion_group!(benches, portrait, noise);
+criterion_main!(benches);
@@ -0,0 +1,109 @@
+// Shared between the blit benchmark and tests/blit.rs: the straightforward per-pixel
+// float blitter that Screen::bitblt has to keep matching, and some textures to blit.
+#![allow(dead_code)]
+
+use engine2d::objects::{Rect, Vec2, DEPTH};
+use engine2d::texture::Texture;
+use image::{Rgba, RgbaImage};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// The blitter as it was before the span fast paths, on a raw framebuffer
+pub fn reference_bitblt(
+    framebuffer: &mut [u8],
+    width: usize,
+    height: usize,
+    src: &Texture,
+    from: Rect,
+    Vec2 { x: to_x, y: to_y }: Vec2,
+) {
+    let to_x = to_x as i32;
+    let to_y = to_y as i32;
+    if (to_x + from.w as i32) < 0
+        || (width as i32) <= to_x
+        || (to_y + from.h as i32) < 0
+        || (height as i32) <= to_y
+    {
+        return;
+    }
+    let src_pitch = src.pitch();
+    let dst_pitch = width * DEPTH;
+    let y_skip = to_y.max(0) - to_y;
+    let x_skip = to_x.max(0) - to_x;
+    let y_count = (to_y + from.h as i32).min(height as i32) - to_y;
+    let x_count = (to_x + from.w as i32).min(width as i32) - to_x;
+    for (row_a, row_b) in src.buffer()[(src_pitch * ((from.y as i32 + y_skip) as usize))
+        ..(src_pitch * ((from.y as i32 + y_count) as usize))]
+        .chunks_exact(src_pitch)
+        .zip(
+            framebuffer[(dst_pitch * ((to_y + y_skip) as usize))
+                ..(dst_pitch * ((to_y + y_count) as usize))]
+                .chunks_exact_mut(dst_pitch),
+        )
+    {
+        let to_cols = row_b
+            [(DEPTH * (to_x + x_skip) as usize)..(DEPTH * (to_x + x_count) as usize)]
+            .chunks_exact_mut(DEPTH);
+        let from_cols = row_a[(DEPTH * (from.x as i32 + x_skip) as usize)
+            ..(DEPTH * (from.x as i32 + x_count) as usize)]
+            .chunks_exact(DEPTH);
+        for (to, from) in to_cols.zip(from_cols) {
+            reference_composite(to, from);
+        }
+    }
+}
+
+pub fn reference_composite(to: &mut [u8], from: &[u8]) {
+    let ta = to[3] as f32 / 255.0;
+    let fa = from[3] as f32 / 255.0;
+    for i in 0..3 {
+        to[i] = from[i].saturating_add((to[i] as f32 * (1.0 - fa)).round() as u8);
+    }
+    to[3] = ((fa + ta * (1.0 - fa)) * 255.0).round() as u8;
+}
+
+// Like a character portrait: transparent margins, an opaque body,
+// and a band of partly transparent pixels around its edge
+pub fn portrait_texture(w: u32, h: u32, seed: u64) -> Texture {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    let radius = w.min(h) as f32 * 0.4;
+    let img = RgbaImage::from_fn(w, h, |x, y| {
+        let d = Vec2::new(x as f32 - cx, y as f32 - cy).length();
+        let alpha = if d < radius {
+            255
+        } else if d < radius + 4.0 {
+            rng.gen_range(1..255)
+        } else {
+            0
+        };
+        Rgba([rng.gen(), rng.gen(), rng.gen(), alpha])
+    });
+    Texture::new(img)
+}
+
+// Every pixel gets an arbitrary alpha, so nearly everything goes through the slow path
+pub fn noise_texture(w: u32, h: u32, seed: u64) -> Texture {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let img = RgbaImage::from_fn(w, h, |_, _| {
+        let alpha = match rng.gen_range(0..4) {
+            0 => 0,
+            1 => 255,
+            _ => rng.gen(),
+        };
+        Rgba([rng.gen(), rng.gen(), rng.gen(), alpha])
+    });
+    Texture::new(img)
+}
+
+// A framebuffer that's already got something partly transparent in it
+pub fn background(width: usize, height: usize, seed: u64) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..width * height)
+        .flat_map(|_| {
+            let a: u8 = rng.gen();
+            let c = |rng: &mut StdRng| rng.gen_range(0..=a);
+            [c(&mut rng), c(&mut rng), c(&mut rng), a]
+        })
+        .collect()
+}
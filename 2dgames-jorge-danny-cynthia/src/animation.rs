@@ -1,46 +1,198 @@
-use crate::objects::Rect;
+use crate::objects::{Rect, Vec2};
+use std::collections::VecDeque;
 use std::rc::Rc;
 
+// events nobody polls get dropped oldest first past this point
+const MAX_PENDING_EVENTS: usize = 64;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Playback {
+    // play through once and hold the last frame
+    Once,
+    Loop,
+    // forwards then backwards, forever
+    PingPong,
+    // play through once backwards and hold the first frame
+    Reverse,
+}
+
+#[derive(PartialEq)]
+pub struct Frame {
+    pub rect: Rect,
+    // how long the frame is shown for, in seconds
+    pub duration: f32,
+    // named markers that fire when the animation reaches this frame, e.g. "flap_sound"
+    pub events: Vec<String>,
+    // named points in frame pixels where child sprites get attached, e.g. "beak"
+    pub anchors: Vec<(String, Vec2)>,
+}
+
+impl Frame {
+    pub fn new(rect: Rect, duration: f32) -> Self {
+        Self {
+            rect,
+            duration,
+            events: Vec::new(),
+            anchors: Vec::new(),
+        }
+    }
+
+    pub fn with_events(rect: Rect, duration: f32, events: &[&str]) -> Self {
+        Self {
+            rect,
+            duration,
+            events: events.iter().map(|e| e.to_string()).collect(),
+            anchors: Vec::new(),
+        }
+    }
+
+    pub fn with_anchor(mut self, name: &str, point: Vec2) -> Self {
+        self.anchors.push((name.to_string(), point));
+        self
+    }
+
+    pub fn anchor(&self, name: &str) -> Option<Vec2> {
+        self.anchors
+            .iter()
+            .find(|(anchor, _)| anchor == name)
+            .map(|(_, point)| *point)
+    }
+}
+
 #[derive(PartialEq)]
 pub struct AnimationData {
-    pub frames: Vec<(Rect, usize)>, // position of frame and how many frames it'll take
-    pub looping: bool,
+    pub frames: Vec<Frame>,
+    pub playback: Playback,
+}
+
+impl AnimationData {
+    pub fn total_duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
 }
 
 pub struct Animation {
-    current_frame: (usize, usize),
+    frame: usize,
+    // seconds spent on the current frame so far
+    elapsed: f32,
+    // for ping-pong, whether we're currently going backwards
+    backwards: bool,
+    finished: bool,
+    // (frame, event) indices of events that fired but haven't been polled yet
+    events: VecDeque<(usize, usize)>,
+    // multiplies the dt passed to animate; 2.0 plays twice as fast
+    pub speed: f32,
     data: Rc<AnimationData>,
 }
 
 impl Animation {
     pub fn new(data: &Rc<AnimationData>) -> Self {
-        Self {
-            current_frame: (0, 0),
+        let mut anim = Self {
+            frame: 0,
+            elapsed: 0.0,
+            backwards: false,
+            finished: false,
+            events: VecDeque::new(),
+            speed: 1.0,
             data: Rc::clone(data),
-        }
+        };
+        anim.restart();
+        anim
     }
 
     pub fn set_animation(&mut self, data: &Rc<AnimationData>, force: bool) {
-        if force || *data != self.data {
-            self.current_frame = (0, 0);
+        if force || !Rc::ptr_eq(data, &self.data) {
             self.data = Rc::clone(data);
+            self.restart();
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = match self.data.playback {
+            Playback::Reverse => self.data.frames.len() - 1,
+            _ => 0,
+        };
+        self.elapsed = 0.0;
+        self.backwards = self.data.playback == Playback::Reverse;
+        self.finished = false;
+        self.events.clear();
+        self.enter_frame(self.frame);
+    }
+
+    fn enter_frame(&mut self, frame: usize) {
+        self.frame = frame;
+        for event in 0..self.data.frames[frame].events.len() {
+            if self.events.len() == MAX_PENDING_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back((frame, event));
         }
     }
 
-    pub fn animate(&mut self) {
-        let (frame_idx, frame_timer) = &mut self.current_frame;
-        *frame_timer += 1;
-        if *frame_timer == self.data.frames[*frame_idx].1 {
-            *frame_timer = 0;
-            if self.data.looping {
-                *frame_idx = (*frame_idx + 1) % self.data.frames.len();
-            } else {
-                *frame_idx = (*frame_idx + 1).min(self.data.frames.len() - 1);
+    // Advance by dt seconds (scaled by speed), firing the events of every frame passed through
+    pub fn animate(&mut self, dt: f32) {
+        // an animation whose frames take no time can't advance without spinning forever
+        if self.finished || self.data.total_duration() <= 0.0 {
+            return;
+        }
+        self.elapsed += dt * self.speed;
+        let last = self.data.frames.len() - 1;
+        while !self.finished && self.elapsed >= self.data.frames[self.frame].duration {
+            self.elapsed -= self.data.frames[self.frame].duration;
+            let next = match self.data.playback {
+                Playback::Once if self.frame == last => None,
+                Playback::Once => Some(self.frame + 1),
+                Playback::Reverse if self.frame == 0 => None,
+                Playback::Reverse => Some(self.frame - 1),
+                Playback::Loop => Some((self.frame + 1) % self.data.frames.len()),
+                Playback::PingPong if last == 0 => Some(0),
+                Playback::PingPong => {
+                    if (self.backwards && self.frame == 0)
+                        || (!self.backwards && self.frame == last)
+                    {
+                        self.backwards = !self.backwards;
+                    }
+                    if self.backwards {
+                        Some(self.frame - 1)
+                    } else {
+                        Some(self.frame + 1)
+                    }
+                }
+            };
+            match next {
+                Some(frame) => self.enter_frame(frame),
+                None => {
+                    self.finished = true;
+                    self.elapsed = self.data.frames[self.frame].duration;
+                }
             }
         }
     }
 
+    // Only Once and Reverse animations ever finish
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Takes the oldest event that has fired since the last poll
+    pub fn poll_event(&mut self) -> Option<&str> {
+        let (frame, event) = self.events.pop_front()?;
+        Some(&self.data.frames[frame].events[event])
+    }
+
+    pub fn current_frame_index(&self) -> usize {
+        self.frame
+    }
+
     pub fn get_current_frame(&self) -> Rect {
-        self.data.frames[self.current_frame.0].0
+        self.data.frames[self.frame].rect
+    }
+
+    pub fn get_current_anchor(&self, name: &str) -> Option<Vec2> {
+        self.data.frames[self.frame].anchor(name)
+    }
+
+    pub fn data(&self) -> &Rc<AnimationData> {
+        &self.data
     }
 }
@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+use crate::animation::{Animation, AnimationData};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Condition {
+    FloatAbove(String, f32),
+    FloatBelow(String, f32),
+    Bool(String, bool),
+    // set with set_trigger, and used up by the transition that fires on it
+    Trigger(String),
+    // the current state's animation has finished (only Once and Reverse animations do)
+    Finished,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Transition {
+    // None means the transition can fire from any state
+    pub from: Option<String>,
+    pub to: String,
+    // all of them have to hold
+    pub conditions: Vec<Condition>,
+    // seconds the current state has to have played before the transition can fire
+    pub exit_time: Option<f32>,
+}
+
+impl Transition {
+    pub fn new(from: Option<&str>, to: &str, conditions: Vec<Condition>) -> Self {
+        Self {
+            from: from.map(|s| s.to_string()),
+            to: to.to_string(),
+            conditions,
+            exit_time: None,
+        }
+    }
+
+    pub fn with_exit_time(mut self, seconds: f32) -> Self {
+        self.exit_time = Some(seconds);
+        self
+    }
+
+    fn uses_trigger(&self) -> bool {
+        self.conditions
+            .iter()
+            .any(|c| matches!(c, Condition::Trigger(_)))
+    }
+}
+
+// Picks which animation a sprite should be playing from parameters the game sets,
+// so game code says "flap" or "vel_y is -1.2" instead of swapping AnimationData itself.
+pub struct AnimationController {
+    states: BTreeMap<String, Rc<AnimationData>>,
+    // checked in the order they were added, first match wins
+    transitions: Vec<Transition>,
+    floats: BTreeMap<String, f32>,
+    bools: BTreeMap<String, bool>,
+    triggers: BTreeSet<String>,
+    current: String,
+    time_in_state: f32,
+}
+
+impl AnimationController {
+    pub fn new(initial: &str, data: &Rc<AnimationData>) -> Self {
+        let mut states = BTreeMap::new();
+        states.insert(initial.to_string(), Rc::clone(data));
+        Self {
+            states,
+            transitions: Vec::new(),
+            floats: BTreeMap::new(),
+            bools: BTreeMap::new(),
+            triggers: BTreeSet::new(),
+            current: initial.to_string(),
+            time_in_state: 0.0,
+        }
+    }
+
+    pub fn add_state(&mut self, name: &str, data: &Rc<AnimationData>) {
+        self.states.insert(name.to_string(), Rc::clone(data));
+    }
+
+    // Panics if either end of the transition isn't a state yet
+    pub fn add_transition(&mut self, transition: Transition) {
+        if let Some(from) = &transition.from {
+            assert!(self.states.contains_key(from), "no state named {}", from);
+        }
+        assert!(
+            self.states.contains_key(&transition.to),
+            "no state named {}",
+            transition.to
+        );
+        self.transitions.push(transition);
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.floats.insert(name.to_string(), value);
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.bools.insert(name.to_string(), value);
+    }
+
+    pub fn set_trigger(&mut self, name: &str) {
+        self.triggers.insert(name.to_string());
+    }
+
+    pub fn reset_trigger(&mut self, name: &str) {
+        self.triggers.remove(name);
+    }
+
+    pub fn get_float(&self, name: &str) -> Option<f32> {
+        self.floats.get(name).copied()
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.bools.get(name).copied()
+    }
+
+    pub fn is_triggered(&self, name: &str) -> bool {
+        self.triggers.contains(name)
+    }
+
+    pub fn current_state(&self) -> &str {
+        &self.current
+    }
+
+    pub fn time_in_state(&self) -> f32 {
+        self.time_in_state
+    }
+
+    // Jump straight to a state, e.g. when the game restarts
+    pub fn play(&mut self, state: &str, animation: &mut Animation) {
+        let data = self
+            .states
+            .get(state)
+            .unwrap_or_else(|| panic!("no state named {}", state));
+        animation.set_animation(data, true);
+        self.current = state.to_string();
+        self.time_in_state = 0.0;
+    }
+
+    fn holds(&self, condition: &Condition, animation: &Animation) -> bool {
+        match condition {
+            Condition::FloatAbove(name, v) => self.floats.get(name).is_some_and(|f| f > v),
+            Condition::FloatBelow(name, v) => self.floats.get(name).is_some_and(|f| f < v),
+            Condition::Bool(name, v) => self.bools.get(name) == Some(v),
+            Condition::Trigger(name) => self.triggers.contains(name),
+            Condition::Finished => animation.is_finished(),
+        }
+    }
+
+    fn can_fire(&self, transition: &Transition, animation: &Animation) -> bool {
+        if let Some(from) = &transition.from {
+            if *from != self.current {
+                return false;
+            }
+        }
+        // without a trigger, "any state" transitions into the current state would restart it every update
+        if transition.to == self.current && transition.from.is_none() && !transition.uses_trigger()
+        {
+            return false;
+        }
+        if let Some(exit_time) = transition.exit_time {
+            if self.time_in_state < exit_time {
+                return false;
+            }
+        }
+        transition
+            .conditions
+            .iter()
+            .all(|c| self.holds(c, animation))
+    }
+
+    // Take at most one transition, then advance the animation by dt.
+    // Returns the name of the state that was entered, if any.
+    pub fn update(&mut self, dt: f32, animation: &mut Animation) -> Option<&str> {
+        let fired = self
+            .transitions
+            .iter()
+            .position(|t| self.can_fire(t, animation));
+        if let Some(idx) = fired {
+            for condition in self.transitions[idx].conditions.iter() {
+                if let Condition::Trigger(name) = condition {
+                    self.triggers.remove(name);
+                }
+            }
+            let to = self.transitions[idx].to.clone();
+            self.play(&to, animation);
+        }
+        self.time_in_state += dt * animation.speed;
+        animation.animate(dt);
+        fired.map(move |_| self.current.as_str())
+    }
+}
@@ -0,0 +1,147 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::objects::{Color, Rect, Vec2};
+use crate::screen::{Screen, Transform};
+use crate::sprite::Sprite;
+use crate::text::TextInfo;
+use crate::texture::Texture;
+
+enum Command {
+    Blit(Rc<Texture>, Rect, Vec2, Transform),
+    Rect(Rect, Color),
+    RectLines(Rect, Color),
+    Line(Vec2, Vec2, Color),
+}
+
+struct Item {
+    layer: i32,
+    z: i32,
+    // bottom edge of whatever was queued, for y-sorting
+    y: f32,
+    // queue order, so equal keys keep the order they were queued in
+    seq: usize,
+    command: Command,
+}
+
+// Collects draws for a frame and then does them back to front: by layer, then z,
+// then (if y_sort is on) bottom edge so lower things overlap higher ones, then queue order.
+// Sprites and text get broken down into blits when they're queued, so everything that
+// makes up one sprite (children included) or one string stays together.
+// The queue keeps its capacity between flushes, so a warmed up batch doesn't allocate.
+pub struct SpriteBatch {
+    items: Vec<Item>,
+    pub y_sort: bool,
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            y_sort: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // How much can be queued before the queue has to grow
+    pub fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+
+    fn push(&mut self, layer: i32, z: i32, y: f32, command: Command) {
+        let seq = self.items.len();
+        self.items.push(Item {
+            layer,
+            z,
+            y,
+            seq,
+            command,
+        });
+    }
+
+    pub fn sprite(&mut self, s: &Sprite, layer: i32, z: i32) {
+        let y = s.position.y + s.animation.get_current_frame().h;
+        s.for_each_blit(|image, frame, position, transform| {
+            self.push(
+                layer,
+                z,
+                y,
+                Command::Blit(Rc::clone(image), frame, position, *transform),
+            )
+        });
+    }
+
+    pub fn rect(&mut self, r: Rect, col: Color, layer: i32, z: i32) {
+        self.push(layer, z, r.y + r.h, Command::Rect(r, col));
+    }
+
+    pub fn rect_lines(&mut self, r: Rect, col: Color, layer: i32, z: i32) {
+        self.push(layer, z, r.y + r.h, Command::RectLines(r, col));
+    }
+
+    pub fn line(&mut self, from: Vec2, to: Vec2, col: Color, layer: i32, z: i32) {
+        self.push(layer, z, from.y.max(to.y), Command::Line(from, to, col));
+    }
+
+    // Same as DrawTextExt::draw_text_at_pos, just queued
+    pub fn text(&mut self, string: &str, pos: Vec2, font: &TextInfo, layer: i32, z: i32) {
+        let y = pos.y + font.get_char_height();
+        let mut x = pos.x;
+        for ch in string.chars() {
+            if let Some((image, rect)) = font.glyph(ch) {
+                self.push(
+                    layer,
+                    z,
+                    y,
+                    Command::Blit(
+                        Rc::clone(image),
+                        rect,
+                        Vec2::new(x, pos.y),
+                        Transform::IDENTITY,
+                    ),
+                );
+                x += rect.w;
+            }
+        }
+    }
+
+    // Draw everything that's been queued and empty the queue
+    pub fn flush(&mut self, screen: &mut Screen) {
+        let y_sort = self.y_sort;
+        // seq makes every key unique, so the unstable sort (which doesn't allocate) is stable here
+        self.items.sort_unstable_by(|a, b| {
+            a.layer
+                .cmp(&b.layer)
+                .then(a.z.cmp(&b.z))
+                .then(if y_sort {
+                    a.y.total_cmp(&b.y)
+                } else {
+                    Ordering::Equal
+                })
+                .then(a.seq.cmp(&b.seq))
+        });
+        for item in self.items.drain(..) {
+            match item.command {
+                Command::Blit(image, frame, pos, transform) => {
+                    screen.bitblt_transformed(&image, frame, pos, &transform)
+                }
+                Command::Rect(r, col) => screen.rect(r, col),
+                Command::RectLines(r, col) => screen.rect_lines(r, col),
+                Command::Line(from, to, col) => screen.line(from, to, col),
+            }
+        }
+    }
+}
+
+impl Default for SpriteBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
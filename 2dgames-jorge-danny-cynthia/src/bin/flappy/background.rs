@@ -2,13 +2,13 @@ use std::time::{Duration, Instant};
 
 use engine2d::{
     animation::Animation,
+    batch::SpriteBatch,
     objects::{Rect, Vec2},
-    screen::Screen,
-    sprite::{DrawSpriteExt, Sprite},
+    sprite::Sprite,
 };
 use rand::prelude::*;
 
-use crate::{Resources, HEIGHT, WIDTH};
+use crate::{Resources, HEIGHT, LAYER_BACKGROUND, WIDTH};
 
 pub struct Background {
     buildings: Vec<Sprite>,
@@ -36,14 +36,14 @@ impl Background {
             self.buildings.push(Sprite::new(
                 &rsrc.textures[1],
                 Animation::new(which_building),
-                Vec2::new(x_pos as f32, 280.0 - which_building.frames[0].0.h),
+                Vec2::new(x_pos as f32, 280.0 - which_building.frames[0].rect.h),
             ));
-            x_pos += which_building.frames[0].0.w as u32 + thread_rng().gen_range(30..50);
+            x_pos += which_building.frames[0].rect.w as u32 + thread_rng().gen_range(30..50);
         }
 
         x_pos = thread_rng().gen_range(30..50);
         let anim = &rsrc.animation_data[6];
-        let anim_height = anim.frames[0].0.h;
+        let anim_height = anim.frames[0].rect.h;
         for _ in 0..3 {
             let mut y = thread_rng().gen_range(0..140 - 2 * anim_height as u32) as f32;
             if !self.clouds.is_empty() {
@@ -101,17 +101,25 @@ impl Background {
         }
     }
 
-    pub fn draw(&self, screen: &mut Screen) {
-        screen.rect(
+    pub fn draw(&self, batch: &mut SpriteBatch) {
+        batch.rect(
             Rect::new(0.0, 0.0, WIDTH as f32, 280.0),
             [130, 177, 255, 255],
+            LAYER_BACKGROUND,
+            0,
         );
-        screen.rect(
+        batch.rect(
             Rect::new(0.0, 280.0, WIDTH as f32, HEIGHT as f32 - 280.0),
             [76, 175, 80, 255],
+            LAYER_BACKGROUND,
+            0,
         );
-        for sprite in self.clouds.iter().chain(self.buildings.iter()) {
-            screen.draw_sprite(sprite);
+        // clouds go behind the buildings
+        for sprite in self.clouds.iter() {
+            batch.sprite(sprite, LAYER_BACKGROUND, 1);
+        }
+        for sprite in self.buildings.iter() {
+            batch.sprite(sprite, LAYER_BACKGROUND, 2);
         }
     }
 
@@ -120,14 +128,14 @@ impl Background {
         self.buildings.push(Sprite::new(
             &rsrc.textures[1],
             Animation::new(which_building),
-            Vec2::new(WIDTH as f32, 280.0 - which_building.frames[0].0.h),
+            Vec2::new(WIDTH as f32, 280.0 - which_building.frames[0].rect.h),
         ));
         self.last_building = None;
     }
 
     fn add_cloud(&mut self, rsrc: &Resources) {
         let anim = &rsrc.animation_data[6];
-        let anim_height = anim.frames[0].0.h;
+        let anim_height = anim.frames[0].rect.h;
         let mut y = thread_rng().gen_range(0..140 - 2 * anim_height as u32) as f32;
         if !self.clouds.is_empty() {
             if y >= self.clouds[self.clouds.len() - 1].position.y {
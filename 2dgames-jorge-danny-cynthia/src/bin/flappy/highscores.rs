@@ -0,0 +1,41 @@
+use engine2d::save::SaveData;
+use serde::{Deserialize, Serialize};
+
+// how many scores the table keeps
+pub const TABLE_SIZE: usize = 5;
+pub const SAVE_SLOT: &str = "highscores";
+
+// The best scores so far, highest first
+#[derive(Serialize, Deserialize, Default)]
+pub struct HighScores {
+    pub scores: Vec<u32>,
+}
+
+impl SaveData for HighScores {
+    const VERSION: u32 = 1;
+}
+
+impl HighScores {
+    // Puts the score in the table if it's good enough, and says where it went (0 is the top).
+    // Ties go below the scores that were there first.
+    pub fn add(&mut self, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let place = self
+            .scores
+            .iter()
+            .position(|&s| score > s)
+            .unwrap_or(self.scores.len());
+        if place >= TABLE_SIZE {
+            return None;
+        }
+        self.scores.insert(place, score);
+        self.scores.truncate(TABLE_SIZE);
+        Some(place)
+    }
+
+    pub fn best(&self) -> u32 {
+        self.scores.first().copied().unwrap_or(0)
+    }
+}
@@ -15,24 +15,37 @@ use winit_input_helper::WinitInputHelper;
 
 mod background;
 mod generation;
+mod highscores;
 
 use background::Background;
+use highscores::HighScores;
 
 use engine2d::{
-    animation::{Animation, AnimationData},
+    animation::{Animation, AnimationData, Frame, Playback},
+    animcontroller::{AnimationController, Condition, Transition},
+    batch::SpriteBatch,
     collision, input,
+    locale::{StringTable, Strings},
     objects::*,
-    screen::Screen,
-    sprite::{DrawSpriteExt, Sprite},
+    save::SaveDir,
+    screen::{Letterbox, Screen},
+    sprite::Sprite,
     text::{self, DrawTextExt},
     texture::Texture,
+    ui::{self, Ui, UiInput},
 };
 
 const DT: f64 = 1.0 / 60.0;
 const WIDTH: usize = 240;
 const HEIGHT: usize = 360;
-const DEPTH: usize = 4;
 const CHAR_SIZE: f32 = 16.0;
+const BIRD_SIZE: Vec2 = Vec2 { x: 20.0, y: 17.0 };
+// draw layers for the play screen, back to front
+pub const LAYER_BACKGROUND: i32 = 0;
+pub const LAYER_PLAYFIELD: i32 = 1;
+pub const LAYER_UI: i32 = 2;
+// anchor on the bird frames that the held item hangs from
+const BEAK: &str = "beak";
 
 #[derive(Debug)]
 enum Mode {
@@ -44,13 +57,16 @@ enum Mode {
 struct GameState {
     player: MovingRect,
     player_sprite: Sprite,
-    holding: Holding,
+    player_anim: AnimationController,
     obstacles: Vec<Rect>,
     obstacle_data: Vec<ObstacleData>,
     move_vel: f32,
     background: Background,
     last_flap_noise: Instant,
     score: u32,
+    high_scores: HighScores,
+    // where this round's score went in the table, if it made it
+    new_high_score: Option<usize>,
     time_between: u32,
     mode: Mode,
 }
@@ -59,6 +75,7 @@ pub struct Resources {
     pub animation_data: Vec<Rc<AnimationData>>,
     pub text_info: text::TextInfo,
     pub textures: Vec<Rc<Texture>>,
+    pub strings: Strings,
 }
 
 struct ObstacleData {
@@ -71,58 +88,35 @@ enum ActionID {
     Flap,
 }
 
-enum Holding {
-    Worm(Sprite),
-    Flower(Sprite),
-    Letter(Sprite),
-}
-
-impl Holding {
-    fn random(rsrc: &Resources) -> Self {
-        let mut rng = thread_rng();
-        match rng.gen_range(0..3) {
-            0 => Self::Flower(Sprite::new(
-                &Rc::clone(&rsrc.textures[0]),
-                Animation::new(&rsrc.animation_data[7]),
-                Vec2::new(45.0, HEIGHT as f32 / 2.0 - 10.0),
-            )),
-            1 => Self::Worm(Sprite::new(
-                &Rc::clone(&rsrc.textures[0]),
-                Animation::new(&rsrc.animation_data[8]),
-                Vec2::new(47.0, HEIGHT as f32 / 2.0 - 9.0),
-            )),
-            2 => Self::Letter(Sprite::new(
-                &Rc::clone(&rsrc.textures[0]),
-                Animation::new(&rsrc.animation_data[9]),
-                Vec2::new(46.0, HEIGHT as f32 / 2.0 - 9.0),
-            )),
-            _ => panic!("unreachable"),
-        }
-    }
-
-    fn get_sprite(&self) -> &Sprite {
-        match self {
-            Self::Flower(sprite) => sprite,
-            Self::Letter(sprite) => sprite,
-            Self::Worm(sprite) => sprite,
-        }
-    }
-
-    fn get_sprite_mut(&mut self) -> &mut Sprite {
-        match self {
-            Self::Flower(sprite) => sprite,
-            Self::Letter(sprite) => sprite,
-            Self::Worm(sprite) => sprite,
-        }
-    }
-
-    fn draw(&self, screen: &mut Screen) {
-        screen.draw_sprite(self.get_sprite());
-    }
+// What the pigeon has in its beak this round (a flower, a worm or a letter),
+// offset so it sits right at the bird's "beak" anchor
+fn random_held_item(rsrc: &Resources) -> Sprite {
+    let (anim, offset) = match thread_rng().gen_range(0..3) {
+        0 => (7, Vec2::new(0.0, 0.0)),
+        1 => (8, Vec2::new(2.0, 1.0)),
+        _ => (9, Vec2::new(1.0, 1.0)),
+    };
+    Sprite::new(
+        &rsrc.textures[0],
+        Animation::new(&rsrc.animation_data[anim]),
+        offset,
+    )
 }
 
 fn main() {
     let rsrc = Resources::new();
+    let saves = SaveDir::for_game("flappy");
+    let high_scores = match saves
+        .as_ref()
+        .map(|saves| saves.read(highscores::SAVE_SLOT))
+    {
+        Some(Ok(Some(high_scores))) => high_scores,
+        Some(Err(err)) => {
+            eprintln!("warning: couldn't load high scores: {}", err);
+            HighScores::default()
+        }
+        _ => HighScores::default(),
+    };
     let mut state = GameState {
         player: MovingRect::new(
             30.0,
@@ -136,16 +130,33 @@ fn main() {
             Animation::new(&rsrc.animation_data[0]),
             Vec2::new(30.0, HEIGHT as f32 / 2.0 - 10.0),
         ),
-        holding: Holding::random(&rsrc),
+        player_anim: {
+            let mut anim = AnimationController::new("glide", &rsrc.animation_data[0]);
+            anim.add_state("flap", &rsrc.animation_data[1]);
+            anim.add_transition(Transition::new(
+                None,
+                "flap",
+                vec![Condition::Trigger("flap".to_string())],
+            ));
+            anim.add_transition(Transition::new(
+                Some("flap"),
+                "glide",
+                vec![Condition::FloatBelow("vel_y".to_string(), 0.0)],
+            ));
+            anim
+        },
         background: Background::new(&rsrc),
         obstacles: Vec::new(),
         obstacle_data: Vec::new(),
         score: 0,
+        high_scores,
+        new_high_score: None,
         move_vel: 1.0,
         time_between: 3000,
         mode: Mode::Title,
         last_flap_noise: Instant::now(),
     };
+    state.player_sprite.attach(BEAK, 1, random_held_item(&rsrc));
     let mut rng = thread_rng();
 
     let (_stream, stream_handle) = rodio::OutputStream::try_default().unwrap();
@@ -170,13 +181,19 @@ fn main() {
     let mut input = input::Input::new();
     input.add_key_to_map(ActionID::Flap, VirtualKeyCode::Space);
 
+    // the title and game over menus
+    let mut ui = Ui::default();
+    let mut ui_keys = ui::default_keys(VirtualKeyCode::Return);
+    let mut ui_input = UiInput::default();
+    let mut menu_choice = None;
+
     let window = {
         let size = LogicalSize::new(WIDTH as f64 * 2.0, HEIGHT as f64 * 2.0);
         WindowBuilder::new()
-            .with_title("flappy bird")
+            .with_title(rsrc.strings.text("flappy.window_title"))
             .with_inner_size(size)
             .with_min_inner_size(size)
-            .with_resizable(false)
+            .with_resizable(true)
             .build(&event_loop)
             .unwrap()
     };
@@ -186,6 +203,7 @@ fn main() {
         Pixels::new(WIDTH as u32, HEIGHT as u32, surface_texture).unwrap()
     };
 
+    let mut batch = SpriteBatch::new();
     let mut last_added_rect = Instant::now();
     let mut available_time = 0.0;
     let mut since = Instant::now();
@@ -195,23 +213,46 @@ fn main() {
             Mode::Title => {
                 // Draw the current frame
                 if let Event::RedrawRequested(_) = event {
-                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH);
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
                     screen.clear([135, 206, 250, 150]);
 
                     screen.draw_text_at_pos(
-                        format!("score: {}", state.score).as_str(),
+                        &rsrc
+                            .strings
+                            .format("flappy.score", &[("score", &state.score)]),
                         Vec2::new(0.0, 0.0),
                         &rsrc.text_info,
                     );
+                    if state.high_scores.best() > 0 {
+                        screen.draw_text_at_pos(
+                            &rsrc
+                                .strings
+                                .format("flappy.best", &[("score", &state.high_scores.best())]),
+                            Vec2::new(0.0, CHAR_SIZE),
+                            &rsrc.text_info,
+                        );
+                    }
                     screen.draw_text_at_pos(
-                        "flappy pigeon",
+                        &rsrc.strings.text("flappy.title"),
                         Vec2::new(20.0, 60.0),
                         &rsrc.text_info,
                     );
-                    screen.draw_text_at_pos("press space", Vec2::new(40.0, 190.0), &rsrc.text_info);
-                    screen.draw_text_at_pos("to flap", Vec2::new(73.0, 210.0), &rsrc.text_info);
-                    screen.draw_text_at_pos("press enter", Vec2::new(40.0, 240.0), &rsrc.text_info);
-                    screen.draw_text_at_pos("to start", Vec2::new(65.0, 260.0), &rsrc.text_info);
+                    let mut menu = ui.begin(
+                        &mut screen,
+                        &rsrc.text_info,
+                        &ui_input,
+                        Rect::new(10.0, 190.0, 220.0, 120.0),
+                    );
+                    menu.label(&rsrc.strings.text("flappy.help"));
+                    menu.space(20.0);
+                    menu_choice = menu
+                        .menu(&[
+                            rsrc.strings.text("flappy.start"),
+                            rsrc.strings.text("flappy.quit"),
+                        ])
+                        .or(menu_choice);
+                    drop(menu);
+                    ui_input = UiInput::default();
 
                     if pixels.render().is_err() {
                         *control_flow = ControlFlow::Exit;
@@ -227,7 +268,14 @@ fn main() {
                         *control_flow = ControlFlow::Exit;
                         return;
                     }
-                    if input_events.key_pressed(VirtualKeyCode::Return) {
+                    ui_keys.update(&input_events);
+                    ui_input = gather_ui_input(&ui_keys, &input_events, &window);
+                    let choice = menu_choice.take();
+                    if choice == Some(1) {
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                    if choice == Some(0) {
                         state.mode = Mode::Play;
                         state.last_flap_noise = Instant::now();
                         state.player.x = 30.0;
@@ -247,28 +295,28 @@ fn main() {
             Mode::Play => {
                 // Draw the current frame
                 if let Event::RedrawRequested(_) = event {
-                    state.player_sprite.animation.animate();
-                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH);
-                    state.background.draw(&mut screen);
-
-                    screen.draw_sprite(&state.player_sprite);
-                    state.holding.draw(&mut screen);
-
-                    // draw state.obstacles
+                    state.background.draw(&mut batch);
+                    batch.sprite(&state.player_sprite, LAYER_PLAYFIELD, 0);
                     for (obstacle, data) in state.obstacles.iter().zip(state.obstacle_data.iter()) {
                         if data.filled {
-                            screen.rect(*obstacle, [255, 0, 0, 255]);
+                            batch.rect(*obstacle, [255, 0, 0, 255], LAYER_PLAYFIELD, 1);
                         } else {
-                            screen.rect_lines(*obstacle, [255, 0, 0, 255]);
+                            batch.rect_lines(*obstacle, [255, 0, 0, 255], LAYER_PLAYFIELD, 1);
                         }
                     }
-
-                    screen.draw_text_at_pos(
-                        format!("score: {}", state.score).as_str(),
+                    batch.text(
+                        &rsrc
+                            .strings
+                            .format("flappy.score", &[("score", &state.score)]),
                         Vec2::new(0.0, 0.0),
                         &rsrc.text_info,
+                        LAYER_UI,
+                        0,
                     );
 
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
+                    batch.flush(&mut screen);
+
                     if pixels.render().is_err() {
                         *control_flow = ControlFlow::Exit;
                         return;
@@ -284,6 +332,8 @@ fn main() {
                         *control_flow = ControlFlow::Exit;
                         return;
                     }
+                    // keep the menu keys current so one held from the title isn't stale at game over
+                    ui_keys.update(&input_events);
 
                     // Resize the window
                     if let Some(size) = input_events.window_resized() {
@@ -304,26 +354,24 @@ fn main() {
                     input.update(&input_events);
                     if input.is_pressed(ActionID::Flap) {
                         state.player.vel.y = 2.0;
-                        state
-                            .player_sprite
-                            .set_animation(&rsrc.animation_data[1], true);
+                        state.player_anim.set_trigger("flap");
                     }
 
                     // update velocity for bird
                     state.player.vel.y -= 0.04;
-                    if state.player.vel.y < 0.0 {
-                        state
-                            .player_sprite
-                            .set_animation(&rsrc.animation_data[0], true);
-                    }
+                    state.player_anim.set_float("vel_y", state.player.vel.y);
+                    state
+                        .player_anim
+                        .update(DT as f32, &mut state.player_sprite.animation);
 
                     // update position
-                    state.player.x -= state.player.vel.x;
-                    state.player.y -= state.player.vel.y;
-                    state.player_sprite.position.x -= state.player.vel.x;
-                    state.player_sprite.position.y -= state.player.vel.y;
-                    state.holding.get_sprite_mut().position.x -= state.player.vel.x;
-                    state.holding.get_sprite_mut().position.y -= state.player.vel.y;
+                    state.player.translate(-state.player.vel);
+                    state.player_sprite.position -= state.player.vel;
+
+                    // tilt nose down while diving, up while climbing, around the middle of the bird
+                    let bird = &mut state.player_sprite;
+                    bird.transform.rotation = (-state.player.vel.y * 0.15).clamp(-0.4, 0.8);
+                    bird.transform.pivot = Vec2::new(BIRD_SIZE.x / 2.0, BIRD_SIZE.y / 2.0);
 
                     for obstacle in state.obstacles.iter_mut() {
                         obstacle.x -= state.move_vel;
@@ -335,6 +383,16 @@ fn main() {
                         if let (ContactID::Player, ContactID::Obstacle) = contact.get_ids() {
                             // TODO: have a function that resets the game state??
                             play_coo(&stream_handle);
+                            if let Mode::Play = state.mode {
+                                state.new_high_score = state.high_scores.add(state.score);
+                                if let (Some(_), Some(saves)) = (state.new_high_score, &saves) {
+                                    if let Err(err) =
+                                        saves.write(highscores::SAVE_SLOT, &state.high_scores)
+                                    {
+                                        eprintln!("warning: couldn't save high scores: {}", err);
+                                    }
+                                }
+                            }
                             state.mode = Mode::EndGame;
                         }
                     }
@@ -403,24 +461,59 @@ fn main() {
             }
             Mode::EndGame => {
                 if let Event::RedrawRequested(_) = event {
-                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH);
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
                     screen.clear([255, 150, 0, 255]);
 
                     screen.draw_text_at_pos(
-                        format!("score: {}", state.score).as_str(),
+                        &rsrc
+                            .strings
+                            .format("flappy.score", &[("score", &state.score)]),
                         Vec2::new(0.0, 0.0),
                         &rsrc.text_info,
                     );
 
-                    screen.draw_text_at_pos("game over!!!", Vec2::new(20.0, 60.0), &rsrc.text_info);
-
-                    screen.draw_text_at_pos("press enter", Vec2::new(40.0, 240.0), &rsrc.text_info);
                     screen.draw_text_at_pos(
-                        "to try again",
-                        Vec2::new(30.0, 260.0),
+                        &rsrc.strings.text("flappy.game_over"),
+                        Vec2::new(20.0, 60.0),
                         &rsrc.text_info,
                     );
 
+                    if !state.high_scores.scores.is_empty() {
+                        screen.draw_text_at_pos(
+                            &rsrc.strings.text("flappy.high_scores"),
+                            Vec2::new(20.0, 100.0),
+                            &rsrc.text_info,
+                        );
+                    }
+                    for (i, score) in state.high_scores.scores.iter().enumerate() {
+                        let id = if state.new_high_score == Some(i) {
+                            "flappy.new_high_score"
+                        } else {
+                            "flappy.high_score"
+                        };
+                        screen.draw_text_at_pos(
+                            &rsrc
+                                .strings
+                                .format(id, &[("place", &(i + 1)), ("score", score)]),
+                            Vec2::new(40.0, 120.0 + CHAR_SIZE * i as f32),
+                            &rsrc.text_info,
+                        );
+                    }
+
+                    menu_choice = ui
+                        .begin(
+                            &mut screen,
+                            &rsrc.text_info,
+                            &ui_input,
+                            Rect::new(10.0, 230.0, 220.0, 80.0),
+                        )
+                        .menu(&[
+                            rsrc.strings.text("flappy.try_again"),
+                            rsrc.strings.text("flappy.quit"),
+                        ])
+                        .or(menu_choice);
+                    ui_input = UiInput::default();
+
                     if pixels.render().is_err() {
                         *control_flow = ControlFlow::Exit;
                         return;
@@ -433,12 +526,23 @@ fn main() {
                         *control_flow = ControlFlow::Exit;
                         return;
                     }
-                    if input_events.key_pressed(VirtualKeyCode::Return) {
+                    ui_keys.update(&input_events);
+                    ui_input = gather_ui_input(&ui_keys, &input_events, &window);
+                    let choice = menu_choice.take();
+                    if choice == Some(1) {
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                    if choice == Some(0) {
                         state.mode = Mode::Play;
                         state.player.x = 30.0;
                         state.player.y = HEIGHT as f32 / 2.0 - 13.0;
                         state.player_sprite.position.x = 30.0;
                         state.player_sprite.position.y = HEIGHT as f32 / 2.0 - 10.0;
+                        state.player_sprite.transform.rotation = 0.0;
+                        state
+                            .player_anim
+                            .play("glide", &mut state.player_sprite.animation);
                         state.player.vel = Vec2::new(0.0, 0.0);
                         state.background.clear(&rsrc);
                         state.obstacles.clear();
@@ -447,7 +551,8 @@ fn main() {
                         state.time_between = 3000;
                         state.move_vel = 1.0;
                         state.score = 0;
-                        state.holding = Holding::random(&rsrc);
+                        state.player_sprite.children.clear();
+                        state.player_sprite.attach(BEAK, 1, random_held_item(&rsrc));
                         last_added_rect = Instant::now();
                         since = Instant::now();
                         //return;
@@ -463,6 +568,17 @@ fn main() {
     });
 }
 
+// Menu input for this frame, with the pointer mapped onto the framebuffer
+fn gather_ui_input(
+    keys: &input::Input<ui::UiAction>,
+    events: &WinitInputHelper,
+    window: &winit::window::Window,
+) -> UiInput {
+    let size = window.inner_size();
+    let letterbox = Letterbox::new((WIDTH, HEIGHT), (size.width, size.height));
+    UiInput::gather(keys, events, &letterbox)
+}
+
 fn play_coo(stream_handle: &OutputStreamHandle) {
     let file = File::open("content/birdcoo.mp3").unwrap();
     let coo = rodio::Decoder::new(BufReader::new(file))
@@ -485,56 +601,59 @@ impl Resources {
             animation_data: vec![
                 // bird glide (pigeon.png)
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(0.0, 0.0, 20.0, 17.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(0.0, 0.0, 20.0, 17.0), 1.0 / 60.0)
+                        .with_anchor(BEAK, Vec2::new(15.0, 0.0))],
+                    playback: Playback::Once,
                 }),
                 // bird flap
                 Rc::new(AnimationData {
                     frames: vec![
-                        (Rect::new(20.0, 0.0, 20.0, 17.0), 13),
-                        (Rect::new(40.0, 0.0, 20.0, 17.0), 1),
+                        Frame::new(Rect::new(20.0, 0.0, 20.0, 17.0), 13.0 / 60.0)
+                            .with_anchor(BEAK, Vec2::new(15.0, 0.0)),
+                        Frame::new(Rect::new(40.0, 0.0, 20.0, 17.0), 1.0 / 60.0)
+                            .with_anchor(BEAK, Vec2::new(15.0, 0.0)),
                     ],
-                    looping: false,
+                    playback: Playback::Once,
                 }),
                 // building (buildings.png)
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(0.0, 56.0, 23.0, 82.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(0.0, 56.0, 23.0, 82.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
                 // building
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(23.0, 21.0, 22.0, 117.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(23.0, 21.0, 22.0, 117.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
                 // building
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(45.0, 79.0, 63.0, 58.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(45.0, 79.0, 63.0, 58.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
                 // building
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(108.0, 0.0, 29.0, 138.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(108.0, 0.0, 29.0, 138.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
                 // cloud
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(45.0, 0.0, 42.0, 29.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(45.0, 0.0, 42.0, 29.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
                 // flower (pigeon.png)
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(0.0, 17.0, 8.0, 7.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(0.0, 17.0, 8.0, 7.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
                 // worm
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(8.0, 17.0, 6.0, 5.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(8.0, 17.0, 6.0, 5.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
                 // letter
                 Rc::new(AnimationData {
-                    frames: vec![(Rect::new(14.0, 17.0, 9.0, 8.0), 1)],
-                    looping: false,
+                    frames: vec![Frame::new(Rect::new(14.0, 17.0, 9.0, 8.0), 1.0 / 60.0)],
+                    playback: Playback::Once,
                 }),
             ],
             textures: vec![
@@ -587,6 +706,10 @@ impl Resources {
                 ];
                 text::TextInfo::new(&image, &info)
             },
+            strings: Strings::for_player(Path::new("content/locale"), "en").unwrap_or_else(|err| {
+                eprintln!("warning: can't load strings from content/locale: {}", err);
+                Strings::new("en", StringTable::new())
+            }),
         }
     }
 }
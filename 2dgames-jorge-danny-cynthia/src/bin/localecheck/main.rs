@@ -0,0 +1,160 @@
+// Checks the string tables in content/locale against what uses them: the message_id and
+// response_id in stories, and the IDs the games' code asks for.
+//
+//     cargo run --bin localecheck -- [--deny-warnings] [--locales DIR] [--default LOCALE]
+//         [--source DIR] [STORY...]
+//
+// The code's IDs are the string literals passed to text, format and get on `strings` in
+// the .rs files under the source dir (see locale::requested_ids), and every table is
+// checked for those. IDs passed some other way, e.g. through a constant, can't be told
+// apart from other strings, so a table's ID that shows up in quotes anywhere in the source
+// isn't reported as unused either. Exits with 0 if every locale is fine, 1 if the default
+// locale is missing strings (or anything's wrong, with --deny-warnings), and 2 if the
+// tables couldn't be checked at all.
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use engine2d::dialogue::Story;
+use engine2d::locale::{self, StringTable};
+
+struct Options {
+    locales: PathBuf,
+    default: String,
+    source: PathBuf,
+    stories: Vec<PathBuf>,
+    deny_warnings: bool,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: localecheck [--deny-warnings] [--locales DIR] [--default LOCALE] \
+         [--source DIR] [STORY...]\n\
+         the locales default to content/locale, the default locale to en, the source to src \
+         and the story to src/bin/nemo/script.json"
+    );
+    process::exit(2);
+}
+
+fn options() -> Options {
+    let mut options = Options {
+        locales: PathBuf::from("content/locale"),
+        default: "en".to_string(),
+        source: PathBuf::from("src"),
+        stories: vec![],
+        deny_warnings: false,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deny-warnings" => options.deny_warnings = true,
+            "--locales" => options.locales = args.next().unwrap_or_else(|| usage()).into(),
+            "--default" => options.default = args.next().unwrap_or_else(|| usage()),
+            "--source" => options.source = args.next().unwrap_or_else(|| usage()).into(),
+            "-h" | "--help" => usage(),
+            _ if arg.starts_with('-') => usage(),
+            _ => options.stories.push(arg.into()),
+        }
+    }
+    if options.stories.is_empty() {
+        options
+            .stories
+            .push(PathBuf::from("src/bin/nemo/script.json"));
+    }
+    options
+}
+
+// Every .rs file under dir, read in
+fn sources(dir: &Path) -> Vec<String> {
+    let mut found = vec![];
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            found.extend(sources(&path));
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            found.extend(fs::read_to_string(&path).ok());
+        }
+    }
+    found
+}
+
+fn main() {
+    let options = options();
+
+    let names = locale::locales(&options.locales);
+    if !names.contains(&options.default) {
+        eprintln!(
+            "error: {} has no {}.json",
+            options.locales.display(),
+            options.default
+        );
+        process::exit(2);
+    }
+    let tables: Vec<(String, StringTable)> = names
+        .into_iter()
+        .map(|name| {
+            let path = options.locales.join(format!("{}.json", name));
+            match locale::read_table(&path) {
+                Ok(table) => (name, table),
+                Err(err) => {
+                    eprintln!("error: {}: {}", path.display(), err);
+                    process::exit(2);
+                }
+            }
+        })
+        .collect();
+
+    let mut used = BTreeSet::new();
+    for path in options.stories.iter() {
+        let story = Story::load(path).unwrap_or_else(|err| {
+            eprintln!("error: {}: {}", path.display(), err);
+            process::exit(2);
+        });
+        used.extend(story.string_ids().into_iter().map(str::to_string));
+    }
+    let sources = sources(&options.source);
+    for source in sources.iter() {
+        used.extend(locale::requested_ids(source));
+    }
+    for (_, table) in tables.iter() {
+        for id in table.keys() {
+            let quoted = format!("{:?}", id);
+            if sources.iter().any(|source| source.contains(&quoted)) {
+                used.insert(id.clone());
+            }
+        }
+    }
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    for (name, table) in tables.iter() {
+        let coverage = locale::check(table, &used);
+        // the default locale has nothing to fall back on, so the ID itself gets shown
+        let level = if *name == options.default {
+            errors += coverage.missing.len();
+            "error"
+        } else {
+            warnings += coverage.missing.len();
+            "warning"
+        };
+        for id in coverage.missing.iter() {
+            println!("{}: {} is missing {:?}", level, name, id);
+        }
+        for id in coverage.unused.iter() {
+            println!("warning: {} has {:?}, which nothing uses", name, id);
+        }
+        warnings += coverage.unused.len();
+        println!(
+            "{}: {} strings, {} missing, {} unused",
+            name,
+            table.len(),
+            coverage.missing.len(),
+            coverage.unused.len()
+        );
+    }
+    if errors > 0 || (options.deny_warnings && warnings > 0) {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,98 @@
+// The story's music and sound effects. Both are files in content/, named like
+// "the-fish-who-dreamt-of-a-distant-planet.mp3".
+use std::fs::File;
+use std::io::BufReader;
+
+use rodio::{Decoder, OutputStreamHandle, Sink, Source};
+
+// what plays until the story asks for something else
+pub const DEFAULT_MUSIC: &str = "the-fish-who-dreamt-of-a-distant-planet.mp3";
+// how long one track takes to fade into the next, in seconds
+const CROSSFADE_TIME: f32 = 1.5;
+
+fn decode(sound: &str) -> Option<Decoder<BufReader<File>>> {
+    let path = format!("content/{}", sound);
+    let decoded = File::open(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| Decoder::new(BufReader::new(file)).map_err(|err| err.to_string()));
+    match decoded {
+        Ok(decoder) => Some(decoder),
+        Err(err) => {
+            eprintln!("warning: can't play {}: {}", path, err);
+            None
+        }
+    }
+}
+
+pub struct Jukebox {
+    handle: OutputStreamHandle,
+    // the track that's on, "" for none
+    track: String,
+    playing: Option<Sink>,
+    // the track before, on its way out
+    fading: Option<Sink>,
+    // seconds into the crossfade
+    elapsed: f32,
+}
+
+impl Jukebox {
+    pub fn new(handle: OutputStreamHandle) -> Self {
+        Self {
+            handle,
+            track: String::new(),
+            playing: None,
+            fading: None,
+            elapsed: CROSSFADE_TIME,
+        }
+    }
+
+    // Crossfade to track, looping it, unless it's already on. "" fades out to silence.
+    pub fn play_music(&mut self, track: &str) {
+        if track == self.track {
+            return;
+        }
+        self.track = track.to_string();
+        if let Some(old) = self.fading.take() {
+            old.stop();
+        }
+        self.fading = self.playing.take();
+        // for silence there's nothing to decode, the old track just fades out
+        if !track.is_empty() {
+            self.playing = decode(track).and_then(|decoder| {
+                let sink = Sink::try_new(&self.handle).ok()?;
+                sink.set_volume(0.0);
+                sink.append(decoder.repeat_infinite());
+                Some(sink)
+            });
+        }
+        self.elapsed = 0.0;
+        self.update(0.0);
+    }
+
+    // Play sound once, over the music
+    pub fn play_sfx(&self, sound: &str) {
+        if let Some(decoder) = decode(sound) {
+            let _ = self.handle.play_raw(decoder.convert_samples());
+        }
+    }
+
+    // Moves the crossfade along; call every frame
+    pub fn update(&mut self, dt: f32) {
+        if self.elapsed >= CROSSFADE_TIME {
+            return;
+        }
+        self.elapsed = (self.elapsed + dt).min(CROSSFADE_TIME);
+        let t = self.elapsed / CROSSFADE_TIME;
+        if let Some(sink) = &self.playing {
+            sink.set_volume(t);
+        }
+        if let Some(sink) = &self.fading {
+            sink.set_volume(1.0 - t);
+        }
+        if t >= 1.0 {
+            if let Some(old) = self.fading.take() {
+                old.stop();
+            }
+        }
+    }
+}
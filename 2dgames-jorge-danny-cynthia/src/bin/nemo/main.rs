@@ -1,12 +1,12 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::rc::Rc;
-
+use std::time::Instant;
 
 use pixels::{Pixels, SurfaceTexture};
-#[allow(unused)]
-use rodio::Source;
-use std::fs::File;
-use std::io::BufReader;
+use serde::{Deserialize, Serialize};
 use winit::dpi::LogicalSize;
 use winit::event::{Event, VirtualKeyCode};
 use winit::event_loop::{ControlFlow, EventLoop};
@@ -14,64 +14,330 @@ use winit::window::WindowBuilder;
 use winit_input_helper::WinitInputHelper; //, PlayError};
 
 use engine2d::{
-    animation::{Animation, AnimationData},
+    dialogue::{self, Next, Problem, Runner, Story, Value},
+    locale::{StringTable, Strings},
     objects::*,
-    screen::Screen,
-    sprite::{DrawSpriteExt, Sprite},
+    panel::{DrawPanelExt, Insets, Panel},
+    save::{SaveData, SaveDir},
+    screen::{Letterbox, Screen},
     text::*,
     texture::Texture,
+    ui::{self, Theme, Ui, UiInput},
+    watch::FileWatcher,
 };
 
-mod storyparser;
-
 const WIDTH: usize = 1280;
 const HEIGHT: usize = 720;
-const DEPTH: usize = 4;
 pub const CHAR_SIZE: f32 = 16.0;
-const BOX_COLOR: Color = [255, 255, 255, 255];
 const BOX_X: f32 = WIDTH as f32 / 10.0;
 const BOX_Y: f32 = 6.0 * HEIGHT as f32 / 11.0;
 const BOX_WIDTH: f32 = 8.0 * WIDTH as f32 / 10.0;
 const BOX_HEIGHT: f32 = 4.0 * HEIGHT as f32 / 10.0;
+// how far in the border of content/panel.png goes
+const BOX_BORDER: f32 = 8.0;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 enum Mode {
     Title,
     Read,
     Respond,
+    // looking back over what's been said, on top of Read or Respond
+    Backlog,
     EndGame,
 }
 
-use storyparser::*;
 struct GameState {
-    scene_map: HashMap<String, Scene>,
-    current_scene: Scene,
+    story: Runner,
     box_read: bool,
     message_index: usize,
     box_text_index: usize,
-    response_index: usize,
+    // picks the response
+    ui: Ui,
+    // input for the response menu, waiting for the next redraw
+    ui_input: UiInput,
     text_info: TextInfo,
+    // for the game's own text, and the story's message_id and response_id
+    strings: Rc<Strings>,
+    // what's drawn behind the text box
+    stage: Stage,
     mode: Mode,
+    // None if there's nowhere to save
+    saves: Option<SaveDir>,
+    // which history entry the backlog has picked out
+    backlog_selected: usize,
+    // the mode to go back to when the backlog closes
+    backlog_from: Mode,
+}
+
+// Where the player is in the story, kept in the "story" save slot
+#[derive(Serialize, Deserialize)]
+struct Progress {
+    scene: String,
+    box_text_index: usize,
+    variables: BTreeMap<String, Value>,
+    // older saves don't have it, and get whatever the scene plays
+    #[serde(default)]
+    music: Option<String>,
 }
 
+impl SaveData for Progress {
+    const VERSION: u32 = 1;
+}
+
+const SAVE_SLOT: &str = "story";
+
 impl GameState {
     pub fn reset_read_info(&mut self) {
         self.message_index = 0;
         self.box_text_index = 0;
-        self.response_index = 0;
+        self.ui.set_focus(0);
         self.box_read = false;
     }
 
     pub fn reset_game(&mut self) {
         self.reset_read_info();
-        self.current_scene = self.scene_map.get("intro").unwrap().clone();
+        if let Err(err) = self.story.restart("intro") {
+            eprintln!("error: can't start over: {}", err);
+        }
+        self.stage = Stage::new(self.story.scene());
         self.mode = Mode::Title;
     }
+
+    // Returns false if there's nothing to look back on yet
+    pub fn open_backlog(&mut self) -> bool {
+        if self.story.history().is_empty() {
+            return false;
+        }
+        self.backlog_selected = self.story.history().len() - 1;
+        self.backlog_from = self.mode;
+        self.mode = Mode::Backlog;
+        true
+    }
+
+    pub fn has_progress(&self) -> bool {
+        self.saves
+            .as_ref()
+            .is_some_and(|saves| saves.path(SAVE_SLOT).is_file())
+    }
+
+    // Endings aren't saved, so continuing never lands on one; the save from the scene
+    // before stays until the end screen clears it
+    pub fn save_progress(&self) {
+        if self.story.is_end() {
+            return;
+        }
+        if let Some(saves) = &self.saves {
+            let progress = Progress {
+                scene: self.story.current().to_string(),
+                box_text_index: self.box_text_index,
+                variables: self.story.variables().clone(),
+                music: self.story.music().map(str::to_string),
+            };
+            if let Err(err) = saves.write(SAVE_SLOT, &progress) {
+                eprintln!("warning: couldn't save: {}", err);
+            }
+        }
+    }
+
+    // Once the story's over there's nothing to continue
+    pub fn clear_progress(&self) {
+        if let Some(saves) = &self.saves {
+            if let Err(err) = saves.delete(SAVE_SLOT) {
+                eprintln!("warning: couldn't clear the save: {}", err);
+            }
+        }
+    }
+
+    // Picks up where the save left off. False if there's no save or it doesn't fit the story.
+    pub fn load_progress(&mut self) -> bool {
+        let progress = match self
+            .saves
+            .as_ref()
+            .map(|saves| saves.read::<Progress>(SAVE_SLOT))
+        {
+            Some(Ok(Some(progress))) => progress,
+            Some(Err(err)) => {
+                eprintln!("warning: couldn't load the save: {}", err);
+                return false;
+            }
+            _ => return false,
+        };
+        if let Err(err) = self.story.restore(&progress.scene, progress.variables) {
+            eprintln!("warning: couldn't load the save: {}", err);
+            return false;
+        }
+        let music = progress.music.or_else(|| self.story.scene().music.clone());
+        self.story.set_music(music);
+        self.reset_read_info();
+        self.stage = Stage::new(self.story.scene());
+        // the story may have been edited since
+        if self
+            .story
+            .message()
+            .is_char_boundary(progress.box_text_index)
+        {
+            self.box_text_index = progress.box_text_index;
+            self.message_index = progress.box_text_index;
+        }
+        true
+    }
 }
 
+mod audio;
+mod stage;
 mod textinfo;
+use audio::{Jukebox, DEFAULT_MUSIC};
+use stage::{Art, Stage};
+
+struct Options {
+    story: PathBuf,
+    // reload the story whenever the file changes
+    dev: bool,
+    // None for the player's own, see Strings::for_player
+    locale: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!("usage: nemo [--dev] [--locale LOCALE] [STORY]");
+    process::exit(2);
+}
+
+fn options() -> Options {
+    let mut options = Options {
+        story: PathBuf::from("src/bin/nemo/script.json"),
+        dev: false,
+        locale: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dev" => options.dev = true,
+            "--locale" => options.locale = Some(args.next().unwrap_or_else(|| usage())),
+            _ if arg.starts_with('-') => usage(),
+            _ => options.story = arg.into(),
+        }
+    }
+    options
+}
+
+// Reports what validate finds in a reloaded story; false if any of it is bad enough to
+// keep playing the old one instead
+fn usable(story: &Story) -> bool {
+    let problems = dialogue::validate(story, "intro");
+    for problem in problems.iter() {
+        let level = if problem.is_error() {
+            "error"
+        } else {
+            "warning"
+        };
+        eprintln!("{}: {}", level, problem);
+    }
+    !problems.iter().any(Problem::is_error)
+}
+
+// Strings for locale, falling back on English for anything it doesn't have
+fn load_strings(locale: Option<&str>) -> Strings {
+    let dir = Path::new("content/locale");
+    let strings = match locale {
+        Some(locale) => Strings::load(dir, locale, "en"),
+        None => Strings::for_player(dir, "en"),
+    };
+    let warn = |err| {
+        eprintln!(
+            "warning: can't load strings from {}: {}",
+            dir.display(),
+            err
+        )
+    };
+    strings
+        .or_else(|err| {
+            warn(err);
+            Strings::load(dir, "en", "en")
+        })
+        .unwrap_or_else(|err| {
+            warn(err);
+            Strings::new("en", StringTable::new())
+        })
+}
+
+// The history, newest at the bottom, scrolled so the selected entry shows
+fn draw_backlog(screen: &mut Screen, state: &GameState, panel: &Panel) {
+    let font = &state.text_info;
+    let backlog_box = Rect::new(
+        BOX_X,
+        HEIGHT as f32 / 20.0,
+        BOX_WIDTH,
+        18.0 * HEIGHT as f32 / 20.0,
+    );
+    screen.draw_panel(panel, backlog_box);
+    let inner = panel.content_rect(backlog_box);
+    let text = Rect::new(
+        inner.x + BOX_WIDTH / 64.0,
+        inner.y + CHAR_SIZE,
+        inner.w - 2.0 * BOX_WIDTH / 64.0,
+        inner.h - 2.0 * CHAR_SIZE,
+    );
+    screen.push_clip(inner);
+    screen.draw_text_at_pos(
+        &state.strings.text("nemo.backlog.help"),
+        Vec2::new(text.x, text.y),
+        font,
+    );
+
+    // speaker, message, choice and a blank line after
+    let history = state.story.history();
+    let message_lines = |entry: &dialogue::Entry| font.wrap(&entry.message, text.w).len();
+    let rows = |entry: &dialogue::Entry| {
+        usize::from(!entry.speaker.is_empty())
+            + message_lines(entry)
+            + usize::from(entry.choice.is_some())
+            + 1
+    };
+    let top = text.y + 2.0 * CHAR_SIZE;
+    let space = ((text.bottom() - top) / CHAR_SIZE) as usize;
+    let mut first = state.backlog_selected;
+    let mut used = rows(&history[first]);
+    while first > 0 && used + rows(&history[first - 1]) <= space {
+        first -= 1;
+        used += rows(&history[first]);
+    }
+
+    let mut y = top;
+    for (i, entry) in history.iter().enumerate().skip(first) {
+        if y >= text.bottom() {
+            break;
+        }
+        let start = y;
+        if !entry.speaker.is_empty() {
+            screen.draw_text_at_pos(&entry.speaker, Vec2::new(text.x, y), font);
+            y += CHAR_SIZE;
+        }
+        let lines = message_lines(entry) as f32;
+        screen.draw_text_in_rect(
+            &entry.message,
+            Rect::new(text.x, y, text.w, lines * CHAR_SIZE),
+            font,
+            false,
+        );
+        y += lines * CHAR_SIZE;
+        if let Some(choice) = &entry.choice {
+            screen.draw_text_at_pos(&format!("- {}", choice), Vec2::new(text.x, y), font);
+            y += CHAR_SIZE;
+        }
+        if i == state.backlog_selected {
+            screen.rect_lines(
+                Rect::new(text.x, start, text.w, y - start).expand(4.0),
+                [255, 255, 255, 255],
+            );
+        }
+        y += CHAR_SIZE;
+    }
+    screen.pop_clip();
+}
 
 fn main() {
+    let options = options();
+
     let text_box = Rect::new(BOX_X, BOX_Y, BOX_WIDTH, BOX_HEIGHT);
     let text_box_text = Rect::new(
         BOX_X + 3.0 * BOX_WIDTH / 64.0,
@@ -80,74 +346,88 @@ fn main() {
         BOX_HEIGHT - CHAR_SIZE * 6.0,
     );
 
-    let story = parse_story().unwrap();
-    let title = story.story_name.clone();
-    let mut scene_map: HashMap<String, Scene> = HashMap::new();
-    let mut sprites: HashMap<String, Sprite> = HashMap::new();
-    use std::path::Path;
-    story.scenes.iter().for_each(|s| {
-        scene_map.insert(s.scene_name.clone(), s.scene.clone());
-        if !s.scene.name.is_empty() {
-            if let Ok(texture) = Texture::with_file(Path::new(&format!(
-                "content/fishsprites/{}.png",
-                s.scene.name.to_lowercase()
-            ))) {
-                let width = texture.width as f32;
-                let height = texture.height as f32;
-                let animation = Animation::new(&Rc::new(AnimationData {
-                    frames: vec![(Rect::new(0.0, 0.0, width, height), 1)],
-                    looping: false,
-                }));
-                sprites.insert(
-                    s.scene.name.clone(),
-                    Sprite::new(
-                        &Rc::new(texture),
-                        animation,
-                        Vec2::new((WIDTH as f32 - width) / 2.0, 200.0 - height),
-                    ),
-                );
-            }
+    let text_box_panel = {
+        let image = Rc::new(Texture::with_file(Path::new("content/panel.png")).unwrap());
+        let (width, height) = image.size();
+        Panel::new(
+            &image,
+            Rect::new(0.0, 0.0, width as f32, height as f32),
+            Insets::uniform(BOX_BORDER),
+        )
+    };
+
+    let story = match Story::load(&options.story) {
+        Ok(story) => Rc::new(story),
+        Err(err) => {
+            eprintln!("error: {}: {}", options.story.display(), err);
+            process::exit(1);
         }
-    });
+    };
+    let mut art = Art::load(&story);
+    let mut watcher = if options.dev {
+        Some(FileWatcher::new(&options.story))
+    } else {
+        None
+    };
 
-    let current_scene = scene_map.get("intro").unwrap();
+    let strings = Rc::new(load_strings(options.locale.as_deref()));
+    let mut runner = Runner::new(&story, "intro").unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", options.story.display(), err);
+        process::exit(1);
+    });
+    runner.set_strings(Some(Rc::clone(&strings)));
     let mut state = GameState {
-        // add tree struct that will represent game text and options. empty until text parser implemented
-        scene_map: scene_map.clone(),
-        current_scene: current_scene.clone(),
+        strings,
+        stage: Stage::new(runner.scene()),
+        story: runner,
         box_read: false,
         message_index: 0,
         box_text_index: 0,
-        response_index: 0,
-        // position in tree
-        //ending_score: 0,
-        // ending determiner
+        ui: Ui::new(Theme {
+            row_height: CHAR_SIZE * 2.0 - 4.0,
+            padding: BOX_WIDTH / 64.0,
+            ..Theme::default()
+        }),
+        ui_input: UiInput::default(),
         text_info: {
             let image = Rc::new(Texture::with_file(Path::new("content/ascii-dark.png")).unwrap());
-            TextInfo::new(&image, &textinfo::info())
+            let mut text_info = TextInfo::new(&image, &textinfo::info());
+            text_info.add_fallback_font(&Rc::new(TextInfo::new(
+                &image,
+                &textinfo::typographic_info(),
+            )));
+            text_info.set_fallback_glyph('?');
+            text_info
         },
         mode: Mode::Title,
+        saves: SaveDir::for_game("nemo"),
+        backlog_selected: 0,
+        backlog_from: Mode::Read,
     };
 
-    let (_stream, stream_handle) = rodio::OutputStream::try_default().unwrap();
-
-    let file = File::open("content/the-fish-who-dreamt-of-a-distant-planet.mp3").unwrap();
-    let background = rodio::Decoder::new(BufReader::new(file))
-        .unwrap()  
-        .repeat_infinite();
+    // src/bin/storycheck goes over everything, these are the ones worth a warning every run
+    for problem in dialogue::validate(&story, "intro") {
+        eprintln!("warning: {}", problem);
+    }
+    let localized = |id: &str| state.strings.get(id);
+    for (scene, chars) in dialogue::missing_glyphs(&story, &state.text_info, localized) {
+        eprintln!("warning: {}", Problem::MissingGlyphs { scene, chars });
+    }
 
-    let _ = stream_handle.play_raw(background.convert_samples());
+    let (_stream, stream_handle) = rodio::OutputStream::try_default().unwrap();
+    let mut jukebox = Jukebox::new(stream_handle);
 
     let event_loop = EventLoop::new();
+    let mut ui_keys = ui::default_keys(VirtualKeyCode::Space);
     let mut input_events = WinitInputHelper::new();
 
     let window = {
         let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
         WindowBuilder::new()
-            .with_title(&title)
+            .with_title(&story.story_name)
             .with_inner_size(size)
             .with_min_inner_size(size)
-            .with_resizable(false)
+            .with_resizable(true)
             .build(&event_loop)
             .unwrap()
     };
@@ -157,21 +437,85 @@ fn main() {
         Pixels::new(WIDTH as u32, HEIGHT as u32, surface_texture).unwrap()
     };
 
+    let mut last_frame = Instant::now();
     event_loop.run(move |event, _, control_flow| {
+        if let Event::MainEventsCleared = event {
+            let now = Instant::now();
+            let dt = (now - last_frame).as_secs_f32();
+            if state.stage.is_moving() {
+                state.stage.animate(dt);
+                window.request_redraw();
+            }
+            // catch up with whatever the story did since the last frame
+            for sound in state.story.take_sounds() {
+                jukebox.play_sfx(&sound);
+            }
+            jukebox.play_music(state.story.music().unwrap_or(DEFAULT_MUSIC));
+            jukebox.update(dt);
+            last_frame = now;
+        }
+        if let (Event::MainEventsCleared, Some(watcher)) = (&event, &mut watcher) {
+            if watcher.changed() {
+                // a broken file is reported and the old story kept, for the next save to fix
+                match Story::load(watcher.path()) {
+                    Ok(story) if !usable(&story) => eprintln!(
+                        "error: {}: not reloaded until the errors are fixed",
+                        watcher.path().display()
+                    ),
+                    Ok(story) => {
+                        let story = Rc::new(story);
+                        match state.story.reload(&story, "intro") {
+                            Ok(kept) => {
+                                eprintln!("reloaded {}", watcher.path().display());
+                                art = Art::load(&story);
+                                state.stage = Stage::new(state.story.scene());
+                                window.set_title(&story.story_name);
+                                // the page being read may have moved or gone
+                                state.reset_read_info();
+                                match state.mode {
+                                    Mode::Read | Mode::Respond
+                                        if !kept || state.story.choices().is_empty() =>
+                                    {
+                                        state.mode = Mode::Read
+                                    }
+                                    // the backlog shows the history, which is kept, but
+                                    // closing it should start the page over
+                                    Mode::Backlog => state.backlog_from = Mode::Read,
+                                    _ => {}
+                                }
+                                window.request_redraw();
+                            }
+                            Err(err) => eprintln!("error: {}: {}", watcher.path().display(), err),
+                        }
+                    }
+                    Err(err) => eprintln!("error: {}: {}", watcher.path().display(), err),
+                }
+            }
+        }
         match state.mode {
             Mode::Title => {
                 // Draw the current frame
                 if let Event::RedrawRequested(_) = event {
-                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH);
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
                     screen.clear([0, 105, 148, 255]);
 
-                    screen.draw_text_at_pos(&title, Vec2::new(450.0, 100.0), &state.text_info);
                     screen.draw_text_at_pos(
-                        "press enter to start.",
+                        &state.story.story().story_name,
+                        Vec2::new(450.0, 100.0),
+                        &state.text_info,
+                    );
+                    screen.draw_text_at_pos(
+                        &state.strings.text("nemo.title.start"),
                         Vec2::new(460.0, 440.0),
                         &state.text_info,
                     );
-                    screen.draw_text_at_pos("", Vec2::new(65.0, 260.0), &state.text_info);
+                    if state.has_progress() {
+                        screen.draw_text_at_pos(
+                            &state.strings.text("nemo.title.continue"),
+                            Vec2::new(460.0, 470.0),
+                            &state.text_info,
+                        );
+                    }
 
                     if pixels.render().is_err() {
                         *control_flow = ControlFlow::Exit;
@@ -191,6 +535,10 @@ fn main() {
                         state.mode = Mode::Read;
                         window.request_redraw();
                     }
+                    if input_events.key_pressed(VirtualKeyCode::C) && state.load_progress() {
+                        state.mode = Mode::Read;
+                        window.request_redraw();
+                    }
 
                     // Resize the window
                     if let Some(size) = input_events.window_resized() {
@@ -201,36 +549,32 @@ fn main() {
             Mode::Read => {
                 // Draw the current frame
                 if let Event::RedrawRequested(_) = event {
-                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH);
-                    screen.clear([0, 105, 148, 255]);
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
+                    state.stage.draw(&mut screen, &art);
 
                     //render text box
-                    screen.rect(text_box, BOX_COLOR);
-                    screen.rect_lines(text_box, [0, 0, 0, 0]);
-
-                    // draw sprite
-                    if let Some(sprite) = sprites.get(&state.current_scene.name) {
-                        screen.draw_sprite(sprite);
-                    }
+                    screen.draw_panel(&text_box_panel, text_box);
 
                     // render text in box
-                    if !state.current_scene.name.is_empty() {
+                    screen.push_clip(text_box);
+                    if !state.story.scene().name.is_empty() {
                         screen.draw_text_at_pos(
-                            &state.current_scene.name,
+                            &state.story.scene().name,
                             Vec2::new(text_box_text.x, BOX_Y + CHAR_SIZE * 2.0),
                             &state.text_info,
                         );
                     }
-                    if let Some(idx) = screen.draw_text_in_rect(
-                        &state.current_scene.message[state.box_text_index..],
+                    if let Some(idx) = screen.draw_text_page(
+                        &state.story.message(),
+                        state.box_text_index,
                         text_box_text,
                         &state.text_info,
-                        false,
                     ) {
                         state.message_index = idx;
                     } else {
-                        state.message_index = state.current_scene.message.len();
+                        state.message_index = state.story.message().len();
                     }
+                    screen.pop_clip();
 
                     if pixels.render().is_err() {
                         *control_flow = ControlFlow::Exit;
@@ -243,35 +587,49 @@ fn main() {
                     // Close events
 
                     if input_events.key_pressed(VirtualKeyCode::Escape) || input_events.quit() {
+                        state.save_progress();
                         *control_flow = ControlFlow::Exit;
                         return;
                     }
 
+                    // kept current here too, so the space that brings up the responses
+                    // doesn't also pick one
+                    ui_keys.update(&input_events);
+
+                    if input_events.key_pressed(VirtualKeyCode::H) && state.open_backlog() {
+                        window.request_redraw();
+                        return;
+                    }
+
                     if input_events.key_pressed(VirtualKeyCode::Space) || input_events.quit() {
                         state.box_text_index = state.message_index;
-                        if !state.current_scene.responses.is_empty()
-                            && !state.current_scene.responses[0].response.is_empty()
-                        {
-                            // if player has read all text and has option to give response switch to response mode
-                            if state.message_index >= state.current_scene.message.len() - 1 {
-                                state.mode = Mode::Respond;
-                                state.box_read = false;
-                                state.box_text_index = 0;
-                                state.message_index = 0;
+                        match state.story.scene().next {
+                            Next::Choice(_) => {
+                                // if player has read all text switch to response mode
+                                if state.message_index
+                                    >= state.story.message().len().saturating_sub(1)
+                                {
+                                    state.mode = Mode::Respond;
+                                    state.box_read = false;
+                                    state.box_text_index = 0;
+                                    state.message_index = 0;
+                                }
                             }
-                        } else {
-                            // if player reached end of tree and no final response available switch to game over
-                            if state.current_scene.responses.is_empty() {
+                            // if player reached end of tree switch to game over
+                            Next::End => {
                                 state.mode = Mode::EndGame;
-                            } else {
-                                // if no response option available go forward in story
-                                state.current_scene = state
-                                    .scene_map
-                                    .get(&*(state.current_scene.responses[0].goto))
-                                    .unwrap()
-                                    .clone();
-                                state.reset_read_info();
+                                state.clear_progress();
                             }
+                            // if no response option available go forward in story
+                            Next::Continue(_) => match state.story.advance() {
+                                Ok(()) => {
+                                    state.stage.change_to(state.story.scene());
+                                    state.reset_read_info();
+                                    state.save_progress();
+                                }
+                                // the runner stays where it was
+                                Err(err) => eprintln!("error: can't go on: {}", err),
+                            },
                         }
 
                         window.request_redraw();
@@ -288,97 +646,140 @@ fn main() {
                 // Draw the current frame
 
                 if let Event::RedrawRequested(_) = event {
-                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH);
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
                     // render background
-                    screen.clear([0, 105, 148, 255]);
+                    state.stage.draw(&mut screen, &art);
 
                     //render text box
-                    screen.rect(text_box, BOX_COLOR);
-                    screen.rect_lines(text_box, [0, 0, 0, 255]);
-
-                    // vec of response y values for pointer to know location
-                    let mut ypos_vec: Vec<f32> = vec![BOX_Y + CHAR_SIZE];
+                    screen.draw_panel(&text_box_panel, text_box);
 
                     // render responses
-                    for (i, resp_map) in state.current_scene.responses.iter().enumerate() {
-                        let cur_rect = Rect::new(
-                            BOX_X + 3.0 * BOX_WIDTH / 64.0,
-                            ypos_vec[i],
-                            BOX_WIDTH - 6.0 * BOX_WIDTH / 64.0,
-                            BOX_HEIGHT,
-                        );
-
-                        screen.draw_text_in_rect(
-                            &resp_map.response,
-                            cur_rect,
-                            &state.text_info,
-                            false,
-                        );
-                        ypos_vec.push(cur_rect.y + CHAR_SIZE * 2.0);
+                    screen.push_clip(text_box);
+                    let mut chosen = None;
+                    let mut frame = state.ui.begin(
+                        &mut screen,
+                        &state.text_info,
+                        &state.ui_input,
+                        Rect::new(BOX_X, BOX_Y + CHAR_SIZE, BOX_WIDTH, BOX_HEIGHT),
+                    );
+                    for (i, choice) in state.story.choices().iter().enumerate() {
+                        // disabled choices are shown, but focus skips them
+                        if !choice.enabled {
+                            frame.wrapped_label(&choice.text);
+                        } else if frame.wrapped_button(&choice.text).activated && chosen.is_none() {
+                            chosen = Some(i);
+                        }
                     }
-
-                    // response pointer
-                    let pointer = Rect {
-                        x: BOX_X + 1.0 * BOX_WIDTH / 64.0,
-                        y: ypos_vec[state.response_index],
-                        h: 8.0,
-                        w: 8.0,
+                    drop(frame);
+                    screen.pop_clip();
+                    // the pointer is kept so the next frame can tell whether it moved
+                    state.ui_input = UiInput {
+                        pointer: state.ui_input.pointer,
+                        ..UiInput::default()
                     };
-                    screen.rect(pointer, [255, 0, 0, 255]);
 
                     if pixels.render().is_err() {
                         *control_flow = ControlFlow::Exit;
                         return;
                     }
+
+                    //move to next value in tree based on response.
+                    if let Some(i) = chosen {
+                        match state.story.choose(i) {
+                            Ok(()) => {
+                                state.stage.change_to(state.story.scene());
+                                state.reset_read_info();
+                                state.save_progress();
+                                state.mode = Mode::Read;
+                            }
+                            Err(err) => eprintln!("error: can't pick that: {}", err),
+                        }
+                        window.request_redraw();
+                    }
                 }
 
                 //TODO update position in tree
 
-                let mut input = false;
-
                 // Handle input_events events
                 if input_events.update(&event) {
                     // Close events
                     if input_events.key_pressed(VirtualKeyCode::Escape) || input_events.quit() {
+                        state.save_progress();
                         *control_flow = ControlFlow::Exit;
                         return;
                     }
 
-                    if input_events.key_pressed(VirtualKeyCode::Down) {
-                        if state.response_index < state.current_scene.responses.len() - 1 {
-                            state.response_index += 1;
-                        } else {
-                            state.response_index = 0;
-                        }
-                        input = true;
+                    ui_keys.update(&input_events);
+                    if input_events.key_pressed(VirtualKeyCode::H) && state.open_backlog() {
+                        window.request_redraw();
+                        return;
+                    }
+                    let size = window.inner_size();
+                    let letterbox = Letterbox::new((WIDTH, HEIGHT), (size.width, size.height));
+                    let input = UiInput::gather(&ui_keys, &input_events, &letterbox);
+                    let moved = input.pointer != state.ui_input.pointer;
+                    state.ui_input = input;
+                    if moved || !state.ui_input.is_idle() {
+                        window.request_redraw();
+                    }
+
+                    // Resize the window
+                    if let Some(size) = input_events.window_resized() {
+                        pixels.resize(size.width, size.height);
                     }
+                }
+            }
+
+            Mode::Backlog => {
+                if let Event::RedrawRequested(_) = event {
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
+                    screen.clear([0, 105, 148, 255]);
+                    draw_backlog(&mut screen, &state, &text_box_panel);
 
+                    if pixels.render().is_err() {
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                }
+
+                if input_events.update(&event) {
+                    if input_events.quit() {
+                        state.save_progress();
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                    // the history can shrink under us if the story is reloaded
+                    let last = state.story.history().len().saturating_sub(1);
                     if input_events.key_pressed(VirtualKeyCode::Up) {
-                        if state.response_index > 0 {
-                            state.response_index -= 1;
-                        } else {
-                            state.response_index = state.current_scene.responses.len() - 1;
-                        }
-                        input = true;
-                    }
-
-                    if input_events.key_pressed(VirtualKeyCode::Space) {
-                        //move to next value in tree based on response.
-                        if state.current_scene.responses.is_empty() {
-                            state.mode = Mode::EndGame;
-                        } else {
-                            state.current_scene = state
-                                .scene_map
-                                .get(&*(state.current_scene.responses[state.response_index].goto))
-                                .unwrap()
-                                .clone();
-                            state.reset_read_info();
-                            state.mode = Mode::Read;
+                        state.backlog_selected = state.backlog_selected.saturating_sub(1);
+                        window.request_redraw();
+                    }
+                    if input_events.key_pressed(VirtualKeyCode::Down) {
+                        state.backlog_selected = (state.backlog_selected + 1).min(last);
+                        window.request_redraw();
+                    }
+                    if input_events.key_pressed(VirtualKeyCode::Return)
+                        && state
+                            .story
+                            .history()
+                            .get(state.backlog_selected)
+                            .is_some_and(|entry| entry.choice.is_some())
+                    {
+                        match state.story.rollback(state.backlog_selected) {
+                            Ok(()) => {
+                                state.reset_read_info();
+                                state.stage = Stage::new(state.story.scene());
+                                state.mode = Mode::Read;
+                                state.save_progress();
+                            }
+                            Err(err) => eprintln!("error: can't go back: {}", err),
                         }
-                        input = true;
+                        window.request_redraw();
                     }
-
-                    if input {
+                    if input_events.key_pressed(VirtualKeyCode::H)
+                        || input_events.key_pressed(VirtualKeyCode::Escape)
+                    {
+                        state.mode = state.backlog_from;
                         window.request_redraw();
                     }
 
@@ -391,18 +792,22 @@ fn main() {
 
             Mode::EndGame => {
                 if let Event::RedrawRequested(_) = event {
-                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH);
+                    let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT);
                     screen.clear([100, 150, 200, 255]);
 
-                    screen.draw_text_at_pos("the end", Vec2::new(400.0, 60.0), &state.text_info);
+                    screen.draw_text_at_pos(
+                        &state.strings.text("nemo.end"),
+                        Vec2::new(400.0, 60.0),
+                        &state.text_info,
+                    );
 
                     screen.draw_text_at_pos(
-                        "press enter to return to title screen",
+                        &state.strings.text("nemo.end.restart"),
                         Vec2::new(400.0, 240.0),
                         &state.text_info,
                     );
                     screen.draw_text_at_pos(
-                        "or escape to exit",
+                        &state.strings.text("nemo.end.exit"),
                         Vec2::new(300.0, 260.0),
                         &state.text_info,
                     );
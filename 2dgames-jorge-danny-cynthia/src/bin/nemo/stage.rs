@@ -0,0 +1,204 @@
+// What's behind the text box: the scene's background and the characters on stage,
+// moving from one scene's lineup to the next
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use engine2d::dialogue::{Character, Scene, Slot, Story, Transition};
+use engine2d::objects::{Color, Rect, Vec2};
+use engine2d::screen::Screen;
+use engine2d::texture::Texture;
+
+use crate::{BOX_Y, WIDTH};
+
+// how long it takes characters to come on, go off or move over, in seconds
+const TRANSITION_TIME: f32 = 0.4;
+// where the bottoms of the characters' pictures line up
+const FLOOR: f32 = 200.0;
+// behind everything when there's no background
+const WATER: Color = [0, 105, 148, 255];
+
+// The pictures a story uses, loaded up front: content/fishsprites/<sprite in lower case>.png
+// for characters and content/backgrounds/<background>.png for backgrounds
+pub struct Art {
+    // None for the ones that couldn't be loaded, so they aren't tried again
+    sprites: HashMap<String, Option<Rc<Texture>>>,
+    backgrounds: HashMap<String, Option<Rc<Texture>>>,
+}
+
+fn load(path: &str) -> Option<Rc<Texture>> {
+    Texture::with_file(Path::new(path)).ok().map(Rc::new)
+}
+
+impl Art {
+    pub fn load(story: &Story) -> Self {
+        let mut sprites = HashMap::new();
+        let mut backgrounds = HashMap::new();
+        for named in story.scenes.iter() {
+            for character in named.scene.cast() {
+                // the plain picture too, for expressions nobody's drawn yet
+                for sprite in [character.sprite(), character.character] {
+                    let sprite = sprite.to_lowercase();
+                    let path = format!("content/fishsprites/{}.png", sprite);
+                    sprites.entry(sprite).or_insert_with(|| load(&path));
+                }
+            }
+            if let Some(background) = &named.scene.background {
+                let path = format!("content/backgrounds/{}.png", background);
+                backgrounds
+                    .entry(background.clone())
+                    .or_insert_with(|| load(&path));
+            }
+        }
+        Self {
+            sprites,
+            backgrounds,
+        }
+    }
+
+    // How character looks, or how they look normally if there's no picture of the expression
+    pub fn sprite(&self, character: &Character) -> Option<&Rc<Texture>> {
+        [character.sprite(), character.character.clone()]
+            .iter()
+            .find_map(|sprite| self.sprites.get(&sprite.to_lowercase())?.as_ref())
+    }
+
+    pub fn background(&self, background: &str) -> Option<&Rc<Texture>> {
+        self.backgrounds.get(background)?.as_ref()
+    }
+}
+
+pub struct Stage {
+    background: Option<String>,
+    cast: Vec<Character>,
+    // on stage in the last scene but not this one, on their way off
+    leaving: Vec<Character>,
+    // where everyone on stage in the last scene was standing
+    was_at: HashMap<String, Slot>,
+    // seconds since the scene changed
+    elapsed: f32,
+}
+
+impl Stage {
+    // Set for scene, with nothing moving
+    pub fn new(scene: &Scene) -> Self {
+        Self {
+            background: scene.background.clone(),
+            cast: scene.cast(),
+            leaving: vec![],
+            was_at: HashMap::new(),
+            elapsed: TRANSITION_TIME,
+        }
+    }
+
+    // Start moving from what's on stage now to scene's lineup
+    pub fn change_to(&mut self, scene: &Scene) {
+        let old = std::mem::replace(&mut self.cast, scene.cast());
+        self.background = scene.background.clone();
+        self.was_at = old
+            .iter()
+            .map(|character| (character.character.clone(), character.slot))
+            .collect();
+        let cast = &self.cast;
+        self.leaving = old
+            .into_iter()
+            .filter(|old| !cast.iter().any(|new| new.character == old.character))
+            .collect();
+        self.elapsed = 0.0;
+    }
+
+    pub fn animate(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(TRANSITION_TIME);
+        if !self.is_moving() {
+            self.leaving.clear();
+        }
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.elapsed < TRANSITION_TIME
+    }
+
+    // Fills the whole screen; the characters stay above the text box
+    pub fn draw(&self, screen: &mut Screen, art: &Art) {
+        screen.clear(WATER);
+        if let Some(texture) = self.background.as_deref().and_then(|b| art.background(b)) {
+            let (width, height) = texture.size();
+            screen.bitblt(
+                texture,
+                Rect::new(0.0, 0.0, width as f32, height as f32),
+                Vec2::ZERO,
+            );
+        }
+
+        screen.push_clip(Rect::new(0.0, 0.0, WIDTH as f32, BOX_Y));
+        let t = self.elapsed / TRANSITION_TIME;
+        // eased, so nothing starts or stops with a jerk
+        let t = t * t * (3.0 - 2.0 * t);
+        for character in self.leaving.iter() {
+            if character.exit != Transition::Cut {
+                show(screen, art, character, None, character.exit, 1.0 - t);
+            }
+        }
+        for character in self.cast.iter() {
+            match self.was_at.get(&character.character) {
+                Some(&from) => show(screen, art, character, Some(from), Transition::Cut, t),
+                None if character.enter == Transition::Cut => {
+                    show(screen, art, character, None, Transition::Cut, 1.0)
+                }
+                None => show(screen, art, character, None, character.enter, t),
+            }
+        }
+        screen.pop_clip();
+    }
+}
+
+// Draws character `shown` of the way through coming on with transition, or through moving
+// over from the slot `from` if they were already on stage
+fn show(
+    screen: &mut Screen,
+    art: &Art,
+    character: &Character,
+    from: Option<Slot>,
+    transition: Transition,
+    shown: f32,
+) {
+    let texture = match art.sprite(character) {
+        Some(texture) => texture,
+        None => return,
+    };
+    let (width, height) = texture.size();
+    let (width, height) = (width as f32, height as f32);
+    let frame = Rect::new(0.0, 0.0, width, height);
+    let x = slot_x(character.slot, width);
+    let lerp = |from: f32| from + (x - from) * shown;
+    match transition {
+        Transition::Cut => {
+            let x = from.map_or(x, |from| lerp(slot_x(from, width)));
+            screen.bitblt(texture, frame, Vec2::new(x, FLOOR - height));
+        }
+        Transition::Fade => screen.bitblt_faded(
+            texture,
+            frame,
+            Vec2::new(x, FLOOR - height),
+            (shown * 255.0) as u8,
+        ),
+        Transition::Slide => {
+            // off the side of the screen their slot is nearer to
+            let offstage = match character.slot {
+                Slot::Left | Slot::Center => -width,
+                Slot::Right => WIDTH as f32,
+            };
+            screen.bitblt(texture, frame, Vec2::new(lerp(offstage), FLOOR - height));
+        }
+    }
+}
+
+// Left edge of a picture `width` wide standing in slot
+fn slot_x(slot: Slot, width: f32) -> f32 {
+    let middle = match slot {
+        Slot::Left => WIDTH as f32 / 4.0,
+        Slot::Center => WIDTH as f32 / 2.0,
+        Slot::Right => 3.0 * WIDTH as f32 / 4.0,
+    };
+    middle - width / 2.0
+}
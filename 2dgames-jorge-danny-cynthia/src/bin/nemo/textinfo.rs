@@ -2,7 +2,7 @@ use engine2d::objects::Rect;
 
 use crate::CHAR_SIZE;
 
-pub fn info() -> [(char, Rect); 71] {
+pub fn info() -> [(char, Rect); 77] {
     [
         (' ', Rect::new(0.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
         ('!', Rect::new(16.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
@@ -75,5 +75,24 @@ pub fn info() -> [(char, Rect); 71] {
         ('\'', Rect::new(112.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
         ('?', Rect::new(240.0, 16.0, CHAR_SIZE, CHAR_SIZE)),
         ('*', Rect::new(160.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('"', Rect::new(32.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('(', Rect::new(128.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        (')', Rect::new(144.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('/', Rect::new(240.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        (';', Rect::new(176.0, 16.0, CHAR_SIZE, CHAR_SIZE)),
+        ('~', Rect::new(224.0, 80.0, CHAR_SIZE, CHAR_SIZE)),
+    ]
+}
+
+// curly quotes and dashes that show up when the script is written in a word processor,
+// drawn with the closest ascii glyph from the same atlas
+pub fn typographic_info() -> [(char, Rect); 6] {
+    [
+        ('\u{201c}', Rect::new(32.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('\u{201d}', Rect::new(32.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('\u{2018}', Rect::new(112.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('\u{2019}', Rect::new(112.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('\u{2013}', Rect::new(208.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
+        ('\u{2014}', Rect::new(208.0, 0.0, CHAR_SIZE, CHAR_SIZE)),
     ]
 }
@@ -0,0 +1,52 @@
+// storycheck graph: prints a story's scenes and how they link up, for Graphviz or Mermaid.
+//
+//     cargo run --bin storycheck -- graph [--mermaid] [--start SCENE] [SCRIPT] | dot -Tsvg
+use std::path::PathBuf;
+use std::process;
+
+use engine2d::dialogue::{self, GraphFormat, Story};
+
+struct Options {
+    script: PathBuf,
+    start: String,
+    format: GraphFormat,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: storycheck graph [--dot | --mermaid] [--start SCENE] [SCRIPT]\n\
+         prints DOT unless asked for Mermaid; SCRIPT defaults to src/bin/nemo/script.json"
+    );
+    process::exit(2);
+}
+
+fn options(mut args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        script: PathBuf::from("src/bin/nemo/script.json"),
+        start: "intro".to_string(),
+        format: GraphFormat::Dot,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dot" => options.format = GraphFormat::Dot,
+            "--mermaid" => options.format = GraphFormat::Mermaid,
+            "--start" => options.start = args.next().unwrap_or_else(|| usage()),
+            "-h" | "--help" => usage(),
+            _ if arg.starts_with('-') => usage(),
+            _ => options.script = arg.into(),
+        }
+    }
+    options
+}
+
+pub fn main(args: impl Iterator<Item = String>) {
+    let options = options(args);
+    let story = Story::load(&options.script).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", options.script.display(), err);
+        process::exit(2);
+    });
+    print!(
+        "{}",
+        dialogue::graph(&story, &options.start, options.format)
+    );
+}
@@ -0,0 +1,81 @@
+// storycheck import: turns a Twine story saved as Twee 3 into a nemo script.
+//
+//     cargo run --bin storycheck -- import [--deny-warnings] [-o SCRIPT] TWEE
+//
+// Whatever couldn't be brought over is printed as warnings (see dialogue/twee.rs). Exits
+// with 1 if there were any and --deny-warnings was given, and 2 if nothing was imported.
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use engine2d::dialogue;
+
+struct Options {
+    twee: PathBuf,
+    output: Option<PathBuf>,
+    deny_warnings: bool,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: storycheck import [--deny-warnings] [-o SCRIPT] TWEE\n\
+         prints the script unless -o says where to write it"
+    );
+    process::exit(2);
+}
+
+fn options(mut args: impl Iterator<Item = String>) -> Options {
+    let mut twee = None;
+    let mut output = None;
+    let mut deny_warnings = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deny-warnings" => deny_warnings = true,
+            "-o" => output = Some(args.next().unwrap_or_else(|| usage()).into()),
+            "-h" | "--help" => usage(),
+            _ if arg.starts_with('-') => usage(),
+            _ => twee = Some(arg.into()),
+        }
+    }
+    Options {
+        twee: twee.unwrap_or_else(|| usage()),
+        output,
+        deny_warnings,
+    }
+}
+
+pub fn main(args: impl Iterator<Item = String>) {
+    let options = options(args);
+    let source = fs::read_to_string(&options.twee).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", options.twee.display(), err);
+        process::exit(2);
+    });
+    let (story, problems) = dialogue::import_twee(&source);
+    if story.scenes.is_empty() {
+        eprintln!("error: {}: no passages to import", options.twee.display());
+        process::exit(2);
+    }
+    for problem in problems.iter() {
+        eprintln!("warning: {}", problem);
+    }
+
+    let json = story.to_json() + "\n";
+    match &options.output {
+        Some(path) => {
+            if let Err(err) = fs::write(path, json) {
+                eprintln!("error: {}: {}", path.display(), err);
+                process::exit(2);
+            }
+            eprintln!(
+                "{}: {} scenes, {} warnings",
+                path.display(),
+                story.scenes.len(),
+                problems.len()
+            );
+        }
+        None => print!("{}", json),
+    }
+    if options.deny_warnings && !problems.is_empty() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,168 @@
+// Checks a nemo story for mistakes without playing through it.
+//
+//     cargo run --bin storycheck -- [--deny-warnings] [--start SCENE] [--sprites DIR]
+//         [--backgrounds DIR] [--sounds DIR] [--locales DIR] [SCRIPT]
+//
+// message_ids are looked up in the English strings under the locales dir, which is what
+// nemo falls back on for every language.
+//
+// Exits with 0 if the story is fine, 1 if it has errors (or warnings, with --deny-warnings),
+// and 2 if it couldn't be checked at all. `storycheck graph` draws the story instead (see
+// graph.rs), `storycheck paths` plays through it (see paths.rs) and `storycheck import`
+// brings one over from Twine (see import.rs).
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+
+use engine2d::dialogue::{self, Problem, Story};
+use engine2d::locale::Strings;
+use engine2d::text::TextInfo;
+use engine2d::texture::Texture;
+
+mod graph;
+mod import;
+mod paths;
+#[path = "../nemo/textinfo.rs"]
+mod textinfo;
+
+pub const CHAR_SIZE: f32 = 16.0;
+
+struct Options {
+    script: PathBuf,
+    sprites: PathBuf,
+    backgrounds: PathBuf,
+    sounds: PathBuf,
+    locales: PathBuf,
+    start: String,
+    deny_warnings: bool,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: storycheck [--deny-warnings] [--start SCENE] [--sprites DIR] \
+         [--backgrounds DIR] [--sounds DIR] [--locales DIR] [SCRIPT]\n\
+         storycheck graph [--dot | --mermaid] [--start SCENE] [SCRIPT]\n\
+         storycheck paths [--start SCENE] [--limit SCENES] [SCRIPT]\n\
+         storycheck import [--deny-warnings] [-o SCRIPT] TWEE\n\
+         SCRIPT defaults to src/bin/nemo/script.json, the sprites to content/fishsprites, \
+         the backgrounds to content/backgrounds, the sounds to content and the locales to \
+         content/locale"
+    );
+    process::exit(2);
+}
+
+fn options() -> Options {
+    let mut options = Options {
+        script: PathBuf::from("src/bin/nemo/script.json"),
+        sprites: PathBuf::from("content/fishsprites"),
+        backgrounds: PathBuf::from("content/backgrounds"),
+        sounds: PathBuf::from("content"),
+        locales: PathBuf::from("content/locale"),
+        start: "intro".to_string(),
+        deny_warnings: false,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deny-warnings" => options.deny_warnings = true,
+            "--start" => options.start = args.next().unwrap_or_else(|| usage()),
+            "--sprites" => options.sprites = args.next().unwrap_or_else(|| usage()).into(),
+            "--backgrounds" => options.backgrounds = args.next().unwrap_or_else(|| usage()).into(),
+            "--sounds" => options.sounds = args.next().unwrap_or_else(|| usage()).into(),
+            "--locales" => options.locales = args.next().unwrap_or_else(|| usage()).into(),
+            "-h" | "--help" => usage(),
+            _ if arg.starts_with('-') => usage(),
+            _ => options.script = arg.into(),
+        }
+    }
+    options
+}
+
+// The same font nemo draws with
+fn font() -> TextInfo {
+    let image = Rc::new(
+        Texture::with_file(Path::new("content/ascii-dark.png")).unwrap_or_else(|err| {
+            eprintln!("error: can't load content/ascii-dark.png: {}", err);
+            process::exit(2);
+        }),
+    );
+    let mut font = TextInfo::new(&image, &textinfo::info());
+    font.add_fallback_font(&Rc::new(TextInfo::new(
+        &image,
+        &textinfo::typographic_info(),
+    )));
+    font
+}
+
+fn main() {
+    match env::args().nth(1).as_deref() {
+        Some("graph") => return graph::main(env::args().skip(2)),
+        Some("paths") => return paths::main(env::args().skip(2)),
+        Some("import") => return import::main(env::args().skip(2)),
+        _ => {}
+    }
+    let options = options();
+    let story = Story::load(&options.script).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", options.script.display(), err);
+        process::exit(2);
+    });
+
+    let mut problems = dialogue::validate(&story, &options.start);
+    // nemo looks for sprites under their name in lower case
+    problems.extend(dialogue::missing_sprites(&story, |sprite| {
+        options
+            .sprites
+            .join(format!("{}.png", sprite.to_lowercase()))
+            .is_file()
+    }));
+    problems.extend(dialogue::missing_backgrounds(&story, |background| {
+        options
+            .backgrounds
+            .join(format!("{}.png", background))
+            .is_file()
+    }));
+    problems.extend(dialogue::missing_sounds(&story, |sound| {
+        options.sounds.join(sound).is_file()
+    }));
+    let strings = Strings::load(&options.locales, "en", "en")
+        .map_err(|err| {
+            let dir = options.locales.display();
+            eprintln!("warning: can't load strings from {}: {}", dir, err)
+        })
+        .ok();
+    problems.extend(dialogue::empty_messages(&story, |id| {
+        strings
+            .as_ref()
+            .and_then(|strings| strings.get(id))
+            .is_some_and(|text| !text.is_empty())
+    }));
+    problems.extend(
+        dialogue::missing_glyphs(&story, &font(), |id| {
+            strings.as_ref().and_then(|strings| strings.get(id))
+        })
+        .into_iter()
+        .map(|(scene, chars)| Problem::MissingGlyphs { scene, chars }),
+    );
+
+    let errors = problems.iter().filter(|p| p.is_error()).count();
+    let warnings = problems.len() - errors;
+    for problem in problems.iter() {
+        let level = if problem.is_error() {
+            "error"
+        } else {
+            "warning"
+        };
+        println!("{}: {}", level, problem);
+    }
+    println!(
+        "{}: {} scenes, {} errors, {} warnings",
+        options.script.display(),
+        story.scenes.len(),
+        errors,
+        warnings
+    );
+    if errors > 0 || (options.deny_warnings && warnings > 0) {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,122 @@
+// storycheck paths: plays every path through a story and says how they end.
+//
+//     cargo run --bin storycheck -- paths [--start SCENE] [--limit SCENES] [--budget SCENES]
+//         [SCRIPT]
+//
+// Exits with 1 if some path goes wrong or leaves the player stuck, or had to be cut off at
+// the limit, or if the budget ran out before every path was tried.
+use std::path::PathBuf;
+use std::process;
+use std::rc::Rc;
+
+use engine2d::dialogue::{self, Story};
+
+struct Options {
+    script: PathBuf,
+    start: String,
+    limit: usize,
+    budget: usize,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: storycheck paths [--start SCENE] [--limit SCENES] [--budget SCENES] [SCRIPT]\n\
+         paths longer than the limit (1000 scenes by default) are cut off, and it stops after \
+         visiting the budget (1000000 scenes by default); \
+         SCRIPT defaults to src/bin/nemo/script.json"
+    );
+    process::exit(2);
+}
+
+fn options(mut args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        script: PathBuf::from("src/bin/nemo/script.json"),
+        start: "intro".to_string(),
+        limit: 1000,
+        budget: 1_000_000,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--start" => options.start = args.next().unwrap_or_else(|| usage()),
+            "--limit" => {
+                options.limit = args
+                    .next()
+                    .and_then(|limit| limit.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            "--budget" => {
+                options.budget = args
+                    .next()
+                    .and_then(|budget| budget.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            "-h" | "--help" => usage(),
+            _ if arg.starts_with('-') => usage(),
+            _ => options.script = arg.into(),
+        }
+    }
+    options
+}
+
+pub fn main(args: impl Iterator<Item = String>) {
+    let options = options(args);
+    let story = Story::load(&options.script).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", options.script.display(), err);
+        process::exit(2);
+    });
+    let found = dialogue::explore(
+        &Rc::new(story),
+        &options.start,
+        options.limit,
+        options.budget,
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", options.script.display(), err);
+        process::exit(2);
+    });
+
+    for failure in found.failures.iter() {
+        println!("error: {}", failure);
+    }
+    if found.cut_off > 0 {
+        println!(
+            "error: {} paths went on past {} scenes",
+            found.cut_off, options.limit
+        );
+    }
+    if found.out_of_budget {
+        println!(
+            "error: stopped after visiting {} scenes, so not every path was tried",
+            options.budget
+        );
+    }
+    for scenes in found.loops.iter() {
+        println!("loop: {}", scenes.join(" > "));
+    }
+    for (ending, paths) in found.endings.iter() {
+        println!("ending {:?}: {} paths", ending, paths);
+    }
+    if found.paths() > 0 {
+        println!(
+            "shortest: {} scenes ({})",
+            found.shortest.len(),
+            found.shortest.join(" > ")
+        );
+        println!(
+            "longest: {} scenes ({})",
+            found.longest.len(),
+            found.longest.join(" > ")
+        );
+    }
+    println!(
+        "{}: {} paths, {} endings, {} loops, {} failures",
+        options.script.display(),
+        found.paths(),
+        found.endings.len(),
+        found.loops.len(),
+        found.failures.len()
+    );
+    if !found.failures.is_empty() || found.cut_off > 0 || found.out_of_budget {
+        process::exit(1);
+    }
+}
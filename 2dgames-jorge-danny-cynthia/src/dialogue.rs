@@ -0,0 +1,881 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Strings;
+
+mod explore;
+mod expr;
+mod graph;
+mod twee;
+mod validate;
+pub use explore::{explore, Exploration, Failure, Trouble};
+pub use expr::{interpolate, Expr, Kind, Value};
+pub use graph::{graph, GraphFormat};
+pub use twee::{import_twee, TweeProblem};
+pub use validate::{
+    empty_messages, missing_backgrounds, missing_glyphs, missing_sounds, missing_sprites, validate,
+    Problem,
+};
+
+// A branching story: scenes that each show a message and then lead on to other scenes.
+// Stories are read from JSON like
+//
+//     {
+//         "story_name": "...",
+//         "scenes": [
+//             { "scene_name": "intro", "scene": { "name": "Nemo", "message": "...",
+//                 "responses": [{ "response": "Okay!", "goto": "start" }] } },
+//             { "scene_name": "start", "scene": { "name": "Nemo", "message": "...",
+//                 "next": "end" } },
+//             { "scene_name": "end", "scene": { "name": "", "message": "..." } }
+//         ]
+//     }
+//
+// A scene with "responses" lets the player pick, one with "next" carries on by itself,
+// and one with neither is an ending.
+//
+// Stories can also keep track of things with variables, declared with their starting values
+// in "variables": { "treasure": 0, "met_dory": false }. Responses can have a "condition"
+// (see expr.rs) that hides them while it's false, or just stops them being picked with
+// "when_false": "disable". Scenes and responses can have "effects" that happen when the scene
+// is shown or the response picked, like { "set": "met_dory", "to": true } or
+// { "add": "treasure", "by": 1 }. Messages and responses can show values with {treasure}.
+//
+// How a scene looks is up to the game, but a scene can ask for a "background" picture and
+// put characters on "stage": [{ "character": "Marlin", "slot": "left",
+// "expression": "worried", "enter": "slide", "exit": "fade" }]. Without a stage, whoever's
+// speaking stands in the center.
+//
+// Scenes and responses can change the "music", which keeps playing until something changes
+// it again ("" for silence), and play a one-off "sfx" when the scene is shown or the
+// response picked.
+//
+// For stories in more than one language, a scene's text can come from a string table
+// (see locale.rs) with "message_id", and a response's with "response_id". The message or
+// response written in the story is only used when there are no strings, or they don't
+// have the ID.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Story {
+    pub story_name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, Value>,
+    pub scenes: Vec<NamedScene>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct NamedScene {
+    pub scene_name: String,
+    pub scene: Scene,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(try_from = "SceneJson", into = "SceneJson")]
+pub struct Scene {
+    // who's speaking, empty for nobody
+    pub name: String,
+    pub message: String,
+    // where to find the message in the story's strings
+    pub message_id: Option<String>,
+    pub next: Next,
+    // on the way into the scene
+    pub effects: Vec<Effect>,
+    // picture to show behind everything, None for none
+    pub background: Option<String>,
+    // who's on screen, None for just the speaker (see cast)
+    pub stage: Option<Vec<Character>>,
+    // what to play from here on, None to keep playing what was
+    pub music: Option<String>,
+    // to play once on the way into the scene
+    pub sfx: Option<String>,
+}
+
+// What happens once a scene's message has been read
+#[derive(Clone, PartialEq, Debug)]
+pub enum Next {
+    // on to the named scene, without asking
+    Continue(String),
+    // the player picks one of these
+    Choice(Vec<Response>),
+    // the story is over
+    End,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Response {
+    #[serde(default)]
+    pub response: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_id: Option<String>,
+    pub goto: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Expr>,
+    #[serde(default, skip_serializing_if = "WhenFalse::is_hide")]
+    pub when_false: WhenFalse,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<Effect>,
+    // like a scene's, when the response is picked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub music: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sfx: Option<String>,
+}
+
+// What happens to a response while its condition is false
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WhenFalse {
+    #[default]
+    Hide,
+    // still shown, but can't be picked
+    Disable,
+}
+
+impl WhenFalse {
+    fn is_hide(&self) -> bool {
+        *self == WhenFalse::Hide
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum Effect {
+    Set { set: String, to: Value },
+    // by can be negative
+    Add { add: String, by: i64 },
+}
+
+impl Effect {
+    // The variable it changes
+    pub fn variable(&self) -> &str {
+        match self {
+            Effect::Set { set, .. } => set,
+            Effect::Add { add, .. } => add,
+        }
+    }
+
+    // Setting a variable that isn't there makes it, and adding to one starts it at 0
+    pub fn apply(&self, variables: &mut BTreeMap<String, Value>) -> Result<(), String> {
+        match self {
+            Effect::Set { set, to } => {
+                variables.insert(set.clone(), to.clone());
+            }
+            Effect::Add { add, by } => {
+                let value = variables.entry(add.clone()).or_insert(Value::Int(0));
+                match value {
+                    Value::Int(n) => *n = n.wrapping_add(*by),
+                    _ => return Err(format!("can't add to {:?}, it's {}", add, value.kind())),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Someone on stage during a scene
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Character {
+    pub character: String,
+    #[serde(default, skip_serializing_if = "Slot::is_center")]
+    pub slot: Slot,
+    // a variation on their picture, like "worried"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    // how they come on if they weren't on stage in the scene before
+    #[serde(default, skip_serializing_if = "Transition::is_cut")]
+    pub enter: Transition,
+    // how they go off if they aren't on stage in the scene after
+    #[serde(default, skip_serializing_if = "Transition::is_cut")]
+    pub exit: Transition,
+}
+
+// Where on stage a character stands
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Slot {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+impl Slot {
+    fn is_center(&self) -> bool {
+        *self == Slot::Center
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Transition {
+    // just there, or just gone
+    #[default]
+    Cut,
+    Fade,
+    // in from, or out to, the side of the screen
+    Slide,
+}
+
+impl Transition {
+    fn is_cut(&self) -> bool {
+        *self == Transition::Cut
+    }
+}
+
+impl Character {
+    pub fn new(character: &str, slot: Slot) -> Self {
+        Self {
+            character: character.to_string(),
+            slot,
+            expression: None,
+            enter: Transition::Cut,
+            exit: Transition::Cut,
+        }
+    }
+
+    pub fn with_expression(mut self, expression: &str) -> Self {
+        self.expression = Some(expression.to_string());
+        self
+    }
+
+    pub fn with_transitions(mut self, enter: Transition, exit: Transition) -> Self {
+        self.enter = enter;
+        self.exit = exit;
+        self
+    }
+
+    // Name of the picture for how they look right now: "Marlin" or "Marlin_worried"
+    pub fn sprite(&self) -> String {
+        match &self.expression {
+            Some(expression) => format!("{}_{}", self.character, expression),
+            None => self.character.clone(),
+        }
+    }
+}
+
+// Scene as it's written in the file
+#[derive(Serialize, Deserialize)]
+struct SceneJson {
+    name: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    responses: Vec<Response>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    effects: Vec<Effect>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    background: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stage: Option<Vec<Character>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    music: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sfx: Option<String>,
+}
+
+impl TryFrom<SceneJson> for Scene {
+    type Error = String;
+
+    fn try_from(json: SceneJson) -> Result<Self, String> {
+        let mut responses = json.responses;
+        if json.next.is_some() && !responses.is_empty() {
+            // one of them would have to be thrown away
+            return Err(format!(
+                "scene {:?} has both \"next\" and \"responses\"",
+                json.name
+            ));
+        }
+        let next = if let Some(goto) = json.next {
+            Next::Continue(goto)
+        } else if responses.len() == 1
+            && responses[0].response.is_empty()
+            && responses[0].response_id.is_none()
+        {
+            // older stories carry on with a single response that has no text
+            Next::Continue(responses.remove(0).goto)
+        } else if responses.is_empty() {
+            Next::End
+        } else {
+            Next::Choice(responses)
+        };
+        Ok(Self {
+            name: json.name,
+            message: json.message,
+            message_id: json.message_id,
+            next,
+            effects: json.effects,
+            background: json.background,
+            stage: json.stage,
+            music: json.music,
+            sfx: json.sfx,
+        })
+    }
+}
+
+impl From<Scene> for SceneJson {
+    fn from(scene: Scene) -> Self {
+        let (responses, next) = match scene.next {
+            Next::Continue(goto) => (vec![], Some(goto)),
+            Next::Choice(responses) => (responses, None),
+            Next::End => (vec![], None),
+        };
+        Self {
+            name: scene.name,
+            message: scene.message,
+            message_id: scene.message_id,
+            responses,
+            next,
+            effects: scene.effects,
+            background: scene.background,
+            stage: scene.stage,
+            music: scene.music,
+            sfx: scene.sfx,
+        }
+    }
+}
+
+impl Story {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let json = fs::read_to_string(path).map_err(LoadError::Io)?;
+        Self::from_json(&json).map_err(|err| {
+            // serde_json puts the position on the end of its message, but it's wanted up front
+            let message = err.to_string();
+            let suffix = format!(" at line {} column {}", err.line(), err.column());
+            LoadError::Parse {
+                line: err.line(),
+                column: err.column(),
+                message: message.trim_end_matches(&suffix).to_string(),
+            }
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    // First scene with the given scene_name
+    pub fn scene(&self, scene_name: &str) -> Option<&Scene> {
+        self.scenes
+            .iter()
+            .find(|named| named.scene_name == scene_name)
+            .map(|named| &named.scene)
+    }
+
+    // Every message_id and response_id in the story
+    pub fn string_ids(&self) -> BTreeSet<&str> {
+        let mut ids = BTreeSet::new();
+        for named in self.scenes.iter() {
+            ids.extend(named.scene.message_id.as_deref());
+            for response in named.scene.choices() {
+                ids.extend(response.response_id.as_deref());
+            }
+        }
+        ids
+    }
+}
+
+impl Scene {
+    pub fn new(name: &str, message: &str, next: Next) -> Self {
+        Self {
+            name: name.to_string(),
+            message: message.to_string(),
+            message_id: None,
+            next,
+            effects: vec![],
+            background: None,
+            stage: None,
+            music: None,
+            sfx: None,
+        }
+    }
+
+    pub fn with_message_id(mut self, id: &str) -> Self {
+        self.message_id = Some(id.to_string());
+        self
+    }
+
+    pub fn with_music(mut self, music: &str) -> Self {
+        self.music = Some(music.to_string());
+        self
+    }
+
+    pub fn with_sfx(mut self, sfx: &str) -> Self {
+        self.sfx = Some(sfx.to_string());
+        self
+    }
+
+    pub fn with_effects(mut self, effects: Vec<Effect>) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    pub fn with_background(mut self, background: &str) -> Self {
+        self.background = Some(background.to_string());
+        self
+    }
+
+    pub fn with_stage(mut self, stage: Vec<Character>) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+
+    // Who's on screen: the stage if the scene has one, otherwise the speaker, in the center
+    pub fn cast(&self) -> Vec<Character> {
+        match &self.stage {
+            Some(stage) => stage.clone(),
+            None if self.name.is_empty() => vec![],
+            None => vec![Character::new(&self.name, Slot::Center)],
+        }
+    }
+
+    // The responses to pick from, empty if the scene doesn't ask
+    pub fn choices(&self) -> &[Response] {
+        match &self.next {
+            Next::Choice(responses) => responses,
+            _ => &[],
+        }
+    }
+
+    // Every scene this one can lead to, in order
+    pub fn targets(&self) -> Vec<&str> {
+        match &self.next {
+            Next::Continue(goto) => vec![goto.as_str()],
+            Next::Choice(responses) => responses.iter().map(|r| r.goto.as_str()).collect(),
+            Next::End => vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    // line and column start at 1
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "{}", err),
+            LoadError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {} column {}: {}", line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl Response {
+    pub fn new(response: &str, goto: &str) -> Self {
+        Self {
+            response: response.to_string(),
+            response_id: None,
+            goto: goto.to_string(),
+            condition: None,
+            when_false: WhenFalse::Hide,
+            effects: vec![],
+            music: None,
+            sfx: None,
+        }
+    }
+
+    pub fn with_music(mut self, music: &str) -> Self {
+        self.music = Some(music.to_string());
+        self
+    }
+
+    pub fn with_sfx(mut self, sfx: &str) -> Self {
+        self.sfx = Some(sfx.to_string());
+        self
+    }
+
+    pub fn with_response_id(mut self, id: &str) -> Self {
+        self.response_id = Some(id.to_string());
+        self
+    }
+
+    pub fn with_condition(mut self, condition: Expr, when_false: WhenFalse) -> Self {
+        self.condition = Some(condition);
+        self.when_false = when_false;
+        self
+    }
+
+    pub fn with_effects(mut self, effects: Vec<Effect>) -> Self {
+        self.effects = effects;
+        self
+    }
+}
+
+// A response the way the player sees it right now
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Choice {
+    // where it is in the scene's responses
+    pub index: usize,
+    pub text: String,
+    pub enabled: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DialogueError {
+    // a goto, or the starting scene, names a scene the story doesn't have
+    UnknownScene(String),
+    // the choice is there but its condition is false
+    Disabled(usize),
+    // there's no choice with this index
+    NoSuchChoice(usize),
+    // advance was called on a scene that doesn't carry on by itself
+    NotContinue(String),
+    // an effect tried to add to something that isn't a number
+    Effect(String),
+}
+
+impl fmt::Display for DialogueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DialogueError::UnknownScene(name) => write!(f, "no scene named {:?}", name),
+            DialogueError::Disabled(choice) => write!(f, "choice {} can't be picked", choice),
+            DialogueError::NoSuchChoice(choice) => write!(f, "there's no choice {}", choice),
+            DialogueError::NotContinue(scene) => {
+                write!(f, "scene {:?} doesn't carry on by itself", scene)
+            }
+            DialogueError::Effect(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DialogueError {}
+
+// A scene the player has already been through
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entry {
+    pub scene: String,
+    pub speaker: String,
+    // as it was shown
+    pub message: String,
+    // what the player picked, None if the scene carried on by itself
+    pub choice: Option<String>,
+    // as they were on the way into the scene, for going back to it
+    variables: BTreeMap<String, Value>,
+    music: Option<String>,
+}
+
+// Walks through a story a scene at a time, keeping track of the story's variables and
+// of the scenes that have been left behind. Nothing here draws, so games show speaker(),
+// message() and choices() however they like and call advance or choose when the player
+// does something.
+#[derive(Clone)]
+pub struct Runner {
+    story: Rc<Story>,
+    current: String,
+    variables: BTreeMap<String, Value>,
+    // the variables just after the current scene's effects happened
+    entered_with: BTreeMap<String, Value>,
+    history: Vec<Entry>,
+    // the last music the story asked for
+    music: Option<String>,
+    // sfx waiting for take_sounds
+    sounds: Vec<String>,
+    // for message_id and response_id
+    strings: Option<Rc<Strings>>,
+}
+
+impl Runner {
+    pub fn new(story: &Rc<Story>, start: &str) -> Result<Self, DialogueError> {
+        let mut runner = Self {
+            story: Rc::clone(story),
+            current: String::new(),
+            variables: story.variables.clone(),
+            entered_with: BTreeMap::new(),
+            history: vec![],
+            music: None,
+            sounds: vec![],
+            strings: None,
+        };
+        runner.goto(start)?;
+        Ok(runner)
+    }
+
+    pub fn story(&self) -> &Rc<Story> {
+        &self.story
+    }
+
+    // scene_name of the current scene
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    pub fn scene(&self) -> &Scene {
+        // only ever set to names that were checked
+        self.story.scene(&self.current).unwrap()
+    }
+
+    pub fn variables(&self) -> &BTreeMap<String, Value> {
+        &self.variables
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: Value) {
+        self.variables.insert(name.to_string(), value);
+    }
+
+    // Where message_id and response_id are looked up, None to always use the story's own text
+    pub fn set_strings(&mut self, strings: Option<Rc<Strings>>) {
+        self.strings = strings;
+    }
+
+    pub fn strings(&self) -> Option<&Rc<Strings>> {
+        self.strings.as_ref()
+    }
+
+    // The string for id if there is one, otherwise text
+    fn localize<'a>(&'a self, id: &Option<String>, text: &'a str) -> &'a str {
+        id.as_deref()
+            .zip(self.strings.as_ref())
+            .and_then(|(id, strings)| strings.get(id))
+            .unwrap_or(text)
+    }
+
+    // The current scene's message, in the runner's language, with the {...} in it filled in
+    pub fn message(&self) -> String {
+        let scene = self.scene();
+        interpolate(
+            self.localize(&scene.message_id, &scene.message),
+            &self.variables,
+        )
+    }
+
+    // The responses whose conditions don't hide them, in order.
+    // A condition that can't be worked out counts as false.
+    pub fn choices(&self) -> Vec<Choice> {
+        self.scene()
+            .choices()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, response)| {
+                let holds = response.condition.as_ref().map_or(true, |condition| {
+                    condition.eval(&self.variables) == Ok(Value::Bool(true))
+                });
+                if !holds && response.when_false == WhenFalse::Hide {
+                    return None;
+                }
+                Some(Choice {
+                    index,
+                    text: interpolate(
+                        self.localize(&response.response_id, &response.response),
+                        &self.variables,
+                    ),
+                    enabled: holds,
+                })
+            })
+            .collect()
+    }
+
+    pub fn is_end(&self) -> bool {
+        self.scene().next == Next::End
+    }
+
+    // What should be playing: the music the story last asked for, "" for silence, or None
+    // if it hasn't asked for any yet
+    pub fn music(&self) -> Option<&str> {
+        self.music.as_deref()
+    }
+
+    // For putting the music back too, e.g. after restore
+    pub fn set_music(&mut self, music: Option<String>) {
+        self.music = music;
+    }
+
+    // The sfx of the responses picked and scenes gone to since the last call, in order
+    pub fn take_sounds(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.sounds)
+    }
+
+    // Jump straight to a scene, e.g. back to the start. Its effects happen and its
+    // music and sfx start.
+    pub fn goto(&mut self, scene_name: &str) -> Result<(), DialogueError> {
+        self.enter(
+            scene_name,
+            self.variables.clone(),
+            self.music.clone(),
+            self.sounds.clone(),
+        )
+    }
+
+    // Go to a scene, with the variables, music and sounds as they'd be on the way in.
+    // Nothing changes unless the scene is there and all of its effects work.
+    fn enter(
+        &mut self,
+        scene_name: &str,
+        mut variables: BTreeMap<String, Value>,
+        music: Option<String>,
+        mut sounds: Vec<String>,
+    ) -> Result<(), DialogueError> {
+        let scene = self
+            .story
+            .scene(scene_name)
+            .ok_or_else(|| DialogueError::UnknownScene(scene_name.to_string()))?;
+        for effect in scene.effects.iter() {
+            effect
+                .apply(&mut variables)
+                .map_err(DialogueError::Effect)?;
+        }
+        sounds.extend(scene.sfx.iter().cloned());
+        self.music = scene.music.clone().or(music);
+        self.current = scene_name.to_string();
+        self.entered_with = variables.clone();
+        self.variables = variables;
+        self.sounds = sounds;
+        Ok(())
+    }
+
+    // Swap in a new version of the story, e.g. after the file was edited. The runner stays
+    // on the current scene if the new story still has it and goes to fallback if not.
+    // Variables keep their values, and ones the new story adds get their starting values.
+    // Returns whether the current scene was kept; either way no effects happen.
+    pub fn reload(&mut self, story: &Rc<Story>, fallback: &str) -> Result<bool, DialogueError> {
+        let kept = story.scene(&self.current).is_some();
+        if !kept && story.scene(fallback).is_none() {
+            return Err(DialogueError::UnknownScene(fallback.to_string()));
+        }
+        self.story = Rc::clone(story);
+        if !kept {
+            self.current = fallback.to_string();
+        }
+        self.add_missing_variables();
+        Ok(kept)
+    }
+
+    // Put the runner back where it was, e.g. from a save: at scene_name with these variables,
+    // plus starting values for any the story declares that aren't there. No effects happen,
+    // and the history starts over.
+    pub fn restore(
+        &mut self,
+        scene_name: &str,
+        variables: BTreeMap<String, Value>,
+    ) -> Result<(), DialogueError> {
+        if self.story.scene(scene_name).is_none() {
+            return Err(DialogueError::UnknownScene(scene_name.to_string()));
+        }
+        self.current = scene_name.to_string();
+        self.variables = variables;
+        self.add_missing_variables();
+        self.entered_with = self.variables.clone();
+        self.history.clear();
+        Ok(())
+    }
+
+    // Oldest first. advance and choose add to it, and it's cleared by restart and restore.
+    pub fn history(&self) -> &[Entry] {
+        &self.history
+    }
+
+    // Go back to the scene of history()[to], with the variables and music as they were then,
+    // and forget everything that's happened since. Panics if there's no such entry.
+    pub fn rollback(&mut self, to: usize) -> Result<(), DialogueError> {
+        let entry = &self.history[to];
+        if self.story.scene(&entry.scene).is_none() {
+            // the story was reloaded without it
+            return Err(DialogueError::UnknownScene(entry.scene.clone()));
+        }
+        self.current = entry.scene.clone();
+        self.variables = entry.variables.clone();
+        self.music = entry.music.clone();
+        self.add_missing_variables();
+        self.entered_with = self.variables.clone();
+        self.history.truncate(to);
+        Ok(())
+    }
+
+    // The current scene, for the history
+    fn entry(&self, choice: Option<String>) -> Entry {
+        Entry {
+            scene: self.current.clone(),
+            speaker: self.scene().name.clone(),
+            message: self.message(),
+            choice,
+            variables: self.entered_with.clone(),
+            music: self.music.clone(),
+        }
+    }
+
+    // Starting values for the variables the story declares that the runner doesn't have
+    fn add_missing_variables(&mut self) {
+        for (name, value) in self.story.variables.iter() {
+            self.variables
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    // Start over at start, with the variables back to how the story declares them
+    pub fn restart(&mut self, start: &str) -> Result<(), DialogueError> {
+        self.enter(start, self.story.variables.clone(), None, vec![])?;
+        self.history.clear();
+        Ok(())
+    }
+
+    // Leave a Continue scene
+    pub fn advance(&mut self) -> Result<(), DialogueError> {
+        let goto = match &self.scene().next {
+            Next::Continue(goto) => goto.clone(),
+            _ => return Err(DialogueError::NotContinue(self.current.clone())),
+        };
+        let entry = self.entry(None);
+        self.goto(&goto)?;
+        self.history.push(entry);
+        Ok(())
+    }
+
+    // Pick one of choices()
+    pub fn choose(&mut self, choice: usize) -> Result<(), DialogueError> {
+        let choices = self.choices();
+        if choice >= choices.len() {
+            return Err(DialogueError::NoSuchChoice(choice));
+        }
+        if !choices[choice].enabled {
+            return Err(DialogueError::Disabled(choice));
+        }
+        let story = Rc::clone(&self.story);
+        let response = &story.scene(&self.current).unwrap().choices()[choices[choice].index];
+        if story.scene(&response.goto).is_none() {
+            return Err(DialogueError::UnknownScene(response.goto.clone()));
+        }
+        let entry = self.entry(Some(choices[choice].text.clone()));
+        let mut variables = self.variables.clone();
+        for effect in response.effects.iter() {
+            effect
+                .apply(&mut variables)
+                .map_err(DialogueError::Effect)?;
+        }
+        let music = response.music.clone().or_else(|| self.music.clone());
+        let mut sounds = self.sounds.clone();
+        sounds.extend(response.sfx.iter().cloned());
+        self.enter(&response.goto, variables, music, sounds)?;
+        self.history.push(entry);
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+// Plays through every path a story has, the way a player would: carrying on by itself
+// from scenes with "next" and trying each response that can be picked in the others.
+// Stories are tested with it, so an edit can't leave a path that goes wrong in the game or
+// leaves the player with nothing to pick.
+//
+// The number of paths can grow exponentially with how many choices a story has, so there's
+// a limit on how long a path can be and a budget for how many scenes a run visits.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::rc::Rc;
+
+use super::{DialogueError, Next, Runner, Story, Value};
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Exploration {
+    // how many paths end at each ending
+    pub endings: BTreeMap<String, usize>,
+    // the scenes along the shortest and longest paths to an ending, in order
+    pub shortest: Vec<String>,
+    pub longest: Vec<String>,
+    // scenes that a path can go round and come back to with nothing changed, from the
+    // one that's first alphabetically
+    pub loops: Vec<Vec<String>>,
+    pub failures: Vec<Failure>,
+    // paths given up on for going on longer than the limit
+    pub cut_off: usize,
+    // the run stopped after visiting its budget of scenes, so some paths weren't tried
+    pub out_of_budget: bool,
+}
+
+impl Exploration {
+    // how many paths there are from the start to an ending
+    pub fn paths(&self) -> usize {
+        self.endings.values().sum()
+    }
+}
+
+// A path the game can't get to the end of
+#[derive(Clone, PartialEq, Debug)]
+pub struct Failure {
+    pub path: Vec<String>,
+    pub trouble: Trouble,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Trouble {
+    // advance or choose went wrong, which leaves the game stuck on the scene
+    Error(DialogueError),
+    // a scene asks the player to pick, but every response is hidden or disabled
+    Stuck,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = self.path.join(" > ");
+        match &self.trouble {
+            Trouble::Error(err) => write!(f, "{}: {}", path, err),
+            Trouble::Stuck => write!(f, "{}: nothing can be picked", path),
+        }
+    }
+}
+
+// Every path from start, each no more than limit scenes long, visiting at most budget
+// scenes in all
+pub fn explore(
+    story: &Rc<Story>,
+    start: &str,
+    limit: usize,
+    budget: usize,
+) -> Result<Exploration, DialogueError> {
+    let mut explorer = Explorer {
+        limit,
+        budget,
+        path: vec![],
+        loops: BTreeSet::new(),
+        found: Exploration::default(),
+    };
+    explorer.walk(Runner::new(story, start)?);
+    let mut found = explorer.found;
+    found.loops = explorer.loops.into_iter().collect();
+    Ok(found)
+}
+
+struct Explorer {
+    limit: usize,
+    // scenes left to visit
+    budget: usize,
+    // the scenes so far, with the variables each was left with
+    path: Vec<(String, BTreeMap<String, Value>)>,
+    loops: BTreeSet<Vec<String>>,
+    found: Exploration,
+}
+
+impl Explorer {
+    fn scenes(&self) -> Vec<String> {
+        self.path.iter().map(|(scene, _)| scene.clone()).collect()
+    }
+
+    fn walk(&mut self, runner: Runner) {
+        let state = (runner.current().to_string(), runner.variables().clone());
+        // back where it's been with nothing changed, so it'd only go round again
+        if let Some(i) = self.path.iter().position(|visited| *visited == state) {
+            let mut scenes: Vec<String> = self.path[i..].iter().map(|(s, _)| s.clone()).collect();
+            let first = (0..scenes.len()).min_by_key(|&j| &scenes[j]).unwrap();
+            scenes.rotate_left(first);
+            self.loops.insert(scenes);
+            return;
+        }
+        if self.path.len() >= self.limit {
+            self.found.cut_off += 1;
+            return;
+        }
+        if self.budget == 0 {
+            self.found.out_of_budget = true;
+            return;
+        }
+        self.budget -= 1;
+        self.path.push(state);
+        match &runner.scene().next {
+            Next::End => self.end(),
+            Next::Continue(_) => {
+                let mut next = runner.clone();
+                self.step(next.advance(), next);
+            }
+            Next::Choice(_) => {
+                let choices = runner.choices();
+                if !choices.iter().any(|choice| choice.enabled) {
+                    self.fail(Trouble::Stuck);
+                }
+                for (i, choice) in choices.iter().enumerate() {
+                    if choice.enabled {
+                        let mut next = runner.clone();
+                        self.step(next.choose(i), next);
+                    }
+                }
+            }
+        }
+        self.path.pop();
+    }
+
+    fn step(&mut self, result: Result<(), DialogueError>, next: Runner) {
+        match result {
+            Ok(()) => self.walk(next),
+            Err(err) => self.fail(Trouble::Error(err)),
+        }
+    }
+
+    fn end(&mut self) {
+        let scenes = self.scenes();
+        let found = &mut self.found;
+        *found
+            .endings
+            .entry(scenes[scenes.len() - 1].clone())
+            .or_insert(0) += 1;
+        if found.shortest.is_empty() || scenes.len() < found.shortest.len() {
+            found.shortest = scenes.clone();
+        }
+        if scenes.len() > found.longest.len() {
+            found.longest = scenes;
+        }
+    }
+
+    fn fail(&mut self, trouble: Trouble) {
+        let path = self.scenes();
+        self.found.failures.push(Failure { path, trouble });
+    }
+}
@@ -0,0 +1,424 @@
+// The little expression language used in response conditions and in {...} in scene text.
+// It has whole numbers, true and false, "strings", variable names, ( ), and
+//
+//     !  -  (in front)      +  -      ==  !=  <  <=  >  >=      &&      ||
+//
+// from tightest to loosest. + also joins strings, and && and || skip their right side
+// when the left side already decides the answer.
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(untagged)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+}
+
+impl Value {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Value::Int(_) => Kind::Int,
+            Value::Bool(_) => Kind::Bool,
+            Value::Str(_) => Kind::Str,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Int,
+    Bool,
+    Str,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Kind::Int => "a number",
+            Kind::Bool => "true or false",
+            Kind::Str => "a string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Op {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Node {
+    Value(Value),
+    Var(String),
+    Not(Box<Node>),
+    Neg(Box<Node>),
+    Binary(Op, Box<Node>, Box<Node>),
+}
+
+// A parsed expression that remembers how it was written, so it saves back out the same
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct Expr {
+    source: String,
+    node: Node,
+}
+
+impl Expr {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, at: 0 };
+        let node = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.at) {
+            return Err(format!("didn't expect {:?} in {:?}", token, source));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            node,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn eval(&self, variables: &BTreeMap<String, Value>) -> Result<Value, String> {
+        eval(&self.node, variables)
+    }
+
+    // What kind of value the expression gives, given the kind of each variable,
+    // or what's wrong with it. Unlike eval, this looks at both sides of && and ||.
+    pub fn check(&self, kinds: &BTreeMap<String, Kind>) -> Result<Kind, String> {
+        check(&self.node, kinds)
+    }
+}
+
+impl TryFrom<String> for Expr {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, String> {
+        Expr::parse(&source)
+    }
+}
+
+impl From<Expr> for String {
+    fn from(expr: Expr) -> Self {
+        expr.source
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Int(i64),
+    Str(String),
+    Name(String),
+    // operators and brackets
+    Punct(&'static str),
+}
+
+const PUNCT: [&str; 14] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "!", "(", ")", "=",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        let ch = rest.chars().next().unwrap();
+        if ch.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let n = rest[..end]
+                .parse()
+                .map_err(|_| format!("{} is too big", &rest[..end]))?;
+            tokens.push(Token::Int(n));
+            rest = &rest[end..];
+        } else if ch.is_alphabetic() || ch == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if ch == '"' {
+            let end = rest[1..]
+                .find('"')
+                .ok_or_else(|| format!("string isn't closed in {:?}", source))?;
+            tokens.push(Token::Str(rest[1..end + 1].to_string()));
+            rest = &rest[end + 2..];
+        } else if let Some(&punct) = PUNCT.iter().find(|p| rest.starts_with(*p)) {
+            if punct == "=" {
+                return Err(format!("use == to compare in {:?}", source));
+            }
+            tokens.push(Token::Punct(punct));
+            rest = &rest[punct.len()..];
+        } else {
+            return Err(format!("didn't expect {:?} in {:?}", ch, source));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    at: usize,
+}
+
+impl Parser {
+    // Moves past the next token if it's one of these operators
+    fn take(&mut self, puncts: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.at) {
+            Some(Token::Punct(p)) if puncts.contains(p) => {
+                self.at += 1;
+                Some(*p)
+            }
+            _ => None,
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.take(&["||"]).is_some() {
+            node = Node::Binary(Op::Or, Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.compare()?;
+        while self.take(&["&&"]).is_some() {
+            node = Node::Binary(Op::And, Box::new(node), Box::new(self.compare()?));
+        }
+        Ok(node)
+    }
+
+    fn compare(&mut self) -> Result<Node, String> {
+        let node = self.sum()?;
+        let op = match self.take(&["==", "!=", "<", "<=", ">", ">="]) {
+            Some("==") => Op::Eq,
+            Some("!=") => Op::Ne,
+            Some("<") => Op::Lt,
+            Some("<=") => Op::Le,
+            Some(">") => Op::Gt,
+            Some(">=") => Op::Ge,
+            _ => return Ok(node),
+        };
+        Ok(Node::Binary(op, Box::new(node), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while let Some(p) = self.take(&["+", "-"]) {
+            let op = if p == "+" { Op::Add } else { Op::Sub };
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        match self.take(&["!", "-"]) {
+            Some("!") => Ok(Node::Not(Box::new(self.unary()?))),
+            Some(_) => Ok(Node::Neg(Box::new(self.unary()?))),
+            None => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let token = self
+            .tokens
+            .get(self.at)
+            .cloned()
+            .ok_or_else(|| "expression ends too soon".to_string())?;
+        self.at += 1;
+        match token {
+            Token::Int(n) => Ok(Node::Value(Value::Int(n))),
+            Token::Str(s) => Ok(Node::Value(Value::Str(s))),
+            Token::Name(name) if name == "true" => Ok(Node::Value(Value::Bool(true))),
+            Token::Name(name) if name == "false" => Ok(Node::Value(Value::Bool(false))),
+            Token::Name(name) => Ok(Node::Var(name)),
+            Token::Punct("(") => {
+                let node = self.or()?;
+                match self.take(&[")"]) {
+                    Some(_) => Ok(node),
+                    None => Err("missing )".to_string()),
+                }
+            }
+            Token::Punct(p) => Err(format!("didn't expect {:?}", p)),
+        }
+    }
+}
+
+fn eval(node: &Node, variables: &BTreeMap<String, Value>) -> Result<Value, String> {
+    let mismatch = |what: &str| Err(format!("can't {} those", what));
+    match node {
+        Node::Value(value) => Ok(value.clone()),
+        Node::Var(name) => variables
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("no variable named {:?}", name)),
+        Node::Not(node) => match eval(node, variables)? {
+            Value::Bool(b) => Ok(Value::Bool(!b)),
+            _ => mismatch("use ! on"),
+        },
+        Node::Neg(node) => match eval(node, variables)? {
+            Value::Int(n) => Ok(Value::Int(n.wrapping_neg())),
+            _ => mismatch("use - on"),
+        },
+        Node::Binary(op @ Op::And, a, b) | Node::Binary(op @ Op::Or, a, b) => {
+            match eval(a, variables)? {
+                // the left side decides it
+                Value::Bool(a) if a == (*op == Op::Or) => Ok(Value::Bool(a)),
+                Value::Bool(_) => match eval(b, variables)? {
+                    Value::Bool(b) => Ok(Value::Bool(b)),
+                    _ => mismatch("use && or || on"),
+                },
+                _ => mismatch("use && or || on"),
+            }
+        }
+        Node::Binary(op, a, b) => {
+            let (a, b) = (eval(a, variables)?, eval(b, variables)?);
+            match (op, a, b) {
+                (Op::Eq, a, b) if a.kind() == b.kind() => Ok(Value::Bool(a == b)),
+                (Op::Ne, a, b) if a.kind() == b.kind() => Ok(Value::Bool(a != b)),
+                (Op::Add, Value::Int(a), Value::Int(b)) => Ok(Value::Int(a.wrapping_add(b))),
+                (Op::Add, Value::Str(a), Value::Str(b)) => Ok(Value::Str(a + &b)),
+                (Op::Sub, Value::Int(a), Value::Int(b)) => Ok(Value::Int(a.wrapping_sub(b))),
+                (Op::Lt, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a < b)),
+                (Op::Le, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a <= b)),
+                (Op::Gt, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a > b)),
+                (Op::Ge, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a >= b)),
+                (Op::Eq, _, _) | (Op::Ne, _, _) => mismatch("compare"),
+                (Op::Add, _, _) => mismatch("add"),
+                (Op::Sub, _, _) => mismatch("subtract"),
+                _ => mismatch("order"),
+            }
+        }
+    }
+}
+
+fn check(node: &Node, kinds: &BTreeMap<String, Kind>) -> Result<Kind, String> {
+    let want = |node: &Node, kind: Kind, what: &str| {
+        let found = check(node, kinds)?;
+        if found == kind {
+            Ok(kind)
+        } else {
+            Err(format!("{} needs {}, not {}", what, kind, found))
+        }
+    };
+    match node {
+        Node::Value(value) => Ok(value.kind()),
+        Node::Var(name) => kinds
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("no variable named {:?}", name)),
+        Node::Not(node) => want(node, Kind::Bool, "!"),
+        Node::Neg(node) => want(node, Kind::Int, "-"),
+        Node::Binary(op, a, b) => match op {
+            Op::Or | Op::And => {
+                want(a, Kind::Bool, "&& and ||")?;
+                want(b, Kind::Bool, "&& and ||")
+            }
+            Op::Eq | Op::Ne | Op::Add => {
+                let kind = check(a, kinds)?;
+                if *op == Op::Add && kind == Kind::Bool {
+                    return Err(format!("+ needs {} or {}", Kind::Int, Kind::Str));
+                }
+                want(b, kind, if *op == Op::Add { "+" } else { "comparing" })?;
+                Ok(if *op == Op::Add { kind } else { Kind::Bool })
+            }
+            Op::Sub => {
+                want(a, Kind::Int, "-")?;
+                want(b, Kind::Int, "-")
+            }
+            Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+                want(a, Kind::Int, "ordering")?;
+                want(b, Kind::Int, "ordering")?;
+                Ok(Kind::Bool)
+            }
+        },
+    }
+}
+
+// Text with each {expression} swapped for its value. {{ and }} are plain braces,
+// and anything in braces that doesn't work out is left the way it was written.
+pub fn interpolate(text: &str, variables: &BTreeMap<String, Value>) -> String {
+    let mut out = String::with_capacity(text.len());
+    for piece in pieces(text) {
+        match piece {
+            Piece::Text(s) => out.push_str(s),
+            Piece::Expr(inner) => match Expr::parse(inner).and_then(|e| e.eval(variables)) {
+                Ok(value) => out.push_str(&value.to_string()),
+                Err(_) => {
+                    out.push('{');
+                    out.push_str(inner);
+                    out.push('}');
+                }
+            },
+        }
+    }
+    out
+}
+
+// The {expressions} in some text, for checking them
+pub(crate) fn embedded(text: &str) -> Vec<&str> {
+    pieces(text)
+        .into_iter()
+        .filter_map(|piece| match piece {
+            Piece::Expr(inner) => Some(inner),
+            Piece::Text(_) => None,
+        })
+        .collect()
+}
+
+enum Piece<'t> {
+    Text(&'t str),
+    Expr(&'t str),
+}
+
+fn pieces(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = vec![];
+    let mut rest = text;
+    while let Some(i) = rest.find(['{', '}']) {
+        pieces.push(Piece::Text(&rest[..i]));
+        let after = &rest[i + 1..];
+        if after.starts_with(&rest[i..i + 1]) {
+            // doubled brace
+            pieces.push(Piece::Text(&rest[i..i + 1]));
+            rest = &after[1..];
+        } else if let (true, Some(end)) = (rest[i..].starts_with('{'), after.find('}')) {
+            pieces.push(Piece::Expr(&after[..end]));
+            rest = &after[end + 1..];
+        } else {
+            // a brace on its own
+            pieces.push(Piece::Text(&rest[i..i + 1]));
+            rest = after;
+        }
+    }
+    pieces.push(Piece::Text(rest));
+    pieces
+}
@@ -0,0 +1,280 @@
+// Draws a story's scenes and the links between them as a Graphviz DOT or Mermaid graph, so
+// its shape can be looked over without playing every path.
+//
+// Each scene is a box with its name, who's speaking and the start of its message, and each
+// response an arrow labelled with what the player picks (dashed if it has a condition).
+// Endings get a double border, dead ends (scenes that can't get to any ending) are red and
+// scenes that can't be reached from the start are grey and dashed. A goto to a scene that
+// doesn't exist points at a red "missing" node.
+use std::collections::{HashMap, VecDeque};
+
+use super::{Next, Story};
+
+// how many characters of a message or response go in a label
+const LABEL_LENGTH: usize = 32;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+struct Node {
+    id: String,
+    scene_name: String,
+    // speaker and message, or None for a scene that isn't there
+    text: Option<(String, String)>,
+    ending: bool,
+    dead_end: bool,
+    unreachable: bool,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    label: String,
+    conditional: bool,
+}
+
+pub fn graph(story: &Story, start: &str, format: GraphFormat) -> String {
+    let (nodes, edges) = build(story, start);
+    match format {
+        GraphFormat::Dot => dot(story, &nodes, &edges),
+        GraphFormat::Mermaid => mermaid(story, &nodes, &edges),
+    }
+}
+
+fn build(story: &Story, start: &str) -> (Vec<Node>, Vec<Edge>) {
+    let mut nodes: Vec<Node> = vec![];
+    // first scene with each name, the same one Story::scene finds
+    let mut index = HashMap::new();
+    for named in story.scenes.iter() {
+        let scene = &named.scene;
+        index.entry(named.scene_name.clone()).or_insert(nodes.len());
+        nodes.push(Node {
+            id: format!("s{}", nodes.len()),
+            scene_name: named.scene_name.clone(),
+            text: Some((scene.name.clone(), scene.message.clone())),
+            ending: scene.next == Next::End,
+            dead_end: false,
+            unreachable: false,
+        });
+    }
+
+    let mut edges = vec![];
+    for (from, named) in story.scenes.iter().enumerate() {
+        let links: Vec<(&str, &str, bool)> = match &named.scene.next {
+            Next::Continue(goto) => vec![(goto, "", false)],
+            Next::Choice(responses) => responses
+                .iter()
+                .map(|r| {
+                    // a response that's only in the strings goes by its ID
+                    let label = match &r.response_id {
+                        Some(id) if r.response.is_empty() => id,
+                        _ => &r.response,
+                    };
+                    (r.goto.as_str(), label.as_str(), r.condition.is_some())
+                })
+                .collect(),
+            Next::End => vec![],
+        };
+        for (goto, label, conditional) in links {
+            let to = *index.entry(goto.to_string()).or_insert_with(|| {
+                nodes.push(Node {
+                    id: format!("s{}", nodes.len()),
+                    scene_name: goto.to_string(),
+                    text: None,
+                    ending: false,
+                    dead_end: false,
+                    unreachable: false,
+                });
+                nodes.len() - 1
+            });
+            edges.push(Edge {
+                from,
+                to,
+                label: label.to_string(),
+                conditional,
+            });
+        }
+    }
+
+    let mut reached = vec![false; nodes.len()];
+    search(&mut reached, index.get(start).copied(), |i| {
+        edges.iter().filter(|e| e.from == i).map(|e| e.to).collect()
+    });
+    let mut ends = vec![false; nodes.len()];
+    for i in (0..nodes.len()).filter(|&i| nodes[i].ending) {
+        search(&mut ends, Some(i), |i| {
+            edges.iter().filter(|e| e.to == i).map(|e| e.from).collect()
+        });
+    }
+    for (i, node) in nodes.iter_mut().enumerate() {
+        if node.text.is_some() {
+            node.unreachable = !reached[i];
+            node.dead_end = !ends[i];
+        }
+    }
+    (nodes, edges)
+}
+
+// Marks everything next leads to from first, breadth first
+fn search<F>(seen: &mut [bool], first: Option<usize>, next: F)
+where
+    F: Fn(usize) -> Vec<usize>,
+{
+    let mut queue: VecDeque<usize> = first.into_iter().collect();
+    for &i in queue.iter() {
+        seen[i] = true;
+    }
+    while let Some(i) = queue.pop_front() {
+        for j in next(i) {
+            if !seen[j] {
+                seen[j] = true;
+                queue.push_back(j);
+            }
+        }
+    }
+}
+
+// The first LABEL_LENGTH characters of text, all on one line
+fn truncate(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= LABEL_LENGTH {
+        text
+    } else {
+        let mut short: String = text.chars().take(LABEL_LENGTH - 1).collect();
+        short.push('…');
+        short
+    }
+}
+
+// The lines of a node's label
+fn label(node: &Node) -> Vec<String> {
+    match &node.text {
+        Some((speaker, message)) if speaker.is_empty() => {
+            vec![node.scene_name.clone(), truncate(message)]
+        }
+        Some((speaker, message)) => vec![
+            node.scene_name.clone(),
+            format!("{}: {}", speaker, truncate(message)),
+        ],
+        None => vec![format!("missing: {}", node.scene_name)],
+    }
+}
+
+fn dot(story: &Story, nodes: &[Node], edges: &[Edge]) -> String {
+    let quote = |text: &str| {
+        let text = text
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        format!("\"{}\"", text)
+    };
+    let mut out = format!("digraph {} {{\n", quote(&story.story_name));
+    out += "    node [shape=box];\n";
+    for node in nodes.iter() {
+        let mut attributes = vec![format!("label={}", quote(&label(node).join("\n")))];
+        let mut style = vec![];
+        if node.text.is_none() {
+            attributes.push("shape=plaintext, fontcolor=red".to_string());
+        }
+        if node.ending {
+            attributes.push("peripheries=2".to_string());
+        }
+        if node.dead_end {
+            style.push("filled");
+            attributes.push("color=red, fillcolor=\"#f4cccc\"".to_string());
+        }
+        if node.unreachable {
+            style.push("dashed");
+            attributes.push("fontcolor=grey40".to_string());
+        }
+        if !style.is_empty() {
+            attributes.push(format!("style={}", quote(&style.join(","))));
+        }
+        out += &format!("    {} [{}];\n", node.id, attributes.join(", "));
+    }
+    for edge in edges.iter() {
+        let mut attributes = vec![];
+        if !edge.label.is_empty() {
+            attributes.push(format!("label={}", quote(&truncate(&edge.label))));
+        }
+        if edge.conditional {
+            attributes.push("style=dashed".to_string());
+        }
+        out += &format!("    {} -> {}", nodes[edge.from].id, nodes[edge.to].id);
+        if !attributes.is_empty() {
+            out += &format!(" [{}]", attributes.join(", "));
+        }
+        out += ";\n";
+    }
+    out += "}\n";
+    out
+}
+
+fn mermaid(story: &Story, nodes: &[Node], edges: &[Edge]) -> String {
+    // Mermaid has its own entities for what would break out of a quoted label
+    let quote = |text: &str| {
+        let text = text
+            .replace('#', "#35;")
+            .replace('"', "#quot;")
+            .replace('<', "#lt;")
+            .replace('>', "#gt;");
+        format!("\"{}\"", text)
+    };
+    let mut out = format!("%% {}\nflowchart TD\n", story.story_name);
+    for node in nodes.iter() {
+        let text = quote(&label(node).join("\n")).replace('\n', "<br/>");
+        let (open, close) = if node.ending {
+            ("([", "])")
+        } else {
+            ("[", "]")
+        };
+        out += &format!("    {}{}{}{}\n", node.id, open, text, close);
+    }
+    for edge in edges.iter() {
+        let arrow = if edge.conditional { "-.->" } else { "-->" };
+        let from = &nodes[edge.from].id;
+        let to = &nodes[edge.to].id;
+        if edge.label.is_empty() {
+            out += &format!("    {} {} {}\n", from, arrow, to);
+        } else {
+            let label = quote(&truncate(&edge.label));
+            out += &format!("    {} {}|{}| {}\n", from, arrow, label, to);
+        }
+    }
+    out += "    classDef ending stroke-width:3px\n";
+    out += "    classDef deadend fill:#f4cccc,stroke:#c00\n";
+    out += "    classDef unreachable color:#666,stroke-dasharray:5 5\n";
+    out += "    classDef missing fill:none,stroke:none,color:#c00\n";
+    for class in ["ending", "deadend", "unreachable", "missing"].iter() {
+        let ids: Vec<&str> = nodes
+            .iter()
+            .filter(|node| classes(node).contains(class))
+            .map(|node| node.id.as_str())
+            .collect();
+        if !ids.is_empty() {
+            out += &format!("    class {} {}\n", ids.join(","), class);
+        }
+    }
+    out
+}
+
+// The Mermaid classDefs node gets
+fn classes(node: &Node) -> Vec<&'static str> {
+    let mut classes = vec![];
+    if node.ending {
+        classes.push("ending");
+    }
+    if node.dead_end {
+        classes.push("deadend");
+    }
+    if node.unreachable {
+        classes.push("unreachable");
+    }
+    if node.text.is_none() {
+        classes.push("missing");
+    }
+    classes
+}
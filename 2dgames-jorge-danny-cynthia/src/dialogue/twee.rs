@@ -0,0 +1,317 @@
+// Reads stories drafted in Twine and saved as Twee 3
+// (https://github.com/iftechfoundation/twine-specs), like
+//
+//     :: StoryTitle
+//     The Treasure
+//
+//     :: intro [Nemo] {"position":"100,100"}
+//     Where should we look? [[Over there->reef]] or [[the wreck]]?
+//
+// Each passage becomes a scene, with its first tag as the speaker and its links as the
+// responses. Links can be [[target]], [[text->target]], [[target<-text]] or [[text|target]].
+// A line that's nothing but links is left out of the message; a link in the middle of a
+// sentence leaves its text behind. A passage with no links is an ending.
+//
+// Twine can do a lot nemo can't, so anything that doesn't carry over is a TweeProblem.
+use std::fmt;
+
+use super::{NamedScene, Next, Response, Scene, Story};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TweeProblem {
+    // only the first passage with the name is imported
+    DuplicatePassage(String),
+    // scripts, stylesheets and the passages story formats run by themselves
+    SkippedPassage(String),
+    // tags after the first, which is the speaker
+    ExtraTags { passage: String, tags: Vec<String> },
+    // macros, variables and HTML, left in the message as they are
+    Markup { passage: String, markup: String },
+    // what a link sets on the way, e.g. [[text->target][$x to 1]]; the link is kept without it
+    Setter { passage: String, setter: String },
+    // nemo shows each message as one paragraph
+    LineBreaks(String),
+    // the story starts at this passage, but nemo starts at intro
+    Start(String),
+    BadStoryData(String),
+}
+
+impl fmt::Display for TweeProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TweeProblem::DuplicatePassage(passage) => {
+                write!(f, "more than one passage is named {:?}", passage)
+            }
+            TweeProblem::SkippedPassage(passage) => {
+                write!(f, "passage {:?} has nothing to turn into", passage)
+            }
+            TweeProblem::ExtraTags { passage, tags } => {
+                write!(f, "passage {:?} has other tags too: {:?}", passage, tags)
+            }
+            TweeProblem::Markup { passage, markup } => {
+                write!(f, "passage {:?} has markup left in: {:?}", passage, markup)
+            }
+            TweeProblem::Setter { passage, setter } => {
+                write!(f, "a link in passage {:?} sets {:?}", passage, setter)
+            }
+            TweeProblem::LineBreaks(passage) => {
+                write!(f, "passage {:?} was joined onto one line", passage)
+            }
+            TweeProblem::Start(passage) => {
+                write!(f, "the story starts at {:?}, not \"intro\"", passage)
+            }
+            TweeProblem::BadStoryData(err) => write!(f, "can't read StoryData: {}", err),
+        }
+    }
+}
+
+// tags for passages that aren't part of the story itself
+const SPECIAL_TAGS: [&str; 6] = [
+    "script",
+    "stylesheet",
+    "startup",
+    "header",
+    "footer",
+    "widget",
+];
+// SugarCube's passages that it runs or shows by itself
+const SPECIAL_PASSAGES: [&str; 7] = [
+    "StoryInit",
+    "StoryCaption",
+    "StoryMenu",
+    "PassageReady",
+    "PassageDone",
+    "PassageHeader",
+    "PassageFooter",
+];
+
+struct Passage {
+    name: String,
+    tags: Vec<String>,
+    text: String,
+}
+
+struct Link {
+    text: String,
+    target: String,
+    setter: Option<String>,
+}
+
+// The story in source, and whatever in it couldn't be brought over
+pub fn import_twee(source: &str) -> (Story, Vec<TweeProblem>) {
+    let mut problems = vec![];
+    let mut story = Story {
+        story_name: String::new(),
+        variables: Default::default(),
+        scenes: vec![],
+    };
+    for passage in passages(source) {
+        let name = passage.name.as_str();
+        if name == "StoryTitle" {
+            story.story_name = passage.text.trim().to_string();
+        } else if name == "StoryData" {
+            match serde_json::from_str::<serde_json::Value>(&passage.text) {
+                Ok(data) => match data.get("start").and_then(|start| start.as_str()) {
+                    Some(start) if start != "intro" => {
+                        problems.push(TweeProblem::Start(start.to_string()))
+                    }
+                    _ => {}
+                },
+                Err(err) => problems.push(TweeProblem::BadStoryData(err.to_string())),
+            }
+        } else if SPECIAL_PASSAGES.contains(&name)
+            || passage
+                .tags
+                .iter()
+                .any(|tag| SPECIAL_TAGS.contains(&tag.as_str()))
+        {
+            problems.push(TweeProblem::SkippedPassage(passage.name));
+        } else if story.scene(name).is_some() {
+            problems.push(TweeProblem::DuplicatePassage(passage.name));
+        } else {
+            let scene = scene(&passage, &mut problems);
+            story.scenes.push(NamedScene {
+                scene_name: passage.name,
+                scene,
+            });
+        }
+    }
+    (story, problems)
+}
+
+fn scene(passage: &Passage, problems: &mut Vec<TweeProblem>) -> Scene {
+    let speaker = passage.tags.first().cloned().unwrap_or_default();
+    if passage.tags.len() > 1 {
+        problems.push(TweeProblem::ExtraTags {
+            passage: passage.name.clone(),
+            tags: passage.tags[1..].to_vec(),
+        });
+    }
+
+    let mut lines = vec![];
+    let mut links = vec![];
+    for line in passage.text.lines() {
+        let (prose, found) = split_links(line);
+        links.extend(found);
+        if !prose.trim().is_empty() {
+            lines.push(prose.trim().to_string());
+        }
+    }
+    if lines.len() > 1 {
+        problems.push(TweeProblem::LineBreaks(passage.name.clone()));
+    }
+    let message = lines.join(" ");
+    for markup in markup(&message) {
+        problems.push(TweeProblem::Markup {
+            passage: passage.name.clone(),
+            markup,
+        });
+    }
+
+    let next = if links.is_empty() {
+        Next::End
+    } else {
+        Next::Choice(
+            links
+                .into_iter()
+                .map(|link| {
+                    if let Some(setter) = link.setter {
+                        problems.push(TweeProblem::Setter {
+                            passage: passage.name.clone(),
+                            setter,
+                        });
+                    }
+                    Response::new(&escape(&link.text), &link.target)
+                })
+                .collect(),
+        )
+    };
+    Scene::new(&speaker, &escape(&message), next)
+}
+
+// The passages in source, in order. Anything before the first one is ignored.
+fn passages(source: &str) -> Vec<Passage> {
+    let mut passages: Vec<Passage> = vec![];
+    for line in source.lines() {
+        if let Some(header) = line.strip_prefix("::") {
+            let (name, tags) = header_parts(header);
+            passages.push(Passage {
+                name,
+                tags,
+                text: String::new(),
+            });
+        } else if let Some(passage) = passages.last_mut() {
+            passage.text.push_str(line);
+            passage.text.push('\n');
+        }
+    }
+    for passage in passages.iter_mut() {
+        passage.text = passage.text.trim_end().to_string();
+    }
+    passages
+}
+
+// The name and tags in a passage's header, `Name [tag tag] {metadata}`. \ escapes the
+// brackets and braces in names.
+fn header_parts(header: &str) -> (String, Vec<String>) {
+    let mut name = String::new();
+    let mut tags = String::new();
+    let mut in_tags = false;
+    let mut chars = header.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => name.extend(chars.next()),
+            '[' if !in_tags => in_tags = true,
+            ']' if in_tags => in_tags = false,
+            '{' if !in_tags => break,
+            _ if in_tags => tags.push(c),
+            _ => name.push(c),
+        }
+    }
+    let tags = tags.split_whitespace().map(str::to_string).collect();
+    (name.trim().to_string(), tags)
+}
+
+// line with each link swapped for its text, and the links. A line that's only links
+// comes back blank.
+fn split_links(line: &str) -> (String, Vec<Link>) {
+    let mut prose = String::new();
+    let mut outside = String::new();
+    let mut links = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        let end = match rest[start..].find("]]") {
+            Some(end) => start + end,
+            None => break,
+        };
+        let link = link(&rest[start + 2..end]);
+        prose += &rest[..start];
+        prose += &link.text;
+        outside += &rest[..start];
+        links.push(link);
+        rest = &rest[end + 2..];
+    }
+    prose += rest;
+    outside += rest;
+    if outside.trim().is_empty() {
+        prose.clear();
+    }
+    (prose, links)
+}
+
+fn link(inner: &str) -> Link {
+    let (inner, setter) = match inner.find("][") {
+        Some(i) => (&inner[..i], Some(inner[i + 2..].to_string())),
+        None => (inner, None),
+    };
+    // the same order Harlowe reads them in: the last ->, then the first <-, then |
+    let (text, target) = if let Some(i) = inner.rfind("->") {
+        (&inner[..i], &inner[i + 2..])
+    } else if let Some(i) = inner.find("<-") {
+        (&inner[i + 2..], &inner[..i])
+    } else if let Some(i) = inner.find('|') {
+        (&inner[..i], &inner[i + 1..])
+    } else {
+        (inner, inner)
+    };
+    Link {
+        text: text.trim().to_string(),
+        target: target.trim().to_string(),
+        setter,
+    }
+}
+
+// Harlowe's (macro: ...), SugarCube's <<macro>>, HTML tags and $variables in text
+fn markup(text: &str) -> Vec<String> {
+    let mut found = vec![];
+    for (i, c) in text.char_indices() {
+        let rest = &text[i..];
+        let word = |from: usize| {
+            rest[from..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                .map_or(&rest[from..], |end| &rest[from..from + end])
+        };
+        let markup = match c {
+            '(' if !word(1).is_empty() && rest[1 + word(1).len()..].starts_with(':') => {
+                format!("({}:", word(1))
+            }
+            // <<macro>> and <</macro>>
+            '<' if rest.starts_with("<</") => format!("<</{}", word(3)),
+            '<' if rest.starts_with("<<") => format!("<<{}", word(2)),
+            '<' if text[..i].ends_with('<') => continue,
+            '<' if !word(1).is_empty() || rest.starts_with("</") => match rest.find('>') {
+                Some(end) => rest[..=end].to_string(),
+                None => continue,
+            },
+            '$' if word(1).starts_with(char::is_alphabetic) => format!("${}", word(1)),
+            _ => continue,
+        };
+        found.push(markup);
+    }
+    found
+}
+
+// nemo fills in {...}, so braces that were just text need doubling
+fn escape(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
@@ -0,0 +1,468 @@
+// Checks for mistakes in a story that would otherwise only show up while playing it
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+
+use super::expr::{embedded, Expr, Kind};
+use super::{Effect, Next, Scene, Story};
+use crate::text::TextInfo;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Problem {
+    // the scene the story starts at isn't there
+    MissingStart(String),
+    UnknownTarget {
+        scene: String,
+        goto: String,
+    },
+    // only the first scene with the name can ever be shown
+    DuplicateScene(String),
+    // a condition, effect or {...} that won't work with the story's variables
+    BadExpression {
+        scene: String,
+        error: String,
+    },
+    // these scenes can be reached, but once you're in them you can never get out,
+    // not even to an ending
+    NoExit(Vec<String>),
+    Unreachable(String),
+    // a character's picture (see Character::sprite) that isn't there, and the scenes
+    // that show it
+    MissingSprite {
+        sprite: String,
+        scenes: Vec<String>,
+    },
+    MissingBackground {
+        background: String,
+        scenes: Vec<String>,
+    },
+    // music or sfx, from a scene or one of its responses
+    MissingSound {
+        sound: String,
+        scenes: Vec<String>,
+    },
+    MissingGlyphs {
+        scene: String,
+        chars: BTreeSet<char>,
+    },
+    // the scene's message_id isn't in the strings and there's no message to fall back on,
+    // so it shows nothing
+    EmptyMessage {
+        scene: String,
+        message_id: String,
+    },
+}
+
+impl Problem {
+    // Errors break the story for the player, the rest are just worth a look
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Problem::MissingStart(_)
+                | Problem::UnknownTarget { .. }
+                | Problem::DuplicateScene(_)
+                | Problem::BadExpression { .. }
+                | Problem::NoExit(_)
+        )
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::MissingStart(start) => write!(f, "there's no {:?} scene to start at", start),
+            Problem::UnknownTarget { scene, goto } => {
+                write!(
+                    f,
+                    "scene {:?} goes to {:?}, which doesn't exist",
+                    scene, goto
+                )
+            }
+            Problem::DuplicateScene(scene) => {
+                write!(f, "more than one scene is named {:?}", scene)
+            }
+            Problem::BadExpression { scene, error } => write!(f, "in scene {:?}: {}", scene, error),
+            Problem::NoExit(scenes) => {
+                write!(f, "scenes {:?} loop with no way out and no ending", scenes)
+            }
+            Problem::Unreachable(scene) => write!(f, "scene {:?} can't be reached", scene),
+            Problem::MissingSprite { sprite, scenes } => {
+                write!(f, "no sprite for {:?} (in scenes {:?})", sprite, scenes)
+            }
+            Problem::MissingBackground { background, scenes } => {
+                write!(f, "no background {:?} (in scenes {:?})", background, scenes)
+            }
+            Problem::MissingSound { sound, scenes } => {
+                write!(f, "no sound {:?} (in scenes {:?})", sound, scenes)
+            }
+            Problem::MissingGlyphs { scene, chars } => write!(
+                f,
+                "scene {:?} uses characters the font can't draw: {:?}",
+                scene, chars
+            ),
+            Problem::EmptyMessage { scene, message_id } => write!(
+                f,
+                "scene {:?} shows nothing: there's no string {:?} and no message",
+                scene, message_id
+            ),
+        }
+    }
+}
+
+// Problems with how the scenes link up, playing from start
+pub fn validate(story: &Story, start: &str) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    // first scene with each name, the same one Story::scene finds
+    let mut index = HashMap::new();
+    let mut duplicates = BTreeSet::new();
+    for (i, named) in story.scenes.iter().enumerate() {
+        if index.contains_key(named.scene_name.as_str()) {
+            duplicates.insert(named.scene_name.clone());
+        } else {
+            index.insert(named.scene_name.as_str(), i);
+        }
+    }
+
+    if !index.contains_key(start) {
+        problems.push(Problem::MissingStart(start.to_string()));
+    }
+    for named in story.scenes.iter() {
+        for goto in named.scene.targets() {
+            if !index.contains_key(goto) {
+                problems.push(Problem::UnknownTarget {
+                    scene: named.scene_name.clone(),
+                    goto: goto.to_string(),
+                });
+            }
+        }
+    }
+    problems.extend(duplicates.into_iter().map(Problem::DuplicateScene));
+    problems.extend(check_expressions(story));
+
+    // the links between scenes, by index, leaving out the ones that go nowhere
+    let edges: Vec<Vec<usize>> = story
+        .scenes
+        .iter()
+        .map(|named| {
+            named
+                .scene
+                .targets()
+                .iter()
+                .filter_map(|goto| index.get(goto).copied())
+                .collect()
+        })
+        .collect();
+
+    let mut reached = vec![false; story.scenes.len()];
+    if let Some(&first) = index.get(start) {
+        let mut queue = VecDeque::new();
+        reached[first] = true;
+        queue.push_back(first);
+        while let Some(i) = queue.pop_front() {
+            for &j in edges[i].iter() {
+                if !reached[j] {
+                    reached[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+
+    for group in traps(story, &edges) {
+        if group.iter().any(|&i| reached[i]) {
+            problems.push(Problem::NoExit(
+                group
+                    .iter()
+                    .map(|&i| story.scenes[i].scene_name.clone())
+                    .collect(),
+            ));
+        }
+    }
+
+    for (i, named) in story.scenes.iter().enumerate() {
+        // duplicates were already reported
+        if !reached[i] && index[named.scene_name.as_str()] == i {
+            problems.push(Problem::Unreachable(named.scene_name.clone()));
+        }
+    }
+    problems
+}
+
+// Every condition, effect and {...} checked against the kinds of the declared variables
+fn check_expressions(story: &Story) -> Vec<Problem> {
+    let kinds: BTreeMap<String, Kind> = story
+        .variables
+        .iter()
+        .map(|(name, value)| (name.clone(), value.kind()))
+        .collect();
+    let check_text = |text: &str| -> Result<(), String> {
+        for inner in embedded(text) {
+            Expr::parse(inner)?.check(&kinds)?;
+        }
+        Ok(())
+    };
+    let check_effect = |effect: &Effect| -> Result<(), String> {
+        let kind = kinds
+            .get(effect.variable())
+            .ok_or_else(|| format!("no variable named {:?}", effect.variable()))?;
+        let wants = match effect {
+            Effect::Set { to, .. } => to.kind(),
+            Effect::Add { .. } => Kind::Int,
+        };
+        if *kind == wants {
+            Ok(())
+        } else {
+            Err(format!(
+                "{:?} is {}, not {}",
+                effect.variable(),
+                kind,
+                wants
+            ))
+        }
+    };
+
+    let mut problems = vec![];
+    for named in story.scenes.iter() {
+        let scene = &named.scene;
+        let mut errors = vec![check_text(&scene.message)];
+        errors.extend(scene.effects.iter().map(check_effect));
+        for response in scene.choices() {
+            errors.push(check_text(&response.response));
+            if let Some(condition) = &response.condition {
+                errors.push(condition.check(&kinds).and_then(|kind| match kind {
+                    Kind::Bool => Ok(()),
+                    _ => Err(format!(
+                        "condition {:?} is {}, not {}",
+                        condition.source(),
+                        kind,
+                        Kind::Bool
+                    )),
+                }));
+            }
+            errors.extend(response.effects.iter().map(check_effect));
+        }
+        problems.extend(errors.into_iter().filter_map(|r| r.err()).map(|error| {
+            Problem::BadExpression {
+                scene: named.scene_name.clone(),
+                error,
+            }
+        }));
+    }
+    problems
+}
+
+// Groups of scenes that go round in a loop and never link to anything outside the group.
+// A goto to a scene that doesn't exist counts as a way out, since it's already an error.
+fn traps(story: &Story, edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut traps = vec![];
+    for group in strongly_connected(edges) {
+        let looped = group.len() > 1 || edges[group[0]].contains(&group[0]);
+        let closed = group.iter().all(|&i| {
+            story.scenes[i].scene.targets().len() == edges[i].len()
+                && edges[i].iter().all(|j| group.contains(j))
+        });
+        let ends = group
+            .iter()
+            .any(|&i| story.scenes[i].scene.next == Next::End);
+        if looped && closed && !ends {
+            traps.push(group);
+        }
+    }
+    traps
+}
+
+// Tarjan's algorithm: each group is a set of scenes that can all get to each other
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Search<'e> {
+        edges: &'e [Vec<usize>],
+        next_order: usize,
+        order: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        groups: Vec<Vec<usize>>,
+    }
+
+    impl<'e> Search<'e> {
+        fn visit(&mut self, i: usize) {
+            self.order[i] = Some(self.next_order);
+            self.low[i] = self.next_order;
+            self.next_order += 1;
+            self.stack.push(i);
+            self.on_stack[i] = true;
+            for &j in self.edges[i].iter() {
+                match self.order[j] {
+                    None => {
+                        self.visit(j);
+                        self.low[i] = self.low[i].min(self.low[j]);
+                    }
+                    Some(order) if self.on_stack[j] => self.low[i] = self.low[i].min(order),
+                    _ => {}
+                }
+            }
+            if Some(self.low[i]) == self.order[i] {
+                let mut group = vec![];
+                while let Some(j) = self.stack.pop() {
+                    self.on_stack[j] = false;
+                    group.push(j);
+                    if j == i {
+                        break;
+                    }
+                }
+                group.sort_unstable();
+                self.groups.push(group);
+            }
+        }
+    }
+
+    let mut search = Search {
+        edges,
+        next_order: 0,
+        order: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        stack: vec![],
+        on_stack: vec![false; edges.len()],
+        groups: vec![],
+    };
+    for i in 0..edges.len() {
+        if search.order[i].is_none() {
+            search.visit(i);
+        }
+    }
+    search.groups
+}
+
+// Sprites of the characters on stage (see Scene::cast) that has_sprite says no to,
+// in the order they're first shown
+pub fn missing_sprites<F>(story: &Story, has_sprite: F) -> Vec<Problem>
+where
+    F: Fn(&str) -> bool,
+{
+    missing(story, |scene| {
+        scene
+            .cast()
+            .iter()
+            .map(|character| character.sprite())
+            .filter(|sprite| !has_sprite(sprite))
+            .collect()
+    })
+    .into_iter()
+    .map(|(sprite, scenes)| Problem::MissingSprite { sprite, scenes })
+    .collect()
+}
+
+// Backgrounds has_background says no to, in the order they're first shown
+pub fn missing_backgrounds<F>(story: &Story, has_background: F) -> Vec<Problem>
+where
+    F: Fn(&str) -> bool,
+{
+    missing(story, |scene| {
+        scene
+            .background
+            .iter()
+            .filter(|background| !has_background(background))
+            .cloned()
+            .collect()
+    })
+    .into_iter()
+    .map(|(background, scenes)| Problem::MissingBackground { background, scenes })
+    .collect()
+}
+
+// Music and sfx has_sound says no to, in the order they're first played.
+// "" is silence, so it's never missing.
+pub fn missing_sounds<F>(story: &Story, has_sound: F) -> Vec<Problem>
+where
+    F: Fn(&str) -> bool,
+{
+    missing(story, |scene| {
+        let mut sounds = vec![&scene.music, &scene.sfx];
+        for response in scene.choices() {
+            sounds.extend([&response.music, &response.sfx]);
+        }
+        sounds
+            .into_iter()
+            .flatten()
+            .filter(|sound| !sound.is_empty() && !has_sound(sound))
+            .cloned()
+            .collect()
+    })
+    .into_iter()
+    .map(|(sound, scenes)| Problem::MissingSound { sound, scenes })
+    .collect()
+}
+
+// Each name lacking finds in a scene, with the scenes it was found in
+fn missing<F>(story: &Story, lacking: F) -> Vec<(String, Vec<String>)>
+where
+    F: Fn(&Scene) -> Vec<String>,
+{
+    let mut missing: Vec<(String, Vec<String>)> = vec![];
+    for named in story.scenes.iter() {
+        for name in lacking(&named.scene) {
+            match missing.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, scenes)) if scenes.last() == Some(&named.scene_name) => {}
+                Some((_, scenes)) => scenes.push(named.scene_name.clone()),
+                None => missing.push((name, vec![named.scene_name.clone()])),
+            }
+        }
+    }
+    missing
+}
+
+// Scenes with a message_id has_string says no to and no message of their own
+pub fn empty_messages<F>(story: &Story, has_string: F) -> Vec<Problem>
+where
+    F: Fn(&str) -> bool,
+{
+    story
+        .scenes
+        .iter()
+        .filter(|named| named.scene.message.is_empty())
+        .filter_map(|named| {
+            let id = named.scene.message_id.as_ref()?;
+            if has_string(id) {
+                return None;
+            }
+            Some(Problem::EmptyMessage {
+                scene: named.scene_name.clone(),
+                message_id: id.clone(),
+            })
+        })
+        .collect()
+}
+
+// Characters in the story that the font can't draw, keyed by the scene_name they show up in.
+// Along with the story's own text, the strings its message_ids and response_ids look up to
+// with `string` are checked. Scenes that are fine are left out, so an empty map means the
+// whole story renders, in the language `string` looks things up in.
+pub fn missing_glyphs<'s, F>(
+    story: &Story,
+    font: &TextInfo,
+    string: F,
+) -> BTreeMap<String, BTreeSet<char>>
+where
+    F: Fn(&str) -> Option<&'s str>,
+{
+    let mut missing = BTreeMap::new();
+    for named in story.scenes.iter() {
+        let scene = &named.scene;
+        let mut texts = vec![scene.name.as_str(), scene.message.as_str()];
+        texts.extend(scene.message_id.as_deref().and_then(&string));
+        for response in scene.choices().iter() {
+            texts.push(&response.response);
+            texts.extend(response.response_id.as_deref().and_then(&string));
+        }
+        let mut chars = BTreeSet::new();
+        for text in texts {
+            chars.extend(font.missing_glyphs(text));
+        }
+        if !chars.is_empty() {
+            missing
+                .entry(named.scene_name.clone())
+                .or_insert_with(BTreeSet::new)
+                .extend(chars);
+        }
+    }
+    missing
+}
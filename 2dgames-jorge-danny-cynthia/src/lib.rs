@@ -1,8 +1,16 @@
 pub mod animation;
+pub mod animcontroller;
+pub mod batch;
 pub mod collision;
+pub mod dialogue;
 pub mod input;
+pub mod locale;
 pub mod objects;
+pub mod panel;
+pub mod save;
 pub mod screen;
 pub mod sprite;
 pub mod text;
 pub mod texture;
+pub mod ui;
+pub mod watch;
@@ -0,0 +1,216 @@
+// Player-facing text in more than one language. Each locale is a JSON file of strings by ID,
+// like content/locale/en.json:
+//
+//     { "title.start": "press enter to start.", "score": "score: {score}" }
+//
+// Strings can have {placeholders} for format to fill in; {{ and }} are literal braces.
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub type StringTable = BTreeMap<String, String>;
+
+#[derive(Debug)]
+pub enum LocaleError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocaleError::Io(err) => write!(f, "{}", err),
+            LocaleError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for LocaleError {}
+
+pub fn read_table(path: &Path) -> Result<StringTable, LocaleError> {
+    let json = fs::read_to_string(path).map_err(LocaleError::Io)?;
+    serde_json::from_str(&json).map_err(LocaleError::Parse)
+}
+
+// The locales dir has a table for, sorted
+pub fn locales(dir: &Path) -> Vec<String> {
+    let mut locales: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            name.strip_suffix(".json").map(|locale| locale.to_string())
+        })
+        .collect();
+    locales.sort();
+    locales
+}
+
+// The player's language from the environment, e.g. "fr" for LANG=fr_FR.UTF-8.
+// None if it isn't set, or is the "C" locale.
+pub fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .split(['_', '.', '@'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .filter(|locale| !locale.is_empty() && locale != "c" && locale != "posix")
+}
+
+pub struct Strings {
+    locale: String,
+    // the locale's own table first, then the ones to fall back on
+    tables: Vec<StringTable>,
+}
+
+impl Strings {
+    pub fn new(locale: &str, table: StringTable) -> Self {
+        Self {
+            locale: locale.to_string(),
+            tables: vec![table],
+        }
+    }
+
+    // For strings the locale doesn't have
+    pub fn with_fallback(mut self, table: StringTable) -> Self {
+        self.tables.push(table);
+        self
+    }
+
+    // dir/locale.json, falling back on dir/fallback.json
+    pub fn load(dir: &Path, locale: &str, fallback: &str) -> Result<Self, LocaleError> {
+        let path = |locale: &str| dir.join(format!("{}.json", locale));
+        let mut strings = Self::new(locale, read_table(&path(locale))?);
+        if fallback != locale {
+            strings = strings.with_fallback(read_table(&path(fallback))?);
+        }
+        Ok(strings)
+    }
+
+    // The player's locale (see system_locale) if dir has a table for it, otherwise fallback
+    pub fn for_player(dir: &Path, fallback: &str) -> Result<Self, LocaleError> {
+        let locale = system_locale()
+            .filter(|locale| dir.join(format!("{}.json", locale)).is_file())
+            .unwrap_or_else(|| fallback.to_string());
+        Self::load(dir, &locale, fallback)
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.tables
+            .iter()
+            .find_map(|table| table.get(id))
+            .map(|s| s.as_str())
+    }
+
+    // The string, or the ID itself if no table has it, so the gap shows
+    pub fn text(&self, id: &str) -> String {
+        self.get(id).unwrap_or(id).to_string()
+    }
+
+    // text with its {placeholders} filled in from args
+    pub fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        substitute(&self.text(id), args)
+    }
+}
+
+// How a locale's table lines up with the IDs that get used
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Coverage {
+    // used, but not in the table
+    pub missing: Vec<String>,
+    // in the table, but nothing uses them
+    pub unused: Vec<String>,
+}
+
+pub fn check(table: &StringTable, used: &BTreeSet<String>) -> Coverage {
+    Coverage {
+        missing: used
+            .iter()
+            .filter(|id| !table.contains_key(*id))
+            .cloned()
+            .collect(),
+        unused: table
+            .keys()
+            .filter(|id| !used.contains(*id))
+            .cloned()
+            .collect(),
+    }
+}
+
+// The IDs Rust source asks for by name: the string literals passed to text, format or get
+// on something called strings, like `state.strings.text("nemo.end")`. The call can be
+// split over lines the way rustfmt does it.
+pub fn requested_ids(source: &str) -> BTreeSet<String> {
+    let mut ids = BTreeSet::new();
+    let mut rest = source;
+    while let Some(i) = rest.find("strings") {
+        let before = rest[..i].chars().next_back();
+        rest = &rest[i + "strings".len()..];
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let call = match rest.trim_start().strip_prefix('.') {
+            Some(call) => call.trim_start(),
+            None => continue,
+        };
+        let args = ["text", "format", "get"]
+            .iter()
+            .find_map(|method| call.strip_prefix(method))
+            .and_then(|args| args.trim_start().strip_prefix('('))
+            .and_then(|args| args.trim_start().strip_prefix('"'));
+        if let Some(id) = args.and_then(|args| args.find('"').map(|end| &args[..end])) {
+            ids.insert(id.to_string());
+        }
+    }
+    ids
+}
+
+// Fills in {name} for each (name, value) in args. Placeholders args doesn't have are left
+// as they are, and {{ and }} become { and }.
+pub fn substitute(text: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find(['{', '}']) {
+        out += &rest[..i];
+        let after = &rest[i + 1..];
+        if after.starts_with(&rest[i..=i]) {
+            out += &rest[i..=i];
+            rest = &after[1..];
+            continue;
+        }
+        let arg = if rest[i..].starts_with('{') {
+            after.find('}').and_then(|end| {
+                args.iter()
+                    .find(|(name, _)| *name == &after[..end])
+                    .map(|(_, value)| (value.to_string(), end))
+            })
+        } else {
+            None
+        };
+        match arg {
+            Some((value, end)) => {
+                out += &value;
+                rest = &after[end + 1..];
+            }
+            None => {
+                out += &rest[i..=i];
+                rest = after;
+            }
+        }
+    }
+    out += rest;
+    out
+}
@@ -1,23 +1,144 @@
 #![allow(dead_code)]
-const DEPTH: usize = 4;
-const WIDTH: usize = 240;
-const HEIGHT: usize = 360;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+// How pixels are laid out in framebuffers and textures. There's only the one for now,
+// but having it in the types means Screen and Texture don't have to check at runtime.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PixelFormat {
+    // 8 bits each of red, green, blue and alpha; textures store it premultiplied
+    Rgba8888,
+}
+
+impl PixelFormat {
+    pub const fn depth(self) -> usize {
+        match self {
+            PixelFormat::Rgba8888 => 4,
+        }
+    }
+}
+
+pub const PIXEL_FORMAT: PixelFormat = PixelFormat::Rgba8888;
+pub const DEPTH: usize = PIXEL_FORMAT.depth();
 
 pub type Color = [u8; DEPTH];
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
 }
 
 impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
     pub fn new(x: f32, y: f32) -> Self {
         Self { x, y }
     }
+
+    pub fn dot(self, other: Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(self, other: Vec2) -> f32 {
+        (self - other).length()
+    }
+
+    // Same direction with length 1; the zero vector stays zero instead of turning into NaNs
+    pub fn normalize(self) -> Vec2 {
+        let len = self.length();
+        if len == 0.0 {
+            Vec2::ZERO
+        } else {
+            self / len
+        }
+    }
+
+    // t = 0 gives self, t = 1 gives other
+    pub fn lerp(self, other: Vec2, t: f32) -> Vec2 {
+        self + (other - self) * t
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+impl Mul<f32> for Vec2 {
+    type Output = Vec2;
+    fn mul(self, s: f32) -> Vec2 {
+        Vec2::new(self.x * s, self.y * s)
+    }
+}
+
+impl Mul<Vec2> for f32 {
+    type Output = Vec2;
+    fn mul(self, v: Vec2) -> Vec2 {
+        v * self
+    }
+}
+
+impl Div<f32> for Vec2 {
+    type Output = Vec2;
+    fn div(self, s: f32) -> Vec2 {
+        Vec2::new(self.x / s, self.y / s)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f32> for Vec2 {
+    fn mul_assign(&mut self, s: f32) {
+        *self = *self * s;
+    }
+}
+
+impl DivAssign<f32> for Vec2 {
+    fn div_assign(&mut self, s: f32) {
+        *self = *self / s;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct MovingRect {
     pub x: f32,
     pub y: f32,
@@ -34,9 +155,17 @@ impl MovingRect {
     pub fn as_rect(&self) -> Rect {
         Rect::new(self.x, self.y, self.w, self.h)
     }
+
+    pub fn translate(&mut self, by: Vec2) {
+        self.x += by.x;
+        self.y += by.y;
+    }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+// Rects are half-open: they cover x..x+w and y..y+h, so two rects that only share
+// an edge don't intersect, and a point on the right or bottom edge isn't contained.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -49,6 +178,10 @@ impl Rect {
         Self { x, y, w, h }
     }
 
+    pub fn from_pos_size(pos: Vec2, size: Vec2) -> Self {
+        Self::new(pos.x, pos.y, size.x, size.y)
+    }
+
     pub fn pos(&self) -> Vec2 {
         Vec2::new(self.x, self.y)
     }
@@ -56,10 +189,154 @@ impl Rect {
     pub fn size(&self) -> Vec2 {
         Vec2::new(self.w, self.h)
     }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.w
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.h
+    }
+
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.w <= 0.0 || self.h <= 0.0
+    }
+
+    pub fn contains_point(&self, p: Vec2) -> bool {
+        self.x <= p.x && p.x < self.right() && self.y <= p.y && p.y < self.bottom()
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    // The overlapping area, or None if they don't overlap
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = self.right().min(other.right());
+        let y1 = self.bottom().min(other.bottom());
+        if x0 < x1 && y0 < y1 {
+            Some(Rect::new(x0, y0, x1 - x0, y1 - y0))
+        } else {
+            None
+        }
+    }
+
+    // The smallest rect covering both
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = self.right().max(other.right());
+        let y1 = self.bottom().max(other.bottom());
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+
+    // Grow by `amount` on every side (shrink if it's negative), keeping the same center
+    pub fn expand(&self, amount: f32) -> Rect {
+        Rect::new(
+            self.x - amount,
+            self.y - amount,
+            self.w + 2.0 * amount,
+            self.h + 2.0 * amount,
+        )
+    }
+
+    pub fn translate(&self, by: Vec2) -> Rect {
+        Rect::new(self.x + by.x, self.y + by.y, self.w, self.h)
+    }
+
+    // The smallest integer rect that covers this one
+    pub fn round_out(&self) -> IRect {
+        let x0 = self.x.floor() as i32;
+        let y0 = self.y.floor() as i32;
+        let x1 = self.right().ceil() as i32;
+        let y1 = self.bottom().ceil() as i32;
+        IRect::new(x0, y0, x1 - x0, y1 - y0)
+    }
 }
 
-pub fn dist((x0, y0): (i32, i32), (x1, y1): (i32, i32)) -> f32 {
-    let dx = (x0 - x1) as f32;
-    let dy = (y0 - y1) as f32;
-    (dx * dx + dy * dy).sqrt()
+// Integer rect for pixel work like clipping, same half-open rules as Rect
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct IRect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl IRect {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.w
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.h
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.w <= 0 || self.h <= 0
+    }
+
+    pub fn contains_point(&self, x: i32, y: i32) -> bool {
+        self.x <= x && x < self.right() && self.y <= y && y < self.bottom()
+    }
+
+    pub fn intersects(&self, other: &IRect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    pub fn intersection(&self, other: &IRect) -> Option<IRect> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = self.right().min(other.right());
+        let y1 = self.bottom().min(other.bottom());
+        if x0 < x1 && y0 < y1 {
+            Some(IRect::new(x0, y0, x1 - x0, y1 - y0))
+        } else {
+            None
+        }
+    }
+
+    pub fn union(&self, other: &IRect) -> IRect {
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = self.right().max(other.right());
+        let y1 = self.bottom().max(other.bottom());
+        IRect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+
+    pub fn expand(&self, amount: i32) -> IRect {
+        IRect::new(
+            self.x - amount,
+            self.y - amount,
+            self.w + 2 * amount,
+            self.h + 2 * amount,
+        )
+    }
+
+    pub fn translate(&self, dx: i32, dy: i32) -> IRect {
+        IRect::new(self.x + dx, self.y + dy, self.w, self.h)
+    }
+}
+
+impl From<IRect> for Rect {
+    fn from(r: IRect) -> Rect {
+        Rect::new(r.x as f32, r.y as f32, r.w as f32, r.h as f32)
+    }
 }
@@ -0,0 +1,201 @@
+use crate::objects::{Rect, Vec2};
+use crate::screen::{Screen, Transform};
+use crate::texture::Texture;
+use std::rc::Rc;
+
+// How the parts of a panel between the corners get to the size of the box
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Fill {
+    // one copy, scaled to fit
+    Stretch,
+    // copies side by side from the top left, with the last ones cut off
+    Tile,
+}
+
+// How far in from each side of the frame the border goes, in frame pixels
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn uniform(inset: f32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+}
+
+// A box that can be drawn at any size from one frame of art (nine-slice).
+// The frame's corners are drawn as they are, its edges fill the sides of the box,
+// and its middle fills the inside.
+pub struct Panel {
+    image: Rc<Texture>,
+    frame: Rect,
+    insets: Insets,
+    pub edges: Fill,
+    pub center: Fill,
+}
+
+impl Panel {
+    // Panics if the frame isn't inside the texture or the insets don't fit in the frame
+    pub fn new(image: &Rc<Texture>, frame: Rect, insets: Insets) -> Self {
+        assert!(
+            image.valid_frame(frame),
+            "frame {:?} isn't in the texture",
+            frame
+        );
+        assert!(
+            insets.left >= 0.0
+                && insets.top >= 0.0
+                && insets.right >= 0.0
+                && insets.bottom >= 0.0
+                && insets.left + insets.right <= frame.w
+                && insets.top + insets.bottom <= frame.h,
+            "insets {:?} don't fit in frame {:?}",
+            insets,
+            frame
+        );
+        Self {
+            image: Rc::clone(image),
+            frame,
+            insets,
+            edges: Fill::Stretch,
+            center: Fill::Stretch,
+        }
+    }
+
+    pub fn with_fill(mut self, edges: Fill, center: Fill) -> Self {
+        self.edges = edges;
+        self.center = center;
+        self
+    }
+
+    // Smallest box the corners fit in without being cut off
+    pub fn min_size(&self) -> Vec2 {
+        Vec2::new(
+            self.insets.left + self.insets.right,
+            self.insets.top + self.insets.bottom,
+        )
+    }
+
+    // What's inside the border when the panel is drawn at r, e.g. for putting text in
+    pub fn content_rect(&self, r: Rect) -> Rect {
+        Rect::new(
+            r.x + self.insets.left,
+            r.y + self.insets.top,
+            (r.w - self.insets.left - self.insets.right).max(0.0),
+            (r.h - self.insets.top - self.insets.bottom).max(0.0),
+        )
+    }
+}
+
+// Which part of a row or column of the nine a piece is in
+#[derive(Copy, Clone, PartialEq)]
+enum Place {
+    // left or top, stuck to that side
+    Start,
+    // right or bottom, stuck to that side
+    End,
+    Middle(Fill),
+}
+
+// Where the three pieces along one side start and end: in the frame, and on screen.
+// A box smaller than both borders together gives each border its share of what there is.
+fn slices(frame_start: f32, frame_len: f32, start: f32, end: f32, to: f32, len: f32) -> [f32; 8] {
+    let to0 = to.floor();
+    let to3 = (to + len).floor();
+    let (start_len, end_len) = if to3 - to0 >= start + end {
+        (start, end)
+    } else {
+        let share = (to3 - to0) * start / (start + end).max(1.0);
+        (share.floor(), (to3 - to0) - share.floor())
+    };
+    [
+        frame_start,
+        frame_start + start,
+        frame_start + frame_len - end,
+        frame_start + frame_len,
+        to0,
+        to0 + start_len,
+        to3 - end_len,
+        to3,
+    ]
+}
+
+pub trait DrawPanelExt {
+    fn draw_panel(&mut self, panel: &Panel, r: Rect);
+}
+
+impl<'fb> DrawPanelExt for Screen<'fb> {
+    fn draw_panel(&mut self, panel: &Panel, r: Rect) {
+        let f = panel.frame;
+        let i = panel.insets;
+        let xs = slices(f.x, f.w, i.left, i.right, r.x, r.w);
+        let ys = slices(f.y, f.h, i.top, i.bottom, r.y, r.h);
+        for row in 0..3 {
+            for col in 0..3 {
+                let fill = if row == 1 && col == 1 {
+                    panel.center
+                } else {
+                    panel.edges
+                };
+                let place = |n| match n {
+                    0 => Place::Start,
+                    1 => Place::Middle(fill),
+                    _ => Place::End,
+                };
+                let from = Rect::new(
+                    xs[col],
+                    ys[row],
+                    xs[col + 1] - xs[col],
+                    ys[row + 1] - ys[row],
+                );
+                let to = Rect::new(
+                    xs[col + 4],
+                    ys[row + 4],
+                    xs[col + 5] - xs[col + 4],
+                    ys[row + 5] - ys[row + 4],
+                );
+                draw_piece(self, &panel.image, from, to, place(col), place(row));
+            }
+        }
+    }
+}
+
+fn draw_piece(screen: &mut Screen, image: &Texture, from: Rect, to: Rect, x: Place, y: Place) {
+    if from.is_empty() || to.is_empty() {
+        return;
+    }
+    // for one axis: how much to scale by, and where copies go
+    let layout = |place: Place, from_len: f32, to_start: f32, to_len: f32| match place {
+        Place::Start => (1.0, to_start, 1),
+        Place::End => (1.0, to_start + to_len - from_len, 1),
+        Place::Middle(Fill::Stretch) => (to_len / from_len, to_start, 1),
+        Place::Middle(Fill::Tile) => (1.0, to_start, (to_len / from_len).ceil() as usize),
+    };
+    let (scale_x, start_x, count_x) = layout(x, from.w, to.x, to.w);
+    let (scale_y, start_y, count_y) = layout(y, from.h, to.y, to.h);
+    let transform = Transform {
+        scale: Vec2::new(scale_x, scale_y),
+        ..Transform::IDENTITY
+    };
+    screen.push_clip(to);
+    for ty in 0..count_y {
+        for tx in 0..count_x {
+            let pos = Vec2::new(start_x + tx as f32 * from.w, start_y + ty as f32 * from.h);
+            screen.bitblt_transformed(image, from, pos, &transform);
+        }
+    }
+    screen.pop_clip();
+}
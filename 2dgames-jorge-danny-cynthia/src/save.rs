@@ -0,0 +1,169 @@
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Something a game keeps between runs. Bump VERSION whenever the saved form changes,
+// and teach migrate how to bring the old form up to date.
+pub trait SaveData: Serialize + DeserializeOwned {
+    const VERSION: u32;
+
+    // Turn data saved as version `from` into what version from + 1 saved.
+    // Only called for versions older than VERSION, one step at a time.
+    fn migrate(from: u32, data: Value) -> Result<Value, String> {
+        let _ = data;
+        Err(format!(
+            "don't know how to read saves from version {}",
+            from
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    // the file isn't JSON, or doesn't look like the data
+    Format(serde_json::Error),
+    // saved by a newer version of the game
+    TooNew { found: u32, current: u32 },
+    Migrate(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Format(err) => write!(f, "{}", err),
+            SaveError::TooNew { found, current } => write!(
+                f,
+                "saved by a newer version (format {}, this reads up to {})",
+                found, current
+            ),
+            SaveError::Migrate(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        SaveError::Format(err)
+    }
+}
+
+// What's actually in a save file
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u32,
+    data: T,
+}
+
+// A folder of named save slots, each one a JSON file
+pub struct SaveDir {
+    dir: PathBuf,
+}
+
+impl SaveDir {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    // The usual place for a game's data: %APPDATA%\game on Windows,
+    // ~/Library/Application Support/game on macOS and $XDG_DATA_HOME/game
+    // (or ~/.local/share/game) elsewhere. None if there's no home to put it in.
+    pub fn for_game(game: &str) -> Option<Self> {
+        let var = |name| {
+            env::var_os(name)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        let base = if cfg!(windows) {
+            var("APPDATA")
+        } else if cfg!(target_os = "macos") {
+            var("HOME").map(|home| home.join("Library/Application Support"))
+        } else {
+            var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local/share")))
+        }?;
+        Some(Self::new(&base.join(game)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, slot: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", slot))
+    }
+
+    // Replaces the slot all at once: a crash partway through leaves the old save
+    pub fn write<T: SaveData>(&self, slot: &str, data: &T) -> Result<(), SaveError> {
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_vec_pretty(&Envelope {
+            version: T::VERSION,
+            data,
+        })?;
+        let path = self.path(slot);
+        let temp = self.dir.join(format!("{}.json.tmp", slot));
+        let mut file = File::create(&temp)?;
+        file.write_all(&json)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    // None if nothing's been saved in the slot. Older saves are migrated as they're read.
+    pub fn read<T: SaveData>(&self, slot: &str) -> Result<Option<T>, SaveError> {
+        let json = match fs::read(self.path(slot)) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let Envelope { version, mut data } = serde_json::from_slice::<Envelope<Value>>(&json)?;
+        if version > T::VERSION {
+            return Err(SaveError::TooNew {
+                found: version,
+                current: T::VERSION,
+            });
+        }
+        for from in version..T::VERSION {
+            data = T::migrate(from, data).map_err(SaveError::Migrate)?;
+        }
+        Ok(Some(serde_json::from_value(data)?))
+    }
+
+    pub fn delete(&self, slot: &str) -> Result<(), SaveError> {
+        match fs::remove_file(self.path(slot)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    // Names of the slots with something in them, sorted
+    pub fn slots(&self) -> Vec<String> {
+        let mut slots: Vec<String> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name().into_string().ok()?;
+                name.strip_suffix(".json").map(|slot| slot.to_string())
+            })
+            .collect();
+        slots.sort();
+        slots
+    }
+}
@@ -1,21 +1,168 @@
 #![allow(dead_code)]
 // We can pull in definitions from elsewhere in the crate!
-use crate::objects::{Color, Rect, Vec2};
-use crate::texture::Texture;
+use crate::objects::{Color, IRect, PixelFormat, Rect, Vec2, DEPTH, PIXEL_FORMAT};
+use crate::texture::{SpanKind, Texture};
+
+mod shapes;
+pub use shapes::{Gradient, LineCap};
+
+// How a frame gets mapped onto the screen by bitblt_transformed.
+// Flips happen inside the frame, then it's scaled and rotated (radians, clockwise) about
+// the pivot, which is in frame pixels from the frame's top left and stays put on screen.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub scale: Vec2,
+    pub rotation: f32,
+    pub pivot: Vec2,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        flip_x: false,
+        flip_y: false,
+        scale: Vec2 { x: 1.0, y: 1.0 },
+        rotation: 0.0,
+        pivot: Vec2 { x: 0.0, y: 0.0 },
+    };
+
+    pub fn is_identity(&self) -> bool {
+        !self.flip_x
+            && !self.flip_y
+            && self.scale.x == 1.0
+            && self.scale.y == 1.0
+            && self.rotation == 0.0
+    }
+
+    // Where the point `local` of a frame ends up relative to where the frame is drawn
+    pub fn apply(&self, local: Vec2, frame_size: Vec2) -> Vec2 {
+        let fx = if self.flip_x {
+            frame_size.x - local.x
+        } else {
+            local.x
+        };
+        let fy = if self.flip_y {
+            frame_size.y - local.y
+        } else {
+            local.y
+        };
+        let sx = (fx - self.pivot.x) * self.scale.x;
+        let sy = (fy - self.pivot.y) * self.scale.y;
+        let (sin, cos) = self.rotation.sin_cos();
+        Vec2::new(
+            self.pivot.x + sx * cos - sy * sin,
+            self.pivot.y + sx * sin + sy * cos,
+        )
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+// Where a logical framebuffer ends up inside a window: scaled up by the biggest whole
+// number that fits (never less than 1) and centered, with bars on the leftover sides.
+// That's how pixels presents its buffer, so this is for mapping between the two,
+// e.g. turning a cursor position into a framebuffer position.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Letterbox {
+    pub logical_size: (usize, usize),
+    pub scale: u32,
+    // in window (physical) pixels; can hang off the window if it's smaller than the logical size
+    pub viewport: IRect,
+}
+
+impl Letterbox {
+    pub fn new(logical_size: (usize, usize), window_size: (u32, u32)) -> Self {
+        let (lw, lh) = (logical_size.0 as u32, logical_size.1 as u32);
+        let (ww, wh) = window_size;
+        let scale = (ww / lw.max(1)).min(wh / lh.max(1)).max(1);
+        let (vw, vh) = ((lw * scale) as i32, (lh * scale) as i32);
+        Self {
+            logical_size,
+            scale,
+            viewport: IRect::new((ww as i32 - vw) / 2, (wh as i32 - vh) / 2, vw, vh),
+        }
+    }
+
+    // None if the point is in the bars
+    pub fn window_to_logical(&self, x: f32, y: f32) -> Option<Vec2> {
+        let p = Vec2::new(
+            (x - self.viewport.x as f32) / self.scale as f32,
+            (y - self.viewport.y as f32) / self.scale as f32,
+        );
+        let bounds = Rect::new(
+            0.0,
+            0.0,
+            self.logical_size.0 as f32,
+            self.logical_size.1 as f32,
+        );
+        if bounds.contains_point(p) {
+            Some(p)
+        } else {
+            None
+        }
+    }
+
+    pub fn logical_to_window(&self, p: Vec2) -> Vec2 {
+        Vec2::new(
+            p.x * self.scale as f32 + self.viewport.x as f32,
+            p.y * self.scale as f32 + self.viewport.y as f32,
+        )
+    }
+}
+
 pub struct Screen<'fb> {
     framebuffer: &'fb mut [u8],
     width: usize,
     height: usize,
-    depth: usize,
+    // nothing gets drawn outside this, in framebuffer pixels
+    clip: IRect,
+    // framebuffer pixel that drawing coordinates are relative to
+    origin: (i32, i32),
+    // what to go back to on each pop
+    saved: Vec<Saved>,
+}
+
+struct Saved {
+    clip: IRect,
+    origin: (i32, i32),
+    viewport: bool,
+}
+
+// Clip rects are in whole pixels, snapped the same way rect() snaps what it fills
+fn pixel_rect(r: Rect) -> IRect {
+    let x0 = r.x.floor() as i32;
+    let y0 = r.y.floor() as i32;
+    IRect::new(
+        x0,
+        y0,
+        r.right().floor() as i32 - x0,
+        r.bottom().floor() as i32 - y0,
+    )
 }
 
 impl<'fb> Screen<'fb> {
-    pub fn wrap(framebuffer: &'fb mut [u8], width: usize, height: usize, depth: usize) -> Self {
+    // The framebuffer is width * height pixels of PIXEL_FORMAT, at whatever logical
+    // resolution the game wants; see Letterbox for getting it onto the window.
+    pub fn wrap(framebuffer: &'fb mut [u8], width: usize, height: usize) -> Self {
+        assert_eq!(
+            framebuffer.len(),
+            width * height * DEPTH,
+            "framebuffer isn't {}x{} pixels",
+            width,
+            height
+        );
         Self {
             framebuffer,
             width,
             height,
-            depth,
+            clip: IRect::new(0, 0, width as i32, height as i32),
+            origin: (0, 0),
+            saved: Vec::new(),
         }
     }
 
@@ -23,61 +170,142 @@ impl<'fb> Screen<'fb> {
         (self.width, self.height)
     }
 
+    pub fn format(&self) -> PixelFormat {
+        PIXEL_FORMAT
+    }
+
+    // Only draw inside r (and inside whatever was clipping before) until the matching pop_clip
+    pub fn push_clip(&mut self, r: Rect) {
+        self.save(false);
+        let (ox, oy) = self.origin;
+        self.clip = self
+            .clip
+            .intersection(&pixel_rect(r).translate(ox, oy))
+            .unwrap_or_default();
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.restore(false);
+    }
+
+    // Like push_clip, but r's top left corner also becomes (0, 0) for everything drawn
+    // until the matching pop_viewport, e.g. for a minimap or one half of a split screen
+    pub fn push_viewport(&mut self, r: Rect) {
+        self.push_clip(r);
+        self.saved.last_mut().unwrap().viewport = true;
+        let clip = pixel_rect(r);
+        self.origin.0 += clip.x;
+        self.origin.1 += clip.y;
+    }
+
+    pub fn pop_viewport(&mut self) {
+        self.restore(true);
+    }
+
+    fn save(&mut self, viewport: bool) {
+        self.saved.push(Saved {
+            clip: self.clip,
+            origin: self.origin,
+            viewport,
+        });
+    }
+
+    fn restore(&mut self, viewport: bool) {
+        let saved = self.saved.pop().expect("nothing pushed to pop");
+        assert_eq!(
+            saved.viewport, viewport,
+            "pops have to match pushes: pop_clip for push_clip, pop_viewport for push_viewport"
+        );
+        self.clip = saved.clip;
+        self.origin = saved.origin;
+    }
+
+    // The area that can currently be drawn to, in drawing coordinates
+    pub fn clip_rect(&self) -> Rect {
+        let (ox, oy) = self.origin;
+        self.clip.translate(-ox, -oy).into()
+    }
+
+    // Where drawing coordinates (0, 0) is on the framebuffer
+    pub fn origin(&self) -> Vec2 {
+        Vec2::new(self.origin.0 as f32, self.origin.1 as f32)
+    }
+
+    // A drawing coordinate moved to the framebuffer
+    fn to_framebuffer(&self, p: Vec2) -> Vec2 {
+        p + self.origin()
+    }
+
+    // Set one framebuffer pixel, if it's inside the clip rect
+    #[inline(always)]
+    fn plot(&mut self, x: i32, y: i32, col: Color) {
+        if self.clip.contains_point(x, y) {
+            let idx = y as usize * self.width * DEPTH + x as usize * DEPTH;
+            self.framebuffer[idx..(idx + DEPTH)].copy_from_slice(&col);
+        }
+    }
+
     // This is not going to be the most efficient API.
     // Lots of bounds checks!
     #[inline(always)]
     pub fn draw_at(&mut self, col: Color, x: usize, y: usize) {
-        // No need to check x or y < 0, they're usizes!
-        if self.width <= x || self.height <= y {
-            return;
-        }
-        assert_eq!(self.depth, 4);
-        let idx = y * self.width * self.depth + x * self.depth;
         // TODO should handle alpha blending!
-        self.framebuffer[idx..(idx + self.depth)].copy_from_slice(&col);
+        self.plot(x as i32 + self.origin.0, y as i32 + self.origin.1, col);
     }
 
-    // If we know the primitives in advance we're in much better shape:
+    // If we know the primitives in advance we're in much better shape.
+    // Clears the whole clip rect, which is the whole screen unless something's been pushed
     pub fn clear(&mut self, col: Color) {
-        for px in self.framebuffer.chunks_exact_mut(4) {
-            px.copy_from_slice(&col);
+        let clip = self.clip;
+        let pitch = self.width * DEPTH;
+        for row in self.framebuffer[clip.y as usize * pitch..clip.bottom() as usize * pitch]
+            .chunks_exact_mut(pitch)
+        {
+            for px in
+                row[clip.x as usize * DEPTH..clip.right() as usize * DEPTH].chunks_exact_mut(DEPTH)
+            {
+                px.copy_from_slice(&col);
+            }
         }
     }
 
     pub fn rect_lines(&mut self, r: Rect, col: Color) {
-        let x0 = r.x.max(0.0).min(self.width as f32) as usize;
-        let x1 = (r.x + r.w).max(0.0).min(self.width as f32) as usize;
-        let y0 = r.y.max(0.0).min(self.height as f32) as usize;
-        let y1 = (r.y + r.h).max(0.0).min(self.height as f32) as usize;
-        let depth = self.depth;
+        if r.w < 0.0 || r.h < 0.0 {
+            return;
+        }
+        let r = r.translate(self.origin());
+        let clip = self.clip;
+        let (cx0, cx1) = (clip.x as f32, clip.right() as f32);
+        let (cy0, cy1) = (clip.y as f32, clip.bottom() as f32);
+        let x0 = r.x.max(cx0).min(cx1) as usize;
+        let x1 = (r.x + r.w).max(cx0).min(cx1) as usize;
+        let y0 = r.y.max(cy0).min(cy1) as usize;
+        let y1 = (r.y + r.h).max(cy0).min(cy1) as usize;
+        let depth = DEPTH;
         let pitch = self.width * depth;
 
-        // vertical lines
-        if r.x > 0.0 {
-            let x0 = x0.min(self.width - 1);
+        // vertical lines, each only if its column is inside the clip rect
+        if cx0 <= r.x && r.x < cx1 {
             for row in y0..y1 {
                 let pixel_idx = row * pitch + x0 * depth;
                 self.framebuffer[pixel_idx..pixel_idx + depth].copy_from_slice(&col);
             }
         }
-        if r.x + r.w < self.width as f32 {
-            let x1 = x1.min(self.width - 1);
+        if cx0 <= r.x + r.w && r.x + r.w < cx1 {
             for row in y0..y1 {
                 let pixel_idx = row * pitch + x1 * depth;
                 self.framebuffer[pixel_idx..pixel_idx + depth].copy_from_slice(&col);
             }
         }
 
-        if r.y > 0.0 {
-            let y0 = y0.min(self.height - 1);
+        if cy0 <= r.y && r.y < cy1 {
             for p in self.framebuffer[y0 * pitch + x0 * depth..y0 * pitch + x1 * depth]
                 .chunks_exact_mut(depth)
             {
                 p.copy_from_slice(&col);
             }
         }
-        if r.y + r.h < self.height as f32 {
-            let y1 = y1.min(self.height - 1);
+        if cy0 <= r.y + r.h && r.y + r.h < cy1 {
             for p in self.framebuffer[y1 * pitch + x0 * depth..y1 * pitch + x1 * depth]
                 .chunks_exact_mut(depth)
             {
@@ -87,13 +315,20 @@ impl<'fb> Screen<'fb> {
     }
 
     pub fn rect(&mut self, r: Rect, col: Color) {
-        let x0 = r.x.max(0.0).min(self.width as f32) as usize;
-        let x1 = (r.x + r.w).max(0.0).min(self.width as f32) as usize;
-        let y0 = r.y.max(0.0).min(self.height as f32) as usize;
-        let y1 = (r.y + r.h).max(0.0).min(self.height as f32) as usize;
-        let depth = self.depth;
+        let r = r.translate(self.origin());
+        let clip = self.clip;
+        let (cx0, cx1) = (clip.x as f32, clip.right() as f32);
+        let (cy0, cy1) = (clip.y as f32, clip.bottom() as f32);
+        let x0 = r.x.max(cx0).min(cx1) as usize;
+        let x1 = (r.x + r.w).max(cx0).min(cx1) as usize;
+        let y0 = r.y.max(cy0).min(cy1) as usize;
+        let y1 = (r.y + r.h).max(cy0).min(cy1) as usize;
+        let depth = DEPTH;
         let pitch = self.width * depth;
-        for row in self.framebuffer[(y0 * pitch)..(y1 * pitch)].chunks_exact_mut(pitch) {
+        if x1 <= x0 {
+            return;
+        }
+        for row in self.framebuffer[(y0 * pitch)..(y1.max(y0) * pitch)].chunks_exact_mut(pitch) {
             for p in row[(x0 * depth)..(x1 * depth)].chunks_exact_mut(depth) {
                 // TODO should handle alpha blending
                 p.copy_from_slice(&col);
@@ -101,7 +336,11 @@ impl<'fb> Screen<'fb> {
         }
     }
 
-    pub fn line(&mut self, Vec2 { x: x0, y: y0 }: Vec2, Vec2 { x: x1, y: y1 }: Vec2, col: Color) {
+    pub fn line(&mut self, from: Vec2, to: Vec2, col: Color) {
+        // stepping a whole pixel at a time only lands on the end point if both are on the grid
+        let Vec2 { x: x0, y: y0 } = self.to_framebuffer(from);
+        let Vec2 { x: x1, y: y1 } = self.to_framebuffer(to);
+        let (x0, y0, x1, y1) = (x0.floor(), y0.floor(), x1.floor(), y1.floor());
         let mut x = x0;
         let mut y = y0;
         let dx = (x1 - x0).abs();
@@ -109,23 +348,13 @@ impl<'fb> Screen<'fb> {
         let dy = -(y1 - y0).abs();
         let sy = if y0 < y1 { 1.0 } else { -1.0 };
         let mut err = dx + dy;
-        let width = self.width as f32;
-        let height = self.height as f32;
-        let depth = self.depth;
-        let pitch = self.width * depth;
         #[allow(clippy::all)]
         while x != x1 || y != y1 {
-            // We couldn't just clamp x0/y0 and x1/y1 into bounds, because then
+            // We couldn't just clamp x0/y0 and x1/y1 into the clip rect, because then
             // we might change the slope of the line.
             // We could find the intercept of the line with the left/right or top/bottom edges of the rect though, but that's work!
-            if 0.0 <= x && x < width && 0.0 <= y && y < height {
-                // TODO this bounds check could in theory be avoided with
-                // the unsafe get_unchecked, but maybe better not...
-                // TODO better handle alpha blending too, but not just yet...
-                self.framebuffer[(y as usize * pitch + x as usize * depth)
-                    ..(y as usize * pitch + (x as usize + 1) * depth)]
-                    .copy_from_slice(&col);
-            }
+            // TODO better handle alpha blending too, but not just yet...
+            self.plot(x.floor() as i32, y.floor() as i32, col);
             let e2 = 2.0 * err;
             if dy <= e2 {
                 err += dy;
@@ -138,72 +367,215 @@ impl<'fb> Screen<'fb> {
         }
     }
 
-    pub fn bitblt(&mut self, src: &Texture, from: Rect, Vec2 { x: to_x, y: to_y }: Vec2) {
+    pub fn bitblt(&mut self, src: &Texture, from: Rect, to: Vec2) {
         let (tw, th) = src.size();
         assert!(0.0 <= from.x);
         assert!(from.x < tw as f32);
         assert!(0.0 <= from.y);
         assert!(from.y < th as f32);
-        let to_x = to_x as i32;
-        let to_y = to_y as i32;
-        if (to_x + from.w as i32) < 0
-            || (self.width as i32) <= to_x
-            || (to_y + from.h as i32) < 0
-            || (self.height as i32) <= to_y
-        {
-            return;
-        }
-        let depth = self.depth;
-        assert_eq!(depth, src.depth());
+        let to_x = to.x as i32 + self.origin.0;
+        let to_y = to.y as i32 + self.origin.1;
+        let clip = self.clip;
+        let depth = DEPTH;
         let src_pitch = src.pitch();
         let dst_pitch = self.width * depth;
         // All this rigmarole is just to avoid bounds checks on each pixel of the blit.
         // We want to calculate which row/col of the src image to start at and which to end at.
         // This way there's no need to even check for out of bounds draws.
-        let y_skip = to_y.max(0) - to_y;
-        let x_skip = to_x.max(0) - to_x;
-        let y_count = (to_y + from.h as i32).min(self.height as i32) - to_y;
-        let x_count = (to_x + from.w as i32).min(self.width as i32) - to_x;
+        let y_skip = to_y.max(clip.y) - to_y;
+        let x_skip = to_x.max(clip.x) - to_x;
+        let y_count = (to_y + from.h as i32).min(clip.bottom()) - to_y;
+        let x_count = (to_x + from.w as i32).min(clip.right()) - to_x;
+        if y_count <= y_skip || x_count <= x_skip {
+            return;
+        }
         let src_buf = src.buffer();
-        for (row_a, row_b) in src_buf[(src_pitch * ((from.y as i32 + y_skip) as usize))
-            ..(src_pitch * ((from.y as i32 + y_count) as usize))]
-            .chunks_exact(src_pitch)
-            .zip(
+        let col_start = from.x as usize + x_skip as usize;
+        let col_end = from.x as usize + x_count as usize;
+        let dst_col = (to_x + x_skip) as usize;
+        for (src_row, row_b) in
+            ((from.y as i32 + y_skip) as usize..(from.y as i32 + y_count) as usize).zip(
                 self.framebuffer[(dst_pitch * ((to_y + y_skip) as usize))
                     ..(dst_pitch * ((to_y + y_count) as usize))]
                     .chunks_exact_mut(dst_pitch),
             )
         {
-            let to_cols = row_b
-                [(depth * (to_x + x_skip) as usize)..(depth * (to_x + x_count) as usize)]
-                .chunks_exact_mut(depth);
-            let from_cols = row_a[(depth * (from.x as i32 + x_skip) as usize)
-                ..(depth * (from.x as i32 + x_count) as usize)]
-                .chunks_exact(depth);
-            // Composite over, assume premultiplied rgba8888
-            for (to, from) in to_cols.zip(from_cols) {
-                let ta = to[3] as f32 / 255.0;
-                let fa = from[3] as f32 / 255.0;
-                for i in 0..3 {
-                    to[i] = from[i].saturating_add((to[i] as f32 * (1.0 - fa)).round() as u8);
+            let row_a = &src_buf[src_pitch * src_row..src_pitch * (src_row + 1)];
+            // Walk the runs that overlap the columns we're drawing: transparent ones are
+            // skipped, opaque ones copied straight over, only the rest get blended
+            let spans = src.row_spans(src_row);
+            let first = spans.partition_point(|span| span.end <= col_start);
+            for span in spans[first..]
+                .iter()
+                .take_while(|span| span.start < col_end)
+            {
+                let start = span.start.max(col_start);
+                let end = span.end.min(col_end);
+                let to = &mut row_b
+                    [depth * (dst_col + start - col_start)..depth * (dst_col + end - col_start)];
+                let from = &row_a[depth * start..depth * end];
+                match span.kind {
+                    SpanKind::Transparent => {}
+                    SpanKind::Opaque => to.copy_from_slice(from),
+                    SpanKind::Mixed => {
+                        for (to, from) in to.chunks_exact_mut(depth).zip(from.chunks_exact(depth)) {
+                            composite(to, from);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Like bitblt, but the frame is see-through: opacity 255 draws it as it is, 0 not at all
+    pub fn bitblt_faded(&mut self, src: &Texture, from: Rect, to: Vec2, opacity: u8) {
+        match opacity {
+            0 => return,
+            255 => return self.bitblt(src, from, to),
+            _ => {}
+        }
+        let (tw, th) = src.size();
+        assert!(0.0 <= from.x);
+        assert!(from.x < tw as f32);
+        assert!(0.0 <= from.y);
+        assert!(from.y < th as f32);
+        let to_x = to.x as i32 + self.origin.0;
+        let to_y = to.y as i32 + self.origin.1;
+        let clip = self.clip;
+        let depth = DEPTH;
+        let y_skip = to_y.max(clip.y) - to_y;
+        let x_skip = to_x.max(clip.x) - to_x;
+        let y_count = (to_y + from.h as i32).min(clip.bottom()) - to_y;
+        let x_count = (to_x + from.w as i32).min(clip.right()) - to_x;
+        if y_count <= y_skip || x_count <= x_skip {
+            return;
+        }
+        let src_buf = src.buffer();
+        let src_pitch = src.pitch();
+        let dst_pitch = self.width * depth;
+        for y in y_skip..y_count {
+            let src_row = (from.y as i32 + y) as usize;
+            if src.row_is_transparent(src_row) {
+                continue;
+            }
+            let dst_row = (to_y + y) as usize * dst_pitch;
+            for x in x_skip..x_count {
+                let src_idx = src_row * src_pitch + (from.x as i32 + x) as usize * depth;
+                let dst_idx = dst_row + (to_x + x) as usize * depth;
+                // premultiplied, so every channel scales down together
+                let mut pixel = [0; DEPTH];
+                for (p, s) in pixel.iter_mut().zip(&src_buf[src_idx..src_idx + depth]) {
+                    *p = div255(*s as u32 * opacity as u32);
                 }
-                to[3] = ((fa + ta * (1.0 - fa)) * 255.0).round() as u8;
+                composite(&mut self.framebuffer[dst_idx..dst_idx + depth], &pixel);
             }
         }
     }
 
-    pub fn filled_circle(&mut self, (x, y): (i32, i32), r: u64, col: Color) {
-        for i in x - r as i32..x + r as i32 {
-            for j in y - r as i32..y + r as i32 {
-                if crate::objects::dist((i, j), (x, y)) < r as f32
-                    && (x >= 0 && x < self.width as i32)
-                    && (y >= 0 && y < self.height as i32)
-                {
-                    self.framebuffer[self.width * self.depth * j as usize + i as usize * self.depth
-                        ..self.width * self.depth * j as usize + (i + 1) as usize * self.depth]
-                        .copy_from_slice(&col);
+    // Like bitblt, but the frame can be flipped, scaled and rotated first.
+    // Every covered screen pixel samples the nearest source pixel, so there's no smoothing.
+    pub fn bitblt_transformed(
+        &mut self,
+        src: &Texture,
+        from: Rect,
+        to: Vec2,
+        transform: &Transform,
+    ) {
+        if transform.is_identity() {
+            self.bitblt(src, from, to);
+            return;
+        }
+        if transform.scale.x == 0.0 || transform.scale.y == 0.0 || from.w <= 0.0 || from.h <= 0.0 {
+            return;
+        }
+        let (tw, th) = src.size();
+        assert!(0.0 <= from.x);
+        assert!(from.x + from.w <= tw as f32);
+        assert!(0.0 <= from.y);
+        assert!(from.y + from.h <= th as f32);
+        let depth = DEPTH;
+        let to = self.to_framebuffer(to);
+
+        // screen-space bounding box of the transformed frame, clipped to the clip rect
+        let size = Vec2::new(from.w, from.h);
+        let corners = [
+            transform.apply(Vec2::new(0.0, 0.0), size),
+            transform.apply(Vec2::new(from.w, 0.0), size),
+            transform.apply(Vec2::new(0.0, from.h), size),
+            transform.apply(Vec2::new(from.w, from.h), size),
+        ];
+        let min_x = corners.iter().map(|c| c.x).fold(f32::INFINITY, f32::min) + to.x;
+        let max_x = corners
+            .iter()
+            .map(|c| c.x)
+            .fold(f32::NEG_INFINITY, f32::max)
+            + to.x;
+        let min_y = corners.iter().map(|c| c.y).fold(f32::INFINITY, f32::min) + to.y;
+        let max_y = corners
+            .iter()
+            .map(|c| c.y)
+            .fold(f32::NEG_INFINITY, f32::max)
+            + to.y;
+        let clip = self.clip;
+        let x0 = min_x.floor().max(clip.x as f32) as usize;
+        let x1 = max_x.ceil().min(clip.right() as f32).max(0.0) as usize;
+        let y0 = min_y.floor().max(clip.y as f32) as usize;
+        let y1 = max_y.ceil().min(clip.bottom() as f32).max(0.0) as usize;
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        // Walk the box backwards: undo the rotation, then the scale, then the flips,
+        // to find which frame pixel each screen pixel center came from.
+        let (sin, cos) = transform.rotation.sin_cos();
+        let inv_sx = 1.0 / transform.scale.x;
+        let inv_sy = 1.0 / transform.scale.y;
+        let origin = Vec2::new(to.x + transform.pivot.x, to.y + transform.pivot.y);
+        let src_buf = src.buffer();
+        let src_pitch = src.pitch();
+        let dst_pitch = self.width * depth;
+        for y in y0..y1 {
+            let dy = y as f32 + 0.5 - origin.y;
+            let row = &mut self.framebuffer[y * dst_pitch..(y + 1) * dst_pitch];
+            for x in x0..x1 {
+                let dx = x as f32 + 0.5 - origin.x;
+                let mut u = (dx * cos + dy * sin) * inv_sx + transform.pivot.x;
+                let mut v = (-dx * sin + dy * cos) * inv_sy + transform.pivot.y;
+                if transform.flip_x {
+                    u = from.w - u;
+                }
+                if transform.flip_y {
+                    v = from.h - v;
                 }
+                if u < 0.0 || v < 0.0 || u >= from.w || v >= from.h {
+                    continue;
+                }
+                let sx = from.x as usize + (u as usize).min(from.w as usize - 1);
+                let sy = from.y as usize + (v as usize).min(from.h as usize - 1);
+                let src_idx = sy * src_pitch + sx * depth;
+                composite(
+                    &mut row[x * depth..(x + 1) * depth],
+                    &src_buf[src_idx..src_idx + depth],
+                );
             }
         }
     }
 }
+
+// Composite over, assume premultiplied rgba8888.
+// Integer math that rounds the same way as doing it in floats: to * (1 - alpha), rounded.
+#[inline(always)]
+fn composite(to: &mut [u8], from: &[u8]) {
+    let inv = 255 - from[3] as u32;
+    for i in 0..3 {
+        to[i] = from[i].saturating_add(div255(to[i] as u32 * inv));
+    }
+    to[3] = from[3].saturating_add(div255(to[3] as u32 * inv));
+}
+
+// x / 255 rounded to nearest, exact for anything up to 255 * 255
+#[inline(always)]
+fn div255(x: u32) -> u8 {
+    let x = x + 128;
+    ((x + (x >> 8)) >> 8) as u8
+}
@@ -0,0 +1,315 @@
+// Filled and outlined shapes. They all work a pixel row at a time: a pixel gets filled
+// if its center is inside the shape, and the outline of a shape is the pixels of its fill
+// that touch the outside, so outlines and fills of the same shape always line up.
+use super::Screen;
+use crate::objects::{Color, Rect, Vec2, DEPTH};
+
+// What the ends of a thick line look like
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineCap {
+    // stops right at the end points
+    Butt,
+    // carries on half the width past the end points
+    Square,
+    // half circles around the end points
+    Round,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Gradient {
+    // from the left edge to the right edge
+    Horizontal,
+    // from the top edge to the bottom edge
+    Vertical,
+}
+
+// No pixels on a row
+const EMPTY: (i32, i32) = (i32::MAX, i32::MIN);
+
+// Pixels whose centers are in a..b, on a row
+fn half_open_span(a: f32, b: f32) -> (i32, i32) {
+    let x0 = (a - 0.5).ceil() as i32;
+    let x1 = (b - 0.5).ceil() as i32;
+    if x0 < x1 {
+        (x0, x1)
+    } else {
+        EMPTY
+    }
+}
+
+// Pixels whose centers are strictly within half of center, on a row
+fn open_span(center: f32, half: f32) -> (i32, i32) {
+    let x0 = (center - half - 0.5).floor() as i32 + 1;
+    let x1 = (center + half - 0.5).ceil() as i32;
+    if x0 < x1 {
+        (x0, x1)
+    } else {
+        EMPTY
+    }
+}
+
+// Row y of a disc, in framebuffer pixels
+fn disc_span(center: Vec2, radius: f32, y: i32) -> (i32, i32) {
+    let dy = y as f32 + 0.5 - center.y;
+    let h2 = radius * radius - dy * dy;
+    if h2 <= 0.0 {
+        EMPTY
+    } else {
+        open_span(center.x, h2.sqrt())
+    }
+}
+
+fn ellipse_span(center: Vec2, radii: Vec2, y: i32) -> (i32, i32) {
+    let dy = (y as f32 + 0.5 - center.y) / radii.y;
+    let k = 1.0 - dy * dy;
+    if k <= 0.0 {
+        EMPTY
+    } else {
+        open_span(center.x, radii.x * k.sqrt())
+    }
+}
+
+fn rounded_rect_span(r: Rect, radius: f32, y: i32) -> (i32, i32) {
+    let yc = y as f32 + 0.5;
+    if yc < r.y || r.bottom() <= yc {
+        return EMPTY;
+    }
+    // how far into the top or bottom corners this row is
+    let d = if yc < r.y + radius {
+        r.y + radius - yc
+    } else if yc > r.bottom() - radius {
+        yc - (r.bottom() - radius)
+    } else {
+        0.0
+    };
+    let inset = radius - (radius * radius - d * d).max(0.0).sqrt();
+    half_open_span(r.x + inset, r.right() - inset)
+}
+
+// Blend two colors, t = 0 gives from and t = 1 gives to
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let mut out = [0; DEPTH];
+    for ((out, &a), &b) in out.iter_mut().zip(from.iter()).zip(to.iter()) {
+        *out = (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    }
+    out
+}
+
+impl<'fb> Screen<'fb> {
+    // Framebuffer pixels x0..x1 on row y, or as much of them as is inside the clip rect
+    fn hspan(&mut self, y: i32, x0: i32, x1: i32, col: Color) {
+        let clip = self.clip;
+        if y < clip.y || clip.bottom() <= y {
+            return;
+        }
+        let x0 = x0.max(clip.x);
+        let x1 = x1.min(clip.right());
+        if x1 <= x0 {
+            return;
+        }
+        let row = y as usize * self.width * DEPTH;
+        for px in self.framebuffer[row + x0 as usize * DEPTH..row + x1 as usize * DEPTH]
+            .chunks_exact_mut(DEPTH)
+        {
+            px.copy_from_slice(&col);
+        }
+    }
+
+    // Fill rows y0..y1 of a shape that's in one piece on every row
+    fn fill_rows<F>(&mut self, (y0, y1): (i32, i32), span: F, col: Color)
+    where
+        F: Fn(i32) -> (i32, i32),
+    {
+        for y in y0.max(self.clip.y)..y1.min(self.clip.bottom()) {
+            let (x0, x1) = span(y);
+            self.hspan(y, x0, x1, col);
+        }
+    }
+
+    // The pixels fill_rows would fill that are next to one it wouldn't
+    fn outline_rows<F>(&mut self, (y0, y1): (i32, i32), span: F, col: Color)
+    where
+        F: Fn(i32) -> (i32, i32),
+    {
+        let row = |y: i32| if y0 <= y && y < y1 { span(y) } else { EMPTY };
+        for y in y0.max(self.clip.y)..y1.min(self.clip.bottom()) {
+            let (x0, x1) = row(y);
+            if x1 <= x0 {
+                continue;
+            }
+            let (above0, above1) = row(y - 1);
+            let (below0, below1) = row(y + 1);
+            // inside is what has a neighbor on all four sides
+            let inside0 = (x0 + 1).max(above0).max(below0);
+            let inside1 = (x1 - 1).min(above1).min(below1);
+            if inside0 < inside1 {
+                self.hspan(y, x0, inside0, col);
+                self.hspan(y, inside1, x1, col);
+            } else {
+                self.hspan(y, x0, x1, col);
+            }
+        }
+    }
+
+    // Every pixel within r of (x, y), not counting the ones exactly r away
+    pub fn filled_circle(&mut self, (x, y): (i32, i32), r: u64, col: Color) {
+        let center = self.to_framebuffer(Vec2::new(x as f32 + 0.5, y as f32 + 0.5));
+        let r = r as f32;
+        let rows = ((center.y - r).floor() as i32, (center.y + r).ceil() as i32);
+        self.fill_rows(rows, |y| disc_span(center, r, y), col);
+    }
+
+    pub fn circle_lines(&mut self, (x, y): (i32, i32), r: u64, col: Color) {
+        let center = self.to_framebuffer(Vec2::new(x as f32 + 0.5, y as f32 + 0.5));
+        let r = r as f32;
+        let rows = ((center.y - r).floor() as i32, (center.y + r).ceil() as i32);
+        self.outline_rows(rows, |y| disc_span(center, r, y), col);
+    }
+
+    // A disc that doesn't have to sit on the pixel grid
+    fn disc(&mut self, center: Vec2, r: f32, col: Color) {
+        let center = self.to_framebuffer(center);
+        let rows = ((center.y - r).floor() as i32, (center.y + r).ceil() as i32);
+        self.fill_rows(rows, |y| disc_span(center, r, y), col);
+    }
+
+    // The ellipse that just fits inside r
+    pub fn ellipse(&mut self, r: Rect, col: Color) {
+        if r.is_empty() {
+            return;
+        }
+        let r = r.translate(self.origin());
+        let (center, radii) = (r.center(), r.size() / 2.0);
+        let rows = (r.y.floor() as i32, r.bottom().ceil() as i32);
+        self.fill_rows(rows, |y| ellipse_span(center, radii, y), col);
+    }
+
+    pub fn ellipse_lines(&mut self, r: Rect, col: Color) {
+        if r.is_empty() {
+            return;
+        }
+        let r = r.translate(self.origin());
+        let (center, radii) = (r.center(), r.size() / 2.0);
+        let rows = (r.y.floor() as i32, r.bottom().ceil() as i32);
+        self.outline_rows(rows, |y| ellipse_span(center, radii, y), col);
+    }
+
+    // Corners are quarter circles of the given radius, capped at half the shorter side
+    pub fn rounded_rect(&mut self, r: Rect, radius: f32, col: Color) {
+        if r.is_empty() {
+            return;
+        }
+        let r = r.translate(self.origin());
+        let radius = radius.clamp(0.0, r.w.min(r.h) / 2.0);
+        let rows = (r.y.floor() as i32, r.bottom().ceil() as i32);
+        self.fill_rows(rows, |y| rounded_rect_span(r, radius, y), col);
+    }
+
+    pub fn rounded_rect_lines(&mut self, r: Rect, radius: f32, col: Color) {
+        if r.is_empty() {
+            return;
+        }
+        let r = r.translate(self.origin());
+        let radius = radius.clamp(0.0, r.w.min(r.h) / 2.0);
+        let rows = (r.y.floor() as i32, r.bottom().ceil() as i32);
+        self.outline_rows(rows, |y| rounded_rect_span(r, radius, y), col);
+    }
+
+    // Any polygon, convex or not, even if its edges cross. Where edges cross, areas
+    // that are inside an even number of times count as outside (the even-odd rule).
+    pub fn polygon(&mut self, points: &[Vec2], col: Color) {
+        if points.len() < 3 {
+            return;
+        }
+        let origin = self.origin();
+        let points: Vec<Vec2> = points.iter().map(|&p| p + origin).collect();
+        let y_min = points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let y_max = points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+        let y0 = (y_min.floor() as i32).max(self.clip.y);
+        let y1 = (y_max.ceil() as i32).min(self.clip.bottom());
+        let mut crossings = Vec::new();
+        for y in y0..y1 {
+            let yc = y as f32 + 0.5;
+            crossings.clear();
+            for (i, &p) in points.iter().enumerate() {
+                let q = points[(i + 1) % points.len()];
+                // counting an end point for only one of the edges that meet there
+                // keeps the crossings paired up
+                if (p.y <= yc) != (q.y <= yc) {
+                    crossings.push(p.x + (yc - p.y) * (q.x - p.x) / (q.y - p.y));
+                }
+            }
+            crossings.sort_unstable_by(|a, b| a.total_cmp(b));
+            for pair in crossings.chunks_exact(2) {
+                let (x0, x1) = half_open_span(pair[0], pair[1]);
+                self.hspan(y, x0, x1, col);
+            }
+        }
+    }
+
+    // Closed outline through the points
+    pub fn polygon_lines(&mut self, points: &[Vec2], col: Color) {
+        for (i, &p) in points.iter().enumerate() {
+            self.line(p, points[(i + 1) % points.len()], col);
+        }
+        if points.len() == 1 {
+            self.line(points[0], points[0] + Vec2::new(1.0, 0.0), col);
+        }
+    }
+
+    pub fn thick_line(&mut self, from: Vec2, to: Vec2, width: f32, cap: LineCap, col: Color) {
+        if width <= 0.0 {
+            return;
+        }
+        let half = width / 2.0;
+        // a line with no length still has caps, so give it a direction to put them on
+        let dir = if to == from {
+            Vec2::new(1.0, 0.0)
+        } else {
+            (to - from).normalize()
+        };
+        let (from_end, to_end) = match cap {
+            LineCap::Square => (from - dir * half, to + dir * half),
+            _ => (from, to),
+        };
+        // sideways from the line, half the width long
+        let side = Vec2::new(-dir.y, dir.x) * half;
+        self.polygon(
+            &[
+                from_end + side,
+                to_end + side,
+                to_end - side,
+                from_end - side,
+            ],
+            col,
+        );
+        if cap == LineCap::Round {
+            self.disc(from, half, col);
+            self.disc(to, half, col);
+        }
+    }
+
+    // Fill r with colors running from `from` to `to`
+    pub fn gradient_rect(&mut self, r: Rect, from: Color, to: Color, direction: Gradient) {
+        if r.is_empty() {
+            return;
+        }
+        let r = r.translate(self.origin());
+        let (x0, x1) = half_open_span(r.x, r.right());
+        let (y0, y1) = half_open_span(r.y, r.bottom());
+        for y in y0.max(self.clip.y)..y1.min(self.clip.bottom()) {
+            match direction {
+                Gradient::Vertical => {
+                    let t = ((y as f32 + 0.5 - r.y) / r.h).clamp(0.0, 1.0);
+                    self.hspan(y, x0, x1, lerp_color(from, to, t));
+                }
+                Gradient::Horizontal => {
+                    for x in x0.max(self.clip.x)..x1.min(self.clip.right()) {
+                        let t = ((x as f32 + 0.5 - r.x) / r.w).clamp(0.0, 1.0);
+                        self.plot(x, y, lerp_color(from, to, t));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::animation::{Animation, AnimationData};
-use crate::objects::Vec2;
+use crate::objects::{Rect, Vec2};
+use crate::screen::Transform;
 use crate::texture::Texture;
 use std::rc::Rc;
 
@@ -7,6 +8,19 @@ pub struct Sprite {
     image: Rc<Texture>,
     pub animation: Animation,
     pub position: Vec2,
+    pub transform: Transform,
+    // kept sorted by z, see attach
+    pub children: Vec<Attachment>,
+}
+
+// A sprite carried by another one, paper doll style.
+// The child's position is an offset from the anchor, in the parent's frame pixels.
+pub struct Attachment {
+    // name of an anchor on the parent's current frame; the child isn't drawn on frames without it
+    pub anchor: String,
+    // negative goes behind the parent, zero and up in front
+    pub z: i32,
+    pub sprite: Sprite,
 }
 
 impl Sprite {
@@ -15,12 +29,35 @@ impl Sprite {
             image: Rc::clone(image),
             animation,
             position,
+            transform: Transform::IDENTITY,
+            children: Vec::new(),
         }
     }
 
     pub fn set_animation(&mut self, data: &Rc<AnimationData>, force: bool) {
         self.animation.set_animation(data, force);
     }
+
+    // Children with the same z are drawn in the order they were attached
+    pub fn attach(&mut self, anchor: &str, z: i32, child: Sprite) {
+        let idx = self.children.partition_point(|c| c.z <= z);
+        self.children.insert(
+            idx,
+            Attachment {
+                anchor: anchor.to_string(),
+                z,
+                sprite: child,
+            },
+        );
+    }
+
+    // Advances this sprite's animation and all of its children's
+    pub fn animate(&mut self, dt: f32) {
+        self.animation.animate(dt);
+        for child in self.children.iter_mut() {
+            child.sprite.animate(dt);
+        }
+    }
 }
 
 pub trait DrawSpriteExt {
@@ -32,6 +69,91 @@ impl<'fb> DrawSpriteExt for Screen<'fb> {
     fn draw_sprite(&mut self, s: &Sprite) {
         // This works because we're only using a public method of Screen here,
         // and the private fields of sprite are visible inside this module
-        self.bitblt(&s.image, s.animation.get_current_frame(), s.position);
+        s.for_each_blit(|image, frame, position, transform| {
+            self.bitblt_transformed(image, frame, position, transform)
+        });
+    }
+}
+
+impl Sprite {
+    // Calls `blit` for this sprite and each of its children in the order they should be drawn,
+    // with where and how each one goes on screen
+    pub fn for_each_blit<F>(&self, mut blit: F)
+    where
+        F: FnMut(&Rc<Texture>, Rect, Vec2, &Transform),
+    {
+        visit_tree(self, self.position, &self.transform, &mut blit);
+    }
+}
+
+fn visit_tree<F>(s: &Sprite, position: Vec2, transform: &Transform, blit: &mut F)
+where
+    F: FnMut(&Rc<Texture>, Rect, Vec2, &Transform),
+{
+    let (behind, in_front) = s.children.split_at(s.children.partition_point(|c| c.z < 0));
+    for child in behind {
+        visit_child(s, position, transform, child, blit);
+    }
+    blit(
+        &s.image,
+        s.animation.get_current_frame(),
+        position,
+        transform,
+    );
+    for child in in_front {
+        visit_child(s, position, transform, child, blit);
+    }
+}
+
+// Children inherit the parent's transform: a flipped parent mirrors where its children sit
+// as well as the children themselves, and scaling/rotation happen about the parent's pivot.
+// The child's own flips, scale and rotation about its own pivot happen first, and the
+// result is carried along with the parent. A mirrored parent turns its children the other
+// way. Scales are multiplied together, so a child turned inside a parent that's stretched
+// more one way than the other comes out close but not exact, since blits can't shear.
+fn visit_child<F>(
+    parent: &Sprite,
+    parent_pos: Vec2,
+    parent_transform: &Transform,
+    child: &Attachment,
+    blit: &mut F,
+) where
+    F: FnMut(&Rc<Texture>, Rect, Vec2, &Transform),
+{
+    let anchor = match parent.animation.get_current_anchor(&child.anchor) {
+        Some(anchor) => anchor,
+        None => return,
+    };
+    let own = &child.sprite.transform;
+    let local = anchor + child.sprite.position;
+    if parent_transform.is_identity() {
+        visit_tree(&child.sprite, parent_pos + local, own, blit);
+        return;
     }
+    let frame = parent.animation.get_current_frame();
+    let frame_size = Vec2::new(frame.w, frame.h);
+    let child_frame = child.sprite.animation.get_current_frame();
+    let child_size = Vec2::new(child_frame.w, child_frame.h);
+    let mirrored = parent_transform.flip_x != parent_transform.flip_y;
+    let transform = Transform {
+        flip_x: parent_transform.flip_x != own.flip_x,
+        flip_y: parent_transform.flip_y != own.flip_y,
+        scale: Vec2::new(
+            parent_transform.scale.x * own.scale.x,
+            parent_transform.scale.y * own.scale.y,
+        ),
+        rotation: parent_transform.rotation
+            + if mirrored {
+                -own.rotation
+            } else {
+                own.rotation
+            },
+        pivot: own.pivot,
+    };
+    // where the middle of the child's frame lands, through its own transform and then the
+    // parent's, decides where the combined one is drawn from
+    let middle = child_size * 0.5;
+    let lands = parent_transform.apply(local + own.apply(middle, child_size), frame_size);
+    let position = parent_pos + lands - transform.apply(middle, child_size);
+    visit_tree(&child.sprite, position, &transform, blit);
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::rc::Rc;
 
 use crate::objects::{Rect, Vec2};
@@ -7,6 +7,10 @@ use crate::texture::Texture;
 pub struct TextInfo {
     pub info: BTreeMap<char, Rect>,
     image: Rc<Texture>,
+    // drawn in place of any character that neither this font nor its fallbacks have
+    fallback_glyph: Option<Rect>,
+    // searched in order when a character isn't in `info`
+    fallback_fonts: Vec<Rc<TextInfo>>,
 }
 
 impl TextInfo {
@@ -14,6 +18,8 @@ impl TextInfo {
         let mut text_info = TextInfo {
             info: BTreeMap::new(),
             image: Rc::clone(image),
+            fallback_glyph: None,
+            fallback_fonts: Vec::new(),
         };
         for (character, rect) in char_info.iter() {
             text_info.info.insert(*character, *rect);
@@ -21,17 +27,85 @@ impl TextInfo {
         text_info
     }
 
+    // Use the glyph for `ch` (which has to be in this font) for unsupported characters.
+    // Returns false if the font has no such glyph.
+    pub fn set_fallback_glyph(&mut self, ch: char) -> bool {
+        self.fallback_glyph = self.info.get(&ch).copied();
+        self.fallback_glyph.is_some()
+    }
+
+    pub fn add_fallback_font(&mut self, font: &Rc<TextInfo>) {
+        self.fallback_fonts.push(Rc::clone(font));
+    }
+
+    // Looks through this font and then the fallback fonts, but doesn't use the fallback glyph
+    fn find_glyph(&self, ch: char) -> Option<(&Rc<Texture>, Rect)> {
+        if let Some(rect) = self.info.get(&ch) {
+            return Some((&self.image, *rect));
+        }
+        self.fallback_fonts
+            .iter()
+            .find_map(|font| font.find_glyph(ch))
+    }
+
+    pub(crate) fn glyph(&self, ch: char) -> Option<(&Rc<Texture>, Rect)> {
+        self.find_glyph(ch)
+            .or_else(|| self.fallback_glyph.map(|rect| (&self.image, rect)))
+    }
+
+    pub fn supports(&self, ch: char) -> bool {
+        self.find_glyph(ch).is_some()
+    }
+
+    // Every character in `string` that would be drawn with the fallback glyph (or not at all).
+    // Line breaks and tabs are skipped since draw_text_in_rect treats them as spaces.
+    pub fn missing_glyphs(&self, string: &str) -> BTreeSet<char> {
+        string
+            .chars()
+            .filter(|ch| (*ch == ' ' || !ch.is_whitespace()) && !self.supports(*ch))
+            .collect()
+    }
+
     fn get_char_width(&self, ch: char) -> f32 {
-        self.info.get(&ch).map_or(0.0, |rect| rect.w)
+        self.glyph(ch).map_or(0.0, |(_, rect)| rect.w)
+    }
+
+    pub(crate) fn get_char_height(&self) -> f32 {
+        self.glyph(' ').map_or(0.0, |(_, rect)| rect.h)
     }
 
-    fn get_string_width(&self, string: &str) -> f32 {
+    pub(crate) fn get_string_width(&self, string: &str) -> f32 {
         let mut width = 0.0;
         for ch in string.chars() {
             width += self.get_char_width(ch);
         }
         width
     }
+
+    // The lines draw_text_in_rect splits string into for a rect `width` wide, each with the
+    // byte idx in string it starts at. A word too wide for a line of its own gets an empty
+    // line before it.
+    pub fn wrap(&self, string: &str, width: f32) -> Vec<(usize, String)> {
+        let space_width = self.get_char_width(' ');
+        let mut lines = vec![];
+        let mut line = String::from("");
+        let mut start = 0;
+        let mut cur_x = 0.0;
+        for word in string.split_whitespace() {
+            let word_width = self.get_string_width(word);
+            if word_width > width - cur_x {
+                lines.push((start, std::mem::take(&mut line)));
+                cur_x = 0.0;
+                // word is a subslice of string, so this is where it starts
+                start = word.as_ptr() as usize - string.as_ptr() as usize;
+            }
+            line += word;
+            line += " ";
+            cur_x += word_width + space_width;
+        }
+        lines.push((start, line));
+        lines
+    }
 }
 
 pub trait DrawTextExt {
@@ -44,6 +118,14 @@ pub trait DrawTextExt {
         font: &TextInfo,
         show_overflow: bool,
     ) -> Option<usize>;
+
+    fn draw_text_page(
+        &mut self,
+        string: &str,
+        from: usize,
+        rect: Rect,
+        font: &TextInfo,
+    ) -> Option<usize>;
 }
 
 use crate::screen::Screen;
@@ -51,18 +133,18 @@ impl<'fb> DrawTextExt for Screen<'fb> {
     // makes a bunch of assumptions, such as that all the characters are the same height. works because we're using a monospace/height font, won't necessarily work for others
     fn draw_text_at_pos(&mut self, string: &str, pos: Vec2, font: &TextInfo) {
         // starting positions
-        let mut x = pos.x as f32;
-        let y = pos.y as f32;
+        let mut x = pos.x;
+        let y = pos.y;
         for ch in string.chars() {
-            if let Some(rect) = font.info.get(&ch) {
-                self.bitblt(&font.image, *rect, Vec2::new(x, y));
+            if let Some((image, rect)) = font.glyph(ch) {
+                self.bitblt(image, rect, Vec2::new(x, y));
                 x += rect.w;
             }
         }
     }
 
     // assumes using a font with same height characters
-    // option is idx in string of cutoff (like message_index in nemo)
+    // option is the byte idx in string of cutoff (like message_index in nemo), always on a char boundary
     fn draw_text_in_rect(
         &mut self,
         string: &str,
@@ -73,38 +155,35 @@ impl<'fb> DrawTextExt for Screen<'fb> {
         if string.is_empty() {
             return None;
         }
-        let char_height = font.info.get(&' ').unwrap().h;
-        let x = rect.x;
-        let y = rect.y;
-        let width = rect.w;
+        let char_height = font.get_char_height();
         let height = if show_overflow {
-            self.size().1 as f32 - y
+            self.clip_rect().bottom() - rect.y
         } else {
             rect.h
         };
 
-        let space_width = font.get_char_width(' ');
-        let mut line = String::from("");
-        let mut cur_x = 0.0;
-        let mut cur_y = rect.y;
-        let mut num_chars = 0;
-        for word in string.split_whitespace() {
-            let word_width = font.get_string_width(word);
-            if word_width > width - cur_x {
-                self.draw_text_at_pos(&line, Vec2::new(x, cur_y), font);
-                line.clear();
-                cur_x = 0.0;
-                cur_y += char_height;
-                if cur_y >= y + height {
-                    return Some(num_chars);
-                }
+        for (i, (start, line)) in font.wrap(string, rect.w).iter().enumerate() {
+            let cur_y = rect.y + i as f32 * char_height;
+            // the first line always goes in
+            if i > 0 && cur_y >= rect.y + height {
+                return Some(*start);
             }
-            num_chars += word.len() + 1;
-            line += word;
-            line += " ";
-            cur_x += word_width + space_width;
+            self.draw_text_at_pos(line, Vec2::new(rect.x, cur_y), font);
         }
-        self.draw_text_at_pos(&line, Vec2::new(x, cur_y), font);
         None
     }
+
+    // draw_text_in_rect for the part of string from byte idx `from` on, for showing a long
+    // message a box at a time. The cutoff is a byte idx in all of string, so it can be
+    // passed back in as `from` for the next page.
+    fn draw_text_page(
+        &mut self,
+        string: &str,
+        from: usize,
+        rect: Rect,
+        font: &TextInfo,
+    ) -> Option<usize> {
+        self.draw_text_in_rect(&string[from..], rect, font, false)
+            .map(|idx| from + idx)
+    }
 }
@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use crate::objects::Rect;
+use crate::objects::{PixelFormat, Rect, DEPTH, PIXEL_FORMAT};
 use image::{self, RgbaImage};
 use std::path::Path;
 
@@ -7,7 +7,28 @@ pub struct Texture {
     image: Vec<u8>,
     pub width: usize,
     pub height: usize,
-    depth: usize,
+    // runs of same-kind pixels on each row, so bitblt can copy or skip whole stretches;
+    // row y's runs are spans[row_starts[y]..row_starts[y + 1]]
+    spans: Vec<Span>,
+    row_starts: Vec<usize>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SpanKind {
+    // alpha 0, drawing these changes nothing
+    Transparent,
+    // alpha 255, these just overwrite what's there
+    Opaque,
+    // anything in between needs blending
+    Mixed,
+}
+
+// Columns start..end of one row, all of the same kind
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub kind: SpanKind,
 }
 
 enum AlphaChannel {
@@ -22,26 +43,44 @@ impl Texture {
     pub fn new(image: RgbaImage) -> Self {
         let (width, height) = image.dimensions();
         let mut image = image.into_vec();
-        premultiply(&mut image, 4, AlphaChannel::Last);
+        premultiply(&mut image, DEPTH, AlphaChannel::Last);
+        let (spans, row_starts) = find_spans(&image, width as usize);
         Self {
             width: width as usize,
             height: height as usize,
-            depth: 4,
             image,
+            spans,
+            row_starts,
         }
     }
+    pub fn format(&self) -> PixelFormat {
+        PIXEL_FORMAT
+    }
     pub fn depth(&self) -> usize {
-        self.depth
+        DEPTH
     }
     pub fn size(&self) -> (usize, usize) {
         (self.width, self.height)
     }
     pub fn pitch(&self) -> usize {
-        self.width * self.depth
+        self.width * DEPTH
     }
     pub fn buffer(&self) -> &[u8] {
         &self.image
     }
+    pub fn row_spans(&self, row: usize) -> &[Span] {
+        &self.spans[self.row_starts[row]..self.row_starts[row + 1]]
+    }
+    pub fn row_is_opaque(&self, row: usize) -> bool {
+        self.row_spans(row)
+            .iter()
+            .all(|span| span.kind == SpanKind::Opaque)
+    }
+    pub fn row_is_transparent(&self, row: usize) -> bool {
+        self.row_spans(row)
+            .iter()
+            .all(|span| span.kind == SpanKind::Transparent)
+    }
     pub fn valid_frame(&self, frame: Rect) -> bool {
         0.0 <= frame.x
             && (frame.x + frame.w) <= (self.width as f32)
@@ -50,6 +89,33 @@ impl Texture {
     }
 }
 
+fn find_spans(img: &[u8], width: usize) -> (Vec<Span>, Vec<usize>) {
+    let mut spans = Vec::new();
+    let mut row_starts = vec![0];
+    if width == 0 {
+        return (spans, row_starts);
+    }
+    for row in img.chunks_exact(width * DEPTH) {
+        for (x, px) in row.chunks_exact(DEPTH).enumerate() {
+            let kind = match px[DEPTH - 1] {
+                0 => SpanKind::Transparent,
+                255 => SpanKind::Opaque,
+                _ => SpanKind::Mixed,
+            };
+            match spans.last_mut() {
+                Some(span) if x > 0 && span.kind == kind => span.end = x + 1,
+                _ => spans.push(Span {
+                    start: x,
+                    end: x + 1,
+                    kind,
+                }),
+            }
+        }
+        row_starts.push(spans.len());
+    }
+    (spans, row_starts)
+}
+
 fn premultiply(img: &mut [u8], depth: usize, alpha: AlphaChannel) {
     match alpha {
         AlphaChannel::First => {
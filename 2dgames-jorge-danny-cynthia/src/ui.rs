@@ -0,0 +1,511 @@
+// Immediate-mode widgets: every frame the game calls button(), checkbox() and so on in
+// the order they should appear, and each call draws the widget and says what happened
+// to it. Widgets stack down a column, one row each (or a few rows' worth for the
+// wrapped_* ones), so where each one goes only depends on the area, the theme and the text.
+use winit::event::VirtualKeyCode;
+use winit_input_helper::{TextChar, WinitInputHelper};
+
+use crate::input::Input;
+use crate::objects::{Color, Rect, Vec2};
+use crate::screen::{Letterbox, Screen};
+use crate::text::{DrawTextExt, TextInfo};
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum UiAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Activate,
+}
+
+// Arrow keys to move and change values, and `activate` to press buttons and tick boxes
+pub fn default_keys(activate: VirtualKeyCode) -> Input<UiAction> {
+    let mut keys = Input::new();
+    keys.add_key_to_map(UiAction::Up, VirtualKeyCode::Up);
+    keys.add_key_to_map(UiAction::Down, VirtualKeyCode::Down);
+    keys.add_key_to_map(UiAction::Left, VirtualKeyCode::Left);
+    keys.add_key_to_map(UiAction::Right, VirtualKeyCode::Right);
+    keys.add_key_to_map(UiAction::Activate, activate);
+    keys
+}
+
+// Everything the widgets need to know about one frame's input.
+// Games fill it with gather(); tests can just write one.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct UiInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub activate: bool,
+    // in framebuffer pixels, None if the pointer isn't over the framebuffer
+    pub pointer: Option<Vec2>,
+    // the left button went down this frame
+    pub clicked: bool,
+    pub pointer_held: bool,
+    // typed this frame, with '\n' for enter
+    pub text: String,
+    pub backspaces: usize,
+}
+
+impl UiInput {
+    // keys has to have been updated with this frame's events already
+    pub fn gather(
+        keys: &Input<UiAction>,
+        events: &WinitInputHelper,
+        letterbox: &Letterbox,
+    ) -> Self {
+        let mut input = Self {
+            up: keys.is_pressed(UiAction::Up),
+            down: keys.is_pressed(UiAction::Down),
+            left: keys.is_pressed(UiAction::Left),
+            right: keys.is_pressed(UiAction::Right),
+            activate: keys.is_pressed(UiAction::Activate),
+            pointer: events
+                .mouse()
+                .and_then(|(x, y)| letterbox.window_to_logical(x, y)),
+            clicked: events.mouse_pressed(0),
+            pointer_held: events.mouse_held(0),
+            ..Self::default()
+        };
+        for ch in events.text() {
+            match ch {
+                TextChar::Char('\r') | TextChar::Char('\n') => input.text.push('\n'),
+                TextChar::Char(c) if !c.is_control() => input.text.push(c),
+                TextChar::Char(_) => {}
+                TextChar::Back => input.backspaces += 1,
+            }
+        }
+        input
+    }
+
+    // Nothing pressed, clicked or typed. Where the pointer is doesn't count, since it's
+    // set whenever the pointer is over the window; compare it with last frame's to see
+    // whether it moved.
+    pub fn is_idle(&self) -> bool {
+        !(self.up
+            || self.down
+            || self.left
+            || self.right
+            || self.activate
+            || self.clicked
+            || self.pointer_held)
+            && self.text.is_empty()
+            && self.backspaces == 0
+    }
+}
+
+pub struct Theme {
+    pub row_height: f32,
+    // gap between rows
+    pub spacing: f32,
+    // gap between a row's edge and what's in it, and between a label and its control
+    pub padding: f32,
+    // drawn behind the focused row
+    pub highlight: Option<Color>,
+    // a square drawn at the left of the focused row, like a menu pointer
+    pub marker: Option<Color>,
+    pub marker_size: f32,
+    // checkbox and text field outlines, slider tracks
+    pub outline: Color,
+    // checkbox ticks, slider knobs, the text cursor
+    pub accent: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            row_height: 20.0,
+            spacing: 4.0,
+            padding: 4.0,
+            highlight: None,
+            marker: Some([255, 0, 0, 255]),
+            marker_size: 8.0,
+            outline: [0, 0, 0, 255],
+            accent: [255, 0, 0, 255],
+        }
+    }
+}
+
+// What happened to a widget this frame
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Response {
+    // the row it was laid out in
+    pub rect: Rect,
+    pub focused: bool,
+    // pressed with the activate key or clicked, or for text entry, enter was typed
+    pub activated: bool,
+    // its value was changed
+    pub changed: bool,
+}
+
+// Keeps what has to last from one frame to the next: which widget has focus and so on
+pub struct Ui {
+    pub theme: Theme,
+    // index of the focused widget among the ones that can take focus
+    focus: usize,
+    // how many widgets could take focus last frame, for wrapping around
+    focusable: usize,
+    // slider being dragged with the pointer
+    dragging: Option<usize>,
+    last_pointer: Option<Vec2>,
+}
+
+impl Ui {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            theme,
+            focus: 0,
+            focusable: 0,
+            dragging: None,
+            last_pointer: None,
+        }
+    }
+
+    // Index of the focused widget, counting only the ones that can take focus
+    pub fn focus(&self) -> usize {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, focus: usize) {
+        self.focus = focus;
+    }
+
+    // Start laying out widgets down `area` for this frame.
+    // Up and down move the focus before anything is drawn, wrapping around at the ends.
+    pub fn begin<'a, 'fb>(
+        &'a mut self,
+        screen: &'a mut Screen<'fb>,
+        font: &'a TextInfo,
+        input: &'a UiInput,
+        area: Rect,
+    ) -> UiFrame<'a, 'fb> {
+        if self.focusable > 0 {
+            if input.down {
+                self.focus = (self.focus + 1) % self.focusable;
+            }
+            if input.up {
+                self.focus = (self.focus + self.focusable - 1) % self.focusable;
+            }
+        }
+        if !input.pointer_held {
+            self.dragging = None;
+        }
+        UiFrame {
+            ui: self,
+            screen,
+            font,
+            input,
+            area,
+            y: area.y,
+            next_id: 0,
+        }
+    }
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self::new(Theme::default())
+    }
+}
+
+// One frame's worth of widgets; what it learned goes back to the Ui when it's dropped
+pub struct UiFrame<'a, 'fb> {
+    ui: &'a mut Ui,
+    screen: &'a mut Screen<'fb>,
+    font: &'a TextInfo,
+    input: &'a UiInput,
+    area: Rect,
+    // top of the next row
+    y: f32,
+    next_id: usize,
+}
+
+impl<'a, 'fb> Drop for UiFrame<'a, 'fb> {
+    fn drop(&mut self) {
+        self.ui.focusable = self.next_id;
+        if self.ui.focus >= self.next_id {
+            self.ui.focus = self.next_id.saturating_sub(1);
+        }
+        self.ui.last_pointer = self.input.pointer;
+    }
+}
+
+impl<'a, 'fb> UiFrame<'a, 'fb> {
+    // Where the next row goes
+    pub fn next_rect(&self) -> Rect {
+        Rect::new(self.area.x, self.y, self.area.w, self.ui.theme.row_height)
+    }
+
+    // Leave a gap before the next row
+    pub fn space(&mut self, height: f32) {
+        self.y += height;
+    }
+
+    fn take_row(&mut self) -> Rect {
+        self.take_row_of(self.ui.theme.row_height)
+    }
+
+    fn take_row_of(&mut self, height: f32) -> Rect {
+        let rect = Rect::new(self.area.x, self.y, self.area.w, height);
+        self.y += rect.h + self.ui.theme.spacing;
+        rect
+    }
+
+    fn text_pos(&self, x: f32, row: Rect) -> Vec2 {
+        Vec2::new(
+            x,
+            row.y + ((row.h - self.font.get_char_height()) / 2.0).floor(),
+        )
+    }
+
+    // Where a row's text starts, leaving room for the focus marker
+    fn content_x(&self, row: Rect) -> f32 {
+        let theme = &self.ui.theme;
+        row.x
+            + theme.padding
+            + theme
+                .marker
+                .map_or(0.0, |_| theme.marker_size + theme.padding)
+    }
+
+    // Lay out and draw the background and marker for a widget that can take focus
+    fn focusable_row(&mut self) -> Response {
+        let rect = self.take_row();
+        self.focus_row(rect)
+    }
+
+    fn focus_row(&mut self, rect: Rect) -> Response {
+        let id = self.next_id;
+        self.next_id += 1;
+        let pointer_in = self.input.pointer.is_some_and(|p| rect.contains_point(p));
+        // only a pointer that moves takes focus, so it doesn't fight the keyboard
+        if pointer_in && self.input.pointer != self.ui.last_pointer {
+            self.ui.focus = id;
+        }
+        let clicked = pointer_in && self.input.clicked;
+        if clicked {
+            self.ui.focus = id;
+        }
+        let focused = self.ui.focus == id;
+        if focused {
+            let theme = &self.ui.theme;
+            if let Some(col) = theme.highlight {
+                self.screen.rect(rect, col);
+            }
+            if let Some(col) = theme.marker {
+                let size = theme.marker_size;
+                let marker = Rect::new(
+                    rect.x + theme.padding,
+                    rect.y + ((rect.h - size) / 2.0).floor(),
+                    size,
+                    size,
+                );
+                self.screen.rect(marker, col);
+            }
+        }
+        Response {
+            rect,
+            focused,
+            activated: clicked || (focused && self.input.activate),
+            changed: false,
+        }
+    }
+
+    // Text that can't be focused or pressed
+    pub fn label(&mut self, text: &str) -> Rect {
+        let rect = self.take_row();
+        let pos = self.text_pos(self.content_x(rect), rect);
+        self.screen.draw_text_at_pos(text, pos, self.font);
+        rect
+    }
+
+    pub fn button(&mut self, text: &str) -> Response {
+        let response = self.focusable_row();
+        let pos = self.text_pos(self.content_x(response.rect), response.rect);
+        self.screen.draw_text_at_pos(text, pos, self.font);
+        response
+    }
+
+    // Like label, but text too long for the row wraps onto more lines and the row grows
+    // to fit them
+    pub fn wrapped_label(&mut self, text: &str) -> Rect {
+        let rect = self.take_row_of(self.wrapped_height(text));
+        self.draw_wrapped(text, rect);
+        rect
+    }
+
+    // Like button, but text too long for the row wraps onto more lines and the row grows
+    // to fit them
+    pub fn wrapped_button(&mut self, text: &str) -> Response {
+        let rect = self.take_row_of(self.wrapped_height(text));
+        let response = self.focus_row(rect);
+        self.draw_wrapped(text, rect);
+        response
+    }
+
+    // Room left for text in a row, after the marker and the padding on the right
+    fn text_width(&self) -> f32 {
+        let row = self.next_rect();
+        row.right() - self.ui.theme.padding - self.content_x(row)
+    }
+
+    // A row tall enough for text wrapped to fit, with the margins a one line row has
+    fn wrapped_height(&self, text: &str) -> f32 {
+        let lines = self.font.wrap(text, self.text_width()).len().max(1);
+        let char_height = self.font.get_char_height();
+        self.ui.theme.row_height + (lines - 1) as f32 * char_height
+    }
+
+    fn draw_wrapped(&mut self, text: &str, row: Rect) {
+        let top = self.text_pos(
+            self.content_x(row),
+            Rect {
+                h: self.ui.theme.row_height,
+                ..row
+            },
+        );
+        let lines = Rect::new(top.x, top.y, self.text_width(), row.bottom() - top.y);
+        self.screen.draw_text_in_rect(text, lines, self.font, false);
+    }
+
+    // A column of buttons; gives the index of the one that was pressed, if any
+    pub fn menu<S: AsRef<str>>(&mut self, items: &[S]) -> Option<usize> {
+        let mut pressed = None;
+        for (i, item) in items.iter().enumerate() {
+            if self.button(item.as_ref()).activated && pressed.is_none() {
+                pressed = Some(i);
+            }
+        }
+        pressed
+    }
+
+    // A box that activating ticks and unticks
+    pub fn checkbox(&mut self, text: &str, value: &mut bool) -> Response {
+        let mut response = self.focusable_row();
+        let rect = response.rect;
+        let size = self.font.get_char_height().min(rect.h);
+        let x = self.content_x(rect);
+        let tick_box = Rect::new(x, rect.y + ((rect.h - size) / 2.0).floor(), size, size);
+        if response.activated {
+            *value = !*value;
+            response.changed = true;
+        }
+        let theme = &self.ui.theme;
+        self.screen.rect_lines(tick_box, theme.outline);
+        if *value {
+            self.screen.rect(tick_box.expand(-3.0), theme.accent);
+        }
+        let pos = self.text_pos(x + size + theme.padding, rect);
+        self.screen.draw_text_at_pos(text, pos, self.font);
+        response
+    }
+
+    // The right half of a row, for sliders and text fields
+    fn control_rect(&self, row: Rect) -> Rect {
+        let x = row.x + (row.w / 2.0).floor();
+        Rect::new(x, row.y, row.right() - self.ui.theme.padding - x, row.h)
+    }
+
+    // Left and right nudge the value by `step`, and the pointer can drag it anywhere in range
+    pub fn slider(
+        &mut self,
+        text: &str,
+        value: &mut f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) -> Response {
+        let mut response = self.focusable_row();
+        let id = self.next_id - 1;
+        let rect = response.rect;
+        let track = self.control_rect(rect);
+        let old = *value;
+        if response.focused {
+            if self.input.left {
+                *value -= step;
+            }
+            if self.input.right {
+                *value += step;
+            }
+        }
+        if self.input.clicked && self.input.pointer.is_some_and(|p| track.contains_point(p)) {
+            self.ui.dragging = Some(id);
+        }
+        if self.ui.dragging == Some(id) {
+            if let Some(p) = self.input.pointer {
+                let t = ((p.x - track.x) / track.w).clamp(0.0, 1.0);
+                *value = min + (max - min) * t;
+            }
+        }
+        *value = value.clamp(min, max);
+        response.changed = *value != old;
+        // clicking a slider drags it rather than pressing it
+        response.activated = response.focused && self.input.activate;
+
+        let theme = &self.ui.theme;
+        let mid = rect.y + (rect.h / 2.0).floor();
+        self.screen
+            .rect(Rect::new(track.x, mid - 1.0, track.w, 2.0), theme.outline);
+        let t = if max > min {
+            (*value - min) / (max - min)
+        } else {
+            0.0
+        };
+        let knob_w = 6.0;
+        let knob = Rect::new(
+            (track.x + (track.w - knob_w) * t).round(),
+            rect.y + 2.0,
+            knob_w,
+            rect.h - 4.0,
+        );
+        self.screen.rect(knob, theme.accent);
+        let pos = self.text_pos(self.content_x(rect), rect);
+        self.screen.draw_text_at_pos(text, pos, self.font);
+        response
+    }
+
+    // One line of typing, at most max_chars long. Typing only goes in while it's focused,
+    // and enter activates it.
+    pub fn text_input(&mut self, text: &str, value: &mut String, max_chars: usize) -> Response {
+        let mut response = self.focusable_row();
+        let rect = response.rect;
+        let field = self.control_rect(rect);
+        // activate is usually a key that types something too, so only enter counts here
+        response.activated = false;
+        if response.focused {
+            for _ in 0..self.input.backspaces {
+                response.changed |= value.pop().is_some();
+            }
+            for ch in self.input.text.chars() {
+                if ch == '\n' {
+                    response.activated = true;
+                } else if value.chars().count() < max_chars && self.font.supports(ch) {
+                    value.push(ch);
+                    response.changed = true;
+                }
+            }
+        }
+
+        let theme = &self.ui.theme;
+        let (outline, accent, padding) = (theme.outline, theme.accent, theme.padding);
+        self.screen.rect_lines(field, outline);
+        let pos = self.text_pos(self.content_x(rect), rect);
+        self.screen.draw_text_at_pos(text, pos, self.font);
+        // long values scroll so the end stays in view
+        let inner = field.expand(-2.0);
+        let width = self.font.get_string_width(value);
+        let text_x = inner.x + padding.min(inner.w - width - 1.0);
+        self.screen.push_clip(inner);
+        let pos = self.text_pos(text_x, rect);
+        self.screen.draw_text_at_pos(value, pos, self.font);
+        if response.focused {
+            let caret_h = self.font.get_char_height();
+            self.screen
+                .rect(Rect::new(text_x + width, pos.y, 1.0, caret_h), accent);
+        }
+        self.screen.pop_clip();
+        response
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// Notices when a file on disk changes, for reloading things while the game runs.
+// It just looks at the file's modification time now and then, so call changed()
+// as often as you like (e.g. every frame).
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    interval: Duration,
+    last_check: Instant,
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: modified(path),
+            interval: Duration::from_millis(250),
+            last_check: Instant::now(),
+        }
+    }
+
+    // How long to wait between looks at the file
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // True once each time the file is saved (or made, or deleted), checking at most
+    // once per interval
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < self.interval {
+            return false;
+        }
+        self.check_now()
+    }
+
+    // Like changed, but looks right away
+    pub fn check_now(&mut self) -> bool {
+        self.last_check = Instant::now();
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,151 @@
+use std::rc::Rc;
+
+use engine2d::animation::{Animation, AnimationData, Frame, Playback};
+use engine2d::objects::Rect;
+
+// Frames that each take the given seconds and fire "f<index>" when they're reached
+fn data(playback: Playback, durations: &[f32]) -> Rc<AnimationData> {
+    Rc::new(AnimationData {
+        frames: durations
+            .iter()
+            .enumerate()
+            .map(|(i, &duration)| {
+                let rect = Rect::new(8.0 * i as f32, 0.0, 8.0, 8.0);
+                Frame::with_events(rect, duration, &[&format!("f{}", i)])
+            })
+            .collect(),
+        playback,
+    })
+}
+
+// The frame shown after each step of dt
+fn frames(anim: &mut Animation, dt: f32, steps: usize) -> Vec<usize> {
+    (0..steps)
+        .map(|_| {
+            anim.animate(dt);
+            anim.current_frame_index()
+        })
+        .collect()
+}
+
+fn events(anim: &mut Animation) -> Vec<String> {
+    std::iter::from_fn(|| anim.poll_event().map(str::to_string)).collect()
+}
+
+#[test]
+fn once_plays_through_and_holds_the_last_frame() {
+    let mut anim = Animation::new(&data(Playback::Once, &[0.25, 0.25, 0.25]));
+    anim.animate(0.1);
+    assert_eq!(anim.current_frame_index(), 0);
+    anim.animate(0.2);
+    assert_eq!(anim.current_frame_index(), 1);
+    assert_eq!(anim.get_current_frame(), Rect::new(8.0, 0.0, 8.0, 8.0));
+    assert!(!anim.is_finished());
+    anim.animate(1.0);
+    assert_eq!(anim.current_frame_index(), 2);
+    assert!(anim.is_finished());
+    assert_eq!(frames(&mut anim, 0.25, 2), vec![2, 2]);
+}
+
+#[test]
+fn loop_wraps_around_and_never_finishes() {
+    let mut anim = Animation::new(&data(Playback::Loop, &[0.25, 0.25, 0.25]));
+    assert_eq!(frames(&mut anim, 0.25, 5), vec![1, 2, 0, 1, 2]);
+    anim.animate(100.0);
+    assert!(!anim.is_finished());
+}
+
+#[test]
+fn ping_pong_bounces_off_both_ends() {
+    let mut anim = Animation::new(&data(Playback::PingPong, &[0.25, 0.25, 0.25]));
+    assert_eq!(frames(&mut anim, 0.25, 7), vec![1, 2, 1, 0, 1, 2, 1]);
+    assert!(!anim.is_finished());
+    // one frame just stays put
+    let mut anim = Animation::new(&data(Playback::PingPong, &[0.25]));
+    assert_eq!(frames(&mut anim, 0.25, 3), vec![0, 0, 0]);
+}
+
+#[test]
+fn reverse_plays_backwards_once() {
+    let mut anim = Animation::new(&data(Playback::Reverse, &[0.25, 0.25, 0.25]));
+    assert_eq!(anim.current_frame_index(), 2);
+    assert_eq!(frames(&mut anim, 0.25, 2), vec![1, 0]);
+    // like Once, it's done when the last frame has had its time
+    assert!(!anim.is_finished());
+    assert_eq!(frames(&mut anim, 0.25, 2), vec![0, 0]);
+    assert!(anim.is_finished());
+
+    anim.restart();
+    assert_eq!(anim.current_frame_index(), 2);
+    assert!(!anim.is_finished());
+}
+
+#[test]
+fn speed_scales_time() {
+    let mut anim = Animation::new(&data(Playback::Loop, &[0.25, 0.25, 0.25]));
+    anim.speed = 2.0;
+    assert_eq!(frames(&mut anim, 0.125, 2), vec![1, 2]);
+    anim.speed = 0.5;
+    assert_eq!(frames(&mut anim, 0.25, 2), vec![2, 0]);
+    anim.speed = 0.0;
+    assert_eq!(frames(&mut anim, 1.0, 1), vec![0]);
+}
+
+#[test]
+fn events_queue_up_until_polled() {
+    let mut anim = Animation::new(&data(Playback::Loop, &[0.25, 0.25, 0.25]));
+    // the first frame's events fire as soon as it starts
+    assert_eq!(events(&mut anim), vec!["f0"]);
+    assert_eq!(anim.poll_event(), None);
+    // every frame passed through fires, in order, even several in one step
+    anim.animate(1.0);
+    assert_eq!(events(&mut anim), vec!["f1", "f2", "f0", "f1"]);
+
+    // nobody polling means only the newest are kept
+    anim.animate(0.25 * 100.0);
+    let kept = events(&mut anim);
+    assert_eq!(kept.len(), 64);
+    assert_eq!(kept.last().unwrap(), "f2");
+
+    // a restart throws away what hasn't been polled
+    anim.animate(0.25);
+    anim.restart();
+    assert_eq!(events(&mut anim), vec!["f0"]);
+}
+
+#[test]
+fn frames_that_take_no_time() {
+    // nothing to wait for at all, so it stays put instead of spinning forever
+    let mut anim = Animation::new(&data(Playback::Loop, &[0.0, 0.0]));
+    anim.animate(1.0);
+    assert_eq!(anim.current_frame_index(), 0);
+    assert_eq!(events(&mut anim), vec!["f0"]);
+
+    // a zero length frame is passed straight through, but its events still fire
+    let mut anim = Animation::new(&data(Playback::Once, &[0.25, 0.0, 0.25]));
+    events(&mut anim);
+    anim.animate(0.25);
+    assert_eq!(anim.current_frame_index(), 2);
+    assert_eq!(events(&mut anim), vec!["f1", "f2"]);
+}
+
+#[test]
+fn set_animation_restarts_for_different_data() {
+    let walk = data(Playback::Loop, &[0.25, 0.25]);
+    let mut anim = Animation::new(&walk);
+    anim.animate(0.25);
+    events(&mut anim);
+
+    anim.set_animation(&walk, false);
+    assert_eq!(anim.current_frame_index(), 1);
+    anim.set_animation(&walk, true);
+    assert_eq!(anim.current_frame_index(), 0);
+    assert_eq!(events(&mut anim), vec!["f0"]);
+
+    // the same frames, but not the same animation
+    anim.animate(0.25);
+    let copy = data(Playback::Loop, &[0.25, 0.25]);
+    anim.set_animation(&copy, false);
+    assert_eq!(anim.current_frame_index(), 0);
+    assert!(Rc::ptr_eq(anim.data(), &copy));
+}
@@ -0,0 +1,127 @@
+use std::rc::Rc;
+
+use engine2d::animation::{Animation, AnimationData, Frame, Playback};
+use engine2d::animcontroller::{AnimationController, Condition, Transition};
+use engine2d::objects::Rect;
+
+fn clip(playback: Playback, frames: usize) -> Rc<AnimationData> {
+    Rc::new(AnimationData {
+        frames: (0..frames)
+            .map(|i| Frame::new(Rect::new(8.0 * i as f32, 0.0, 8.0, 8.0), 0.25))
+            .collect(),
+        playback,
+    })
+}
+
+// A pigeon that glides, falls when it's going down fast, flaps on a trigger and lands
+// on the ground
+fn pigeon() -> (AnimationController, Animation) {
+    let glide = clip(Playback::Loop, 2);
+    let mut controller = AnimationController::new("glide", &glide);
+    controller.add_state("fall", &clip(Playback::Loop, 2));
+    controller.add_state("flap", &clip(Playback::Once, 2));
+    controller.add_state("land", &clip(Playback::Loop, 1));
+    let float_above = |v| Condition::FloatAbove("vel_y".to_string(), v);
+    let float_below = |v| Condition::FloatBelow("vel_y".to_string(), v);
+    let grounded = |v| Condition::Bool("grounded".to_string(), v);
+    for transition in [
+        Transition::new(None, "land", vec![grounded(true)]),
+        Transition::new(Some("land"), "glide", vec![grounded(false)]),
+        Transition::new(None, "flap", vec![Condition::Trigger("flap".to_string())]),
+        Transition::new(Some("flap"), "glide", vec![Condition::Finished]),
+        Transition::new(Some("glide"), "fall", vec![float_above(1.0)]),
+        Transition::new(Some("fall"), "glide", vec![float_below(0.5)]).with_exit_time(0.5),
+    ] {
+        controller.add_transition(transition);
+    }
+    (controller, Animation::new(&glide))
+}
+
+#[test]
+fn floats_and_bools_guard_transitions() {
+    let (mut controller, mut anim) = pigeon();
+    assert_eq!(controller.update(0.1, &mut anim), None);
+
+    // exactly at the threshold isn't above it
+    controller.set_float("vel_y", 1.0);
+    assert_eq!(controller.update(0.1, &mut anim), None);
+    controller.set_float("vel_y", 1.5);
+    assert_eq!(controller.get_float("vel_y"), Some(1.5));
+    assert_eq!(controller.update(0.1, &mut anim), Some("fall"));
+
+    controller.set_bool("grounded", true);
+    assert_eq!(controller.update(0.1, &mut anim), Some("land"));
+    // "any state" transitions don't keep restarting the state they lead to
+    assert_eq!(controller.update(0.1, &mut anim), None);
+    controller.set_bool("grounded", false);
+    assert_eq!(controller.get_bool("grounded"), Some(false));
+    assert_eq!(controller.update(0.1, &mut anim), Some("glide"));
+}
+
+#[test]
+fn exit_time_holds_a_state() {
+    let (mut controller, mut anim) = pigeon();
+    controller.set_float("vel_y", 2.0);
+    controller.update(0.0, &mut anim);
+    assert_eq!(controller.current_state(), "fall");
+
+    controller.set_float("vel_y", 0.0);
+    assert_eq!(controller.update(0.25, &mut anim), None);
+    assert_eq!(controller.update(0.25, &mut anim), None);
+    // half a second in now
+    assert_eq!(controller.update(0.0, &mut anim), Some("glide"));
+}
+
+#[test]
+fn triggers_are_used_up_and_finishing_moves_on() {
+    let (mut controller, mut anim) = pigeon();
+    controller.set_trigger("flap");
+    assert!(controller.is_triggered("flap"));
+    assert_eq!(controller.update(0.0, &mut anim), Some("flap"));
+    assert!(!controller.is_triggered("flap"));
+    assert_eq!(controller.current_state(), "flap");
+
+    // a trigger can restart the state it leads to
+    controller.set_trigger("flap");
+    anim.animate(0.25);
+    assert_eq!(anim.current_frame_index(), 1);
+    assert_eq!(controller.update(0.0, &mut anim), Some("flap"));
+    assert_eq!(anim.current_frame_index(), 0);
+
+    // until the flap plays out, nothing else happens
+    assert_eq!(controller.update(0.25, &mut anim), None);
+    assert_eq!(controller.update(0.25, &mut anim), None);
+    assert!(anim.is_finished());
+    assert_eq!(controller.update(0.0, &mut anim), Some("glide"));
+
+    // a trigger nothing uses stays set until it's reset
+    controller.set_trigger("unused");
+    controller.update(0.1, &mut anim);
+    assert!(controller.is_triggered("unused"));
+    controller.reset_trigger("unused");
+    assert!(!controller.is_triggered("unused"));
+}
+
+#[test]
+fn time_in_state_counts_from_entering() {
+    let (mut controller, mut anim) = pigeon();
+    assert_eq!(controller.current_state(), "glide");
+    assert_eq!(controller.time_in_state(), 0.0);
+    controller.update(0.25, &mut anim);
+    controller.update(0.25, &mut anim);
+    assert_eq!(controller.time_in_state(), 0.5);
+    // and goes as fast as the animation
+    anim.speed = 2.0;
+    controller.update(0.25, &mut anim);
+    assert_eq!(controller.time_in_state(), 1.0);
+
+    // the step a state is entered on counts towards it
+    controller.set_trigger("flap");
+    controller.update(0.125, &mut anim);
+    assert_eq!(controller.current_state(), "flap");
+    assert_eq!(controller.time_in_state(), 0.25);
+
+    controller.play("land", &mut anim);
+    assert_eq!(controller.current_state(), "land");
+    assert_eq!(controller.time_in_state(), 0.0);
+}
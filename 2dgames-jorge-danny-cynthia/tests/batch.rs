@@ -0,0 +1,87 @@
+use engine2d::batch::SpriteBatch;
+use engine2d::objects::{Color, Rect};
+use engine2d::screen::Screen;
+
+const RED: Color = [255, 0, 0, 255];
+const GREEN: Color = [0, 255, 0, 255];
+const BLUE: Color = [0, 0, 255, 255];
+
+// Flushes batch onto a 4x4 screen and says what ended up at (1, 1), where every rect
+// queued here overlaps
+fn top(batch: &mut SpriteBatch) -> Color {
+    let mut frame = vec![0; 4 * 4 * 4];
+    batch.flush(&mut Screen::wrap(&mut frame, 4, 4));
+    let i = (4 + 1) * 4;
+    [frame[i], frame[i + 1], frame[i + 2], frame[i + 3]]
+}
+
+// A rect over (1, 1) whose bottom edge is at bottom
+fn rect(bottom: f32) -> Rect {
+    Rect::new(0.0, 0.0, 3.0, bottom)
+}
+
+#[test]
+fn layer_then_z_then_queue_order() {
+    let mut batch = SpriteBatch::new();
+    batch.rect(rect(2.0), RED, 1, 0);
+    batch.rect(rect(2.0), GREEN, 0, 5);
+    assert_eq!(top(&mut batch), RED);
+
+    batch.rect(rect(2.0), RED, 0, 1);
+    batch.rect(rect(2.0), GREEN, 0, 0);
+    assert_eq!(top(&mut batch), RED);
+
+    // equal keys keep the order they were queued in, however many there are
+    for n in 1..40 {
+        for i in 0..n {
+            let color = if i == n - 1 { BLUE } else { GREEN };
+            batch.rect(rect(2.0), color, 0, 0);
+        }
+        assert_eq!(top(&mut batch), BLUE, "{} rects", n);
+    }
+    assert!(batch.is_empty());
+}
+
+#[test]
+fn y_sort_puts_lower_things_in_front() {
+    let mut batch = SpriteBatch::new();
+    batch.rect(rect(3.0), RED, 0, 0);
+    batch.rect(rect(2.0), GREEN, 0, 0);
+    // off, it's just queue order
+    assert_eq!(top(&mut batch), GREEN);
+
+    batch.y_sort = true;
+    batch.rect(rect(3.0), RED, 0, 0);
+    batch.rect(rect(2.0), GREEN, 0, 0);
+    assert_eq!(top(&mut batch), RED);
+
+    // but only among things on the same layer and z
+    batch.rect(rect(3.0), RED, 0, 0);
+    batch.rect(rect(2.0), GREEN, 0, 1);
+    assert_eq!(top(&mut batch), GREEN);
+
+    // and the same bottom edge falls back on queue order
+    batch.rect(rect(2.0), RED, 0, 0);
+    batch.rect(rect(2.0), GREEN, 0, 0);
+    assert_eq!(top(&mut batch), GREEN);
+}
+
+#[test]
+fn a_warmed_up_batch_keeps_its_capacity() {
+    let mut batch = SpriteBatch::new();
+    let mut frame = vec![0; 4 * 4 * 4];
+    let mut queue_frame = |batch: &mut SpriteBatch| {
+        for i in 0..100 {
+            batch.rect(rect(1.0 + (i % 3) as f32), RED, i % 4, -i);
+        }
+        assert_eq!(batch.len(), 100);
+        batch.flush(&mut Screen::wrap(&mut frame, 4, 4));
+        assert!(batch.is_empty());
+    };
+    queue_frame(&mut batch);
+    let warmed = batch.capacity();
+    assert!(warmed >= 100);
+    queue_frame(&mut batch);
+    queue_frame(&mut batch);
+    assert_eq!(batch.capacity(), warmed);
+}
@@ -0,0 +1,275 @@
+use engine2d::objects::{Rect, Vec2};
+use engine2d::screen::{Screen, Transform};
+use engine2d::texture::{Span, SpanKind, Texture};
+use image::{Rgba, RgbaImage};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[path = "../benches/common/mod.rs"]
+mod common;
+use common::{background, noise_texture, portrait_texture, reference_bitblt, reference_composite};
+
+#[test]
+fn spans_cover_each_row() {
+    let img = RgbaImage::from_fn(5, 2, |x, y| match (x, y) {
+        (0, 0) | (1, 0) => Rgba([0, 0, 0, 0]),
+        (2, 0) => Rgba([9, 9, 9, 128]),
+        (_, 0) => Rgba([9, 9, 9, 255]),
+        _ => Rgba([1, 2, 3, 255]),
+    });
+    let tex = Texture::new(img);
+    let span = |start, end, kind| Span { start, end, kind };
+    assert_eq!(
+        tex.row_spans(0),
+        &[
+            span(0, 2, SpanKind::Transparent),
+            span(2, 3, SpanKind::Mixed),
+            span(3, 5, SpanKind::Opaque),
+        ]
+    );
+    assert_eq!(tex.row_spans(1), &[span(0, 5, SpanKind::Opaque)]);
+    assert!(!tex.row_is_opaque(0));
+    assert!(tex.row_is_opaque(1));
+    assert!(!tex.row_is_transparent(1));
+}
+
+#[test]
+fn composite_matches_float_math() {
+    // every source alpha against every destination value, through a 1x1 blit
+    for a in 0..=255u8 {
+        let tex = Texture::new(RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, a])));
+        let src = tex.buffer().to_vec();
+        for t in 0..=255u8 {
+            let mut expected = [t, t, t, t];
+            reference_composite(&mut expected, &src);
+            let mut actual = [t, t, t, t];
+            Screen::wrap(&mut actual, 1, 1).bitblt(&tex, Rect::new(0.0, 0.0, 1.0, 1.0), Vec2::ZERO);
+            assert_eq!(expected, actual, "alpha {} over {}", a, t);
+        }
+    }
+}
+
+#[test]
+fn bitblt_matches_reference() {
+    const W: usize = 64;
+    const H: usize = 48;
+    let textures = [portrait_texture(40, 30, 10), noise_texture(40, 30, 11)];
+    let mut rng = StdRng::seed_from_u64(12);
+    for _ in 0..500 {
+        let tex = &textures[rng.gen_range(0..textures.len())];
+        let fx = rng.gen_range(0..40);
+        let fy = rng.gen_range(0..30);
+        let from = Rect::new(
+            fx as f32,
+            fy as f32,
+            rng.gen_range(1..=40 - fx) as f32,
+            rng.gen_range(1..=30 - fy) as f32,
+        );
+        // plenty of these hang off the edges of the screen
+        let to = Vec2::new(rng.gen_range(-45..70) as f32, rng.gen_range(-35..55) as f32);
+        let bg = background(W, H, rng.gen());
+        let mut expected = bg.clone();
+        reference_bitblt(&mut expected, W, H, tex, from, to);
+        let mut actual = bg;
+        Screen::wrap(&mut actual, W, H).bitblt(tex, from, to);
+        assert!(expected == actual, "{:?} at {:?}", from, to);
+    }
+}
+
+#[test]
+fn bitblt_faded_scales_the_source() {
+    const W: usize = 64;
+    const H: usize = 48;
+    let tex = portrait_texture(40, 30, 13);
+    let from = Rect::new(0.0, 0.0, 40.0, 30.0);
+    let bg = background(W, H, 14);
+    for &to in [Vec2::new(5.0, 3.0), Vec2::new(-20.0, 30.0)].iter() {
+        // all the way is a plain blit, and none is nothing
+        let mut expected = bg.clone();
+        Screen::wrap(&mut expected, W, H).bitblt(&tex, from, to);
+        let mut actual = bg.clone();
+        Screen::wrap(&mut actual, W, H).bitblt_faded(&tex, from, to, 255);
+        assert!(expected == actual);
+        let mut actual = bg.clone();
+        Screen::wrap(&mut actual, W, H).bitblt_faded(&tex, from, to, 0);
+        assert!(bg == actual);
+
+        // halfway is every source pixel scaled by 128/255, composited as usual
+        let mut expected = bg.clone();
+        for y in 0..30 {
+            for x in 0..40 {
+                let (sx, sy) = (to.x as i32 + x, to.y as i32 + y);
+                if sx < 0 || sy < 0 || sx >= W as i32 || sy >= H as i32 {
+                    continue;
+                }
+                let i = y as usize * tex.pitch() + x as usize * 4;
+                let p: Vec<u8> = tex.buffer()[i..i + 4]
+                    .iter()
+                    .map(|c| (*c as f32 * 128.0 / 255.0).round() as u8)
+                    .collect();
+                let d = (sy as usize * W + sx as usize) * 4;
+                reference_composite(&mut expected[d..d + 4], &p);
+            }
+        }
+        let mut actual = bg.clone();
+        Screen::wrap(&mut actual, W, H).bitblt_faded(&tex, from, to, 128);
+        let off = expected
+            .iter()
+            .zip(actual.iter())
+            .map(|(e, a)| (*e as i32 - *a as i32).abs())
+            .max()
+            .unwrap();
+        assert!(off <= 1, "{} at {:?}", off, to);
+    }
+}
+
+// A texture of four square quadrants `size` pixels wide: r g on top, b w below
+fn quadrants(size: u32) -> Texture {
+    Texture::new(RgbaImage::from_fn(2 * size, 2 * size, |x, y| {
+        match (x < size, y < size) {
+            (true, true) => Rgba([255, 0, 0, 255]),
+            (false, true) => Rgba([0, 255, 0, 255]),
+            (true, false) => Rgba([0, 0, 255, 255]),
+            (false, false) => Rgba([255, 255, 255, 255]),
+        }
+    }))
+}
+
+// An 8x8 screen after blitting all of tex at to, with each pixel as the quadrant it came
+// from, or . for nothing
+fn blit_transformed(tex: &Texture, to: Vec2, transform: Transform) -> Vec<String> {
+    let mut frame = vec![0; 8 * 8 * 4];
+    let (w, h) = tex.size();
+    Screen::wrap(&mut frame, 8, 8).bitblt_transformed(
+        tex,
+        Rect::new(0.0, 0.0, w as f32, h as f32),
+        to,
+        &transform,
+    );
+    frame
+        .chunks_exact(8 * 4)
+        .map(|row| {
+            row.chunks_exact(4)
+                .map(|pixel| match pixel {
+                    [255, 0, 0, 255] => 'r',
+                    [0, 255, 0, 255] => 'g',
+                    [0, 0, 255, 255] => 'b',
+                    [255, 255, 255, 255] => 'w',
+                    [0, 0, 0, 0] => '.',
+                    _ => '?',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn transform() -> Transform {
+    Transform::IDENTITY
+}
+
+#[test]
+fn transformed_blits_flip() {
+    let tex = quadrants(1);
+    let at = Vec2::new(1.0, 1.0);
+    let flipped = |flip_x, flip_y| {
+        let picture = blit_transformed(
+            &tex,
+            at,
+            Transform {
+                flip_x,
+                flip_y,
+                ..transform()
+            },
+        );
+        picture[1..3]
+            .iter()
+            .map(|row| row[1..3].to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(flipped(false, false), vec!["rg", "bw"]);
+    assert_eq!(flipped(true, false), vec!["gr", "wb"]);
+    assert_eq!(flipped(false, true), vec!["bw", "rg"]);
+    assert_eq!(flipped(true, true), vec!["wb", "gr"]);
+    // nothing outside the frame
+    let picture = blit_transformed(
+        &tex,
+        at,
+        Transform {
+            flip_x: true,
+            ..transform()
+        },
+    );
+    assert_eq!(picture[0], "........");
+    assert_eq!(picture[1], ".gr.....");
+    assert_eq!(picture[3], "........");
+}
+
+#[test]
+fn transformed_blits_scale() {
+    let doubled = Transform {
+        scale: Vec2::new(2.0, 2.0),
+        ..transform()
+    };
+    assert_eq!(
+        blit_transformed(&quadrants(1), Vec2::new(1.0, 2.0), doubled),
+        vec![
+            "........", "........", ".rrgg...", ".rrgg...", ".bbww...", ".bbww...", "........",
+            "........",
+        ]
+    );
+    let halved = Transform {
+        scale: Vec2::new(0.5, 0.5),
+        ..transform()
+    };
+    assert_eq!(
+        blit_transformed(&quadrants(2), Vec2::new(3.0, 0.0), halved),
+        vec![
+            "...rg...", "...bw...", "........", "........", "........", "........", "........",
+            "........",
+        ]
+    );
+}
+
+#[test]
+fn transformed_blits_rotate_about_the_pivot() {
+    // a quarter turn clockwise about the frame's top left, which stays at (4, 4)
+    let turned = Transform {
+        rotation: std::f32::consts::FRAC_PI_2,
+        ..transform()
+    };
+    assert_eq!(
+        blit_transformed(&quadrants(1), Vec2::new(4.0, 4.0), turned),
+        vec![
+            "........", "........", "........", "........", "..br....", "..wg....", "........",
+            "........",
+        ]
+    );
+    // the same turn about the middle keeps the frame where it was
+    let centered = Transform {
+        pivot: Vec2::new(1.0, 1.0),
+        ..turned
+    };
+    let picture = blit_transformed(&quadrants(1), Vec2::new(4.0, 4.0), centered);
+    assert_eq!(&picture[4][4..6], "br");
+    assert_eq!(&picture[5][4..6], "wg");
+}
+
+#[test]
+fn transformed_blits_clip_to_the_screen() {
+    let doubled = Transform {
+        scale: Vec2::new(2.0, 2.0),
+        ..transform()
+    };
+    // hanging off the top left
+    let picture = blit_transformed(&quadrants(1), Vec2::new(-1.0, -1.0), doubled);
+    assert_eq!(
+        &picture[..4],
+        &["rgg.....", "bww.....", "bww.....", "........"]
+    );
+    // and off the bottom right
+    let picture = blit_transformed(&quadrants(1), Vec2::new(6.0, 6.0), doubled);
+    assert_eq!(&picture[5..], &["........", "......rr", "......rr"]);
+    // and right off the screen
+    let picture = blit_transformed(&quadrants(1), Vec2::new(20.0, -9.0), doubled);
+    assert!(picture.iter().all(|row| row == "........"));
+}
@@ -0,0 +1,42 @@
+// Golden images: what a test drew, kept in tests/golden/<name>.png to compare against.
+// They only catch changes, so tests should also check what matters about the drawing
+// directly. After changing how something draws on purpose, run the tests with
+// UPDATE_GOLDEN=1 to write new images, and look at them before committing.
+use std::path::PathBuf;
+
+use engine2d::objects::DEPTH;
+use image::RgbaImage;
+
+// Compares a width x height framebuffer to tests/golden/<name>.png, or writes it there
+pub fn golden_image(name: &str, width: usize, height: usize, fb: Vec<u8>) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("png");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        RgbaImage::from_raw(width as u32, height as u32, fb)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        return;
+    }
+    let expected = image::open(&path)
+        .unwrap_or_else(|e| panic!("can't open {}: {}", path.display(), e))
+        .into_rgba8();
+    assert_eq!(expected.dimensions(), (width as u32, height as u32));
+    if expected.as_raw() != &fb {
+        let (i, _) = expected
+            .as_raw()
+            .chunks_exact(DEPTH)
+            .zip(fb.chunks_exact(DEPTH))
+            .enumerate()
+            .find(|(_, (a, b))| a != b)
+            .unwrap();
+        panic!(
+            "{} doesn't match its golden image, first difference at ({}, {})",
+            name,
+            i % width,
+            i / width
+        );
+    }
+}
@@ -0,0 +1,979 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::rc::Rc;
+
+use engine2d::dialogue::{
+    self, Character, DialogueError, Effect, Expr, GraphFormat, Kind, LoadError, NamedScene, Next,
+    Problem, Response, Runner, Scene, Slot, Story, Transition, Trouble, Value, WhenFalse,
+};
+
+const STORY: &str = r#"{
+    "story_name": "test",
+    "scenes": [
+        {
+            "scene_name": "intro",
+            "scene": {
+                "name": "Nemo",
+                "message": "hi",
+                "responses": [
+                    { "response": "go", "goto": "walk" },
+                    { "response": "stay", "goto": "home" }
+                ]
+            }
+        },
+        { "scene_name": "walk", "scene": { "name": "Nemo", "message": "off we go", "next": "home" } },
+        { "scene_name": "home", "scene": { "name": "", "message": "the end" } },
+        {
+            "scene_name": "old",
+            "scene": {
+                "name": "",
+                "message": "written the old way",
+                "responses": [{ "response": "", "goto": "home" }]
+            }
+        },
+        {
+            "scene_name": "lost",
+            "scene": {
+                "name": "",
+                "message": "nowhere to go",
+                "next": "nowhere"
+            }
+        }
+    ]
+}"#;
+
+fn story() -> Rc<Story> {
+    Rc::new(Story::from_json(STORY).unwrap())
+}
+
+#[test]
+fn scenes_get_explicit_kinds() {
+    let story = story();
+    assert!(matches!(story.scene("intro").unwrap().next, Next::Choice(ref r) if r.len() == 2));
+    assert_eq!(
+        story.scene("walk").unwrap().next,
+        Next::Continue("home".to_string())
+    );
+    assert_eq!(story.scene("home").unwrap().next, Next::End);
+    // a single response with no text is how stories used to say "carry on"
+    assert_eq!(
+        story.scene("old").unwrap().next,
+        Next::Continue("home".to_string())
+    );
+    assert!(story.scene("missing").is_none());
+}
+
+#[test]
+fn runner_follows_choices_and_continues() {
+    let mut runner = Runner::new(&story(), "intro").unwrap();
+    assert_eq!(runner.scene().message, "hi");
+    let texts: Vec<String> = runner.choices().into_iter().map(|c| c.text).collect();
+    assert_eq!(texts, vec!["go", "stay"]);
+    assert!(!runner.is_end());
+
+    runner.choose(0).unwrap();
+    assert_eq!(runner.current(), "walk");
+    assert!(runner.choices().is_empty());
+
+    runner.advance().unwrap();
+    assert_eq!(runner.current(), "home");
+    assert!(runner.is_end());
+
+    runner.goto("intro").unwrap();
+    runner.choose(1).unwrap();
+    assert_eq!(runner.current(), "home");
+}
+
+#[test]
+fn unknown_scenes_are_errors() {
+    let story = story();
+    assert_eq!(
+        Runner::new(&story, "nope").err(),
+        Some(DialogueError::UnknownScene("nope".to_string()))
+    );
+    let mut runner = Runner::new(&story, "lost").unwrap();
+    assert_eq!(
+        runner.advance(),
+        Err(DialogueError::UnknownScene("nowhere".to_string()))
+    );
+    // a bad goto leaves the runner where it was
+    assert_eq!(runner.current(), "lost");
+}
+
+#[test]
+fn advancing_from_a_choice_or_choosing_past_the_end_fails() {
+    let mut runner = Runner::new(&story(), "intro").unwrap();
+    assert_eq!(
+        runner.advance(),
+        Err(DialogueError::NotContinue("intro".to_string()))
+    );
+    assert_eq!(runner.choose(2), Err(DialogueError::NoSuchChoice(2)));
+    assert_eq!(runner.current(), "intro");
+    assert!(runner.history().is_empty());
+}
+
+#[test]
+fn scenes_cant_have_both_next_and_responses() {
+    let json = r#"{
+        "story_name": "Both",
+        "scenes": [
+            {"scene_name": "intro", "scene": {
+                "name": "", "message": "hi", "next": "end",
+                "responses": [{"response": "bye", "goto": "end"}]
+            }},
+            {"scene_name": "end", "scene": {"name": "", "message": "bye"}}
+        ]
+    }"#;
+    let err = Story::from_json(json).unwrap_err().to_string();
+    assert!(
+        err.contains("has both \"next\" and \"responses\""),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn json_round_trips_in_the_new_form() {
+    let story = story();
+    let json = story.to_json();
+    assert!(!json.contains(r#""response": """#));
+    let reloaded = Story::from_json(&json).unwrap();
+    assert_eq!(reloaded, *story);
+    assert_eq!(
+        reloaded.scene("intro").unwrap().choices()[1],
+        Response::new("stay", "home")
+    );
+}
+
+#[test]
+fn nemo_script_loads() {
+    let story = Story::from_json(include_str!("../src/bin/nemo/script.json")).unwrap();
+    let mut runner = Runner::new(&Rc::new(story), "intro").unwrap();
+    runner.choose(1).unwrap();
+    assert_eq!(runner.current(), "ending1");
+    assert!(runner.is_end());
+}
+
+// A story from (scene_name, speaker, targets), where no targets is an ending,
+// one unnamed target carries on and anything else is a choice
+fn graph(scenes: &[(&str, &str, &[&str])]) -> Story {
+    Story {
+        story_name: "graph".to_string(),
+        variables: Default::default(),
+        scenes: scenes
+            .iter()
+            .map(|&(scene_name, speaker, targets)| NamedScene {
+                scene_name: scene_name.to_string(),
+                scene: Scene::new(
+                    speaker,
+                    "...",
+                    match targets {
+                        [] => Next::End,
+                        [goto] => Next::Continue(goto.to_string()),
+                        _ => Next::Choice(
+                            targets
+                                .iter()
+                                .map(|goto| Response::new(goto, goto))
+                                .collect(),
+                        ),
+                    },
+                ),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn a_sound_story_has_no_problems() {
+    let story = graph(&[
+        ("intro", "", &["a", "b"]),
+        ("a", "", &["intro"]),
+        ("b", "", &[]),
+    ]);
+    assert!(dialogue::validate(&story, "intro").is_empty());
+}
+
+#[test]
+fn validate_finds_broken_links() {
+    let story = graph(&[
+        ("intro", "", &["a", "gone"]),
+        ("a", "", &[]),
+        ("a", "", &[]),
+        ("island", "", &[]),
+    ]);
+    let problems = dialogue::validate(&story, "intro");
+    assert_eq!(
+        problems,
+        vec![
+            Problem::UnknownTarget {
+                scene: "intro".to_string(),
+                goto: "gone".to_string(),
+            },
+            Problem::DuplicateScene("a".to_string()),
+            Problem::Unreachable("island".to_string()),
+        ]
+    );
+    assert!(problems[0].is_error() && problems[1].is_error() && !problems[2].is_error());
+
+    assert_eq!(
+        dialogue::validate(&story, "start")[0],
+        Problem::MissingStart("start".to_string())
+    );
+}
+
+#[test]
+fn validate_finds_loops_with_no_way_out() {
+    let story = graph(&[
+        ("intro", "", &["end", "x"]),
+        ("end", "", &[]),
+        // x and y only lead to each other
+        ("x", "", &["y"]),
+        ("y", "", &["x", "y"]),
+        // stuck on its own, but nobody goes there
+        ("alone", "", &["alone"]),
+        // a loop that can leave is fine
+        ("p", "", &["q"]),
+        ("q", "", &["p", "end"]),
+    ]);
+    let problems = dialogue::validate(&story, "intro");
+    assert!(problems.contains(&Problem::NoExit(vec!["x".to_string(), "y".to_string()])));
+    let no_exits = problems
+        .iter()
+        .filter(|p| matches!(p, Problem::NoExit(_)))
+        .count();
+    assert_eq!(no_exits, 1);
+    assert!(problems.contains(&Problem::Unreachable("alone".to_string())));
+}
+
+// The line for each node or edge that has all of parts in it
+fn graph_line<'g>(graph: &'g str, parts: &[&str]) -> &'g str {
+    graph
+        .lines()
+        .find(|line| parts.iter().all(|part| line.contains(part)))
+        .unwrap_or_else(|| panic!("no line with {:?} in\n{}", parts, graph))
+}
+
+#[test]
+fn graphs_show_dead_ends_and_unreachable_scenes() {
+    let mut story = graph(&[
+        ("intro", "Nemo", &["end", "x"]),
+        ("end", "", &[]),
+        ("x", "Dory", &["y", "gone"]),
+        ("y", "", &["x"]),
+        ("alone", "", &["end"]),
+    ]);
+    story.scenes[0].scene.message =
+        "A message \"quoted\"\nand long enough to be cut short".to_string();
+
+    let dot = dialogue::graph(&story, "intro", GraphFormat::Dot);
+    assert!(dot.starts_with("digraph \"graph\" {"));
+    assert_eq!(
+        graph_line(&dot, &["s0 ["]),
+        r#"    s0 [label="intro\nNemo: A message \"quoted\" and long eno…"];"#
+    );
+    assert!(graph_line(&dot, &["s1 ["]).contains("peripheries=2"));
+    // x and y can't get to the end; going nowhere doesn't count
+    for id in ["s2 [", "s3 ["] {
+        assert!(graph_line(&dot, &[id]).contains("style=\"filled\""));
+    }
+    assert!(graph_line(&dot, &["s4 ["]).contains("style=\"dashed\""));
+    assert_eq!(
+        graph_line(&dot, &["s5 ["]),
+        r#"    s5 [label="missing: gone", shape=plaintext, fontcolor=red];"#
+    );
+    assert_eq!(
+        graph_line(&dot, &["s0 -> s2"]),
+        r#"    s0 -> s2 [label="x"];"#
+    );
+    assert_eq!(graph_line(&dot, &["s3 -> s2"]), "    s3 -> s2;");
+
+    let mermaid = dialogue::graph(&story, "intro", GraphFormat::Mermaid);
+    assert!(mermaid.contains("\nflowchart TD\n"));
+    assert!(graph_line(&mermaid, &["s0["]).contains("#quot;quoted#quot;"));
+    assert_eq!(graph_line(&mermaid, &["s1("]), r#"    s1(["end<br/>..."])"#);
+    assert_eq!(
+        graph_line(&mermaid, &["s2 -->", "s5"]),
+        r#"    s2 -->|"gone"| s5"#
+    );
+    assert_eq!(
+        graph_line(&mermaid, &["class ", "deadend"]),
+        "    class s2,s3 deadend"
+    );
+    assert_eq!(
+        graph_line(&mermaid, &["class ", "unreachable"]),
+        "    class s4 unreachable"
+    );
+    assert_eq!(
+        graph_line(&mermaid, &["class ", " missing"]),
+        "    class s5 missing"
+    );
+}
+
+#[test]
+fn missing_sprites_are_grouped_by_speaker() {
+    let story = graph(&[
+        ("intro", "Nemo", &["a"]),
+        ("a", "Dory", &["b"]),
+        ("b", "", &["c"]),
+        ("c", "Dory", &[]),
+    ]);
+    assert_eq!(
+        dialogue::missing_sprites(&story, |sprite| sprite == "Nemo"),
+        vec![Problem::MissingSprite {
+            sprite: "Dory".to_string(),
+            scenes: vec!["a".to_string(), "c".to_string()],
+        }]
+    );
+}
+
+#[test]
+fn stage_round_trips_and_leaves_out_defaults() {
+    let json = r#"{
+        "story_name": "stage",
+        "scenes": [
+            {
+                "scene_name": "intro",
+                "scene": {
+                    "name": "Marlin",
+                    "message": "where's nemo?",
+                    "background": "reef",
+                    "stage": [
+                        { "character": "Marlin", "slot": "left", "expression": "worried", "enter": "slide" },
+                        { "character": "Dory", "exit": "fade" }
+                    ]
+                }
+            }
+        ]
+    }"#;
+    let story = Story::from_json(json).unwrap();
+    let scene = story.scene("intro").unwrap();
+    assert_eq!(scene.background.as_deref(), Some("reef"));
+    assert_eq!(
+        scene.cast(),
+        vec![
+            Character::new("Marlin", Slot::Left)
+                .with_expression("worried")
+                .with_transitions(Transition::Slide, Transition::Cut),
+            Character::new("Dory", Slot::Center)
+                .with_transitions(Transition::Cut, Transition::Fade),
+        ]
+    );
+    assert_eq!(scene.cast()[0].sprite(), "Marlin_worried");
+    assert_eq!(Story::from_json(&story.to_json()).unwrap(), story);
+    assert!(!story.to_json().contains("center"));
+    assert!(!story.to_json().contains("cut"));
+
+    // without a stage the speaker stands in the middle, and nobody does for narration
+    let plain = Scene::new("Nemo", "hi", Next::End);
+    assert_eq!(plain.cast(), vec![Character::new("Nemo", Slot::Center)]);
+    assert_eq!(Scene::new("", "...", Next::End).cast(), vec![]);
+    assert_eq!(plain.clone().with_stage(vec![]).cast(), vec![]);
+    assert!(!story_json(plain).contains("stage"));
+}
+
+fn story_json(scene: Scene) -> String {
+    Story {
+        story_name: "one".to_string(),
+        variables: BTreeMap::new(),
+        scenes: vec![NamedScene {
+            scene_name: "intro".to_string(),
+            scene,
+        }],
+    }
+    .to_json()
+}
+
+#[test]
+fn missing_art_is_found_on_stage() {
+    let stage = |expression: &str| {
+        vec![
+            Character::new("Marlin", Slot::Left).with_expression(expression),
+            Character::new("Nemo", Slot::Right),
+        ]
+    };
+    let story = Story {
+        story_name: "art".to_string(),
+        variables: BTreeMap::new(),
+        scenes: vec![
+            NamedScene {
+                scene_name: "intro".to_string(),
+                scene: Scene::new("Marlin", "a", Next::Continue("b".to_string()))
+                    .with_background("reef")
+                    .with_stage(stage("worried")),
+            },
+            NamedScene {
+                scene_name: "b".to_string(),
+                scene: Scene::new("Marlin", "b", Next::End)
+                    .with_background("boat")
+                    .with_stage(stage("happy")),
+            },
+        ],
+    };
+    assert_eq!(
+        dialogue::missing_sprites(&story, |sprite| sprite != "Marlin_worried"),
+        vec![Problem::MissingSprite {
+            sprite: "Marlin_worried".to_string(),
+            scenes: vec!["intro".to_string()],
+        }]
+    );
+    assert_eq!(
+        dialogue::missing_backgrounds(&story, |background| background == "reef"),
+        vec![Problem::MissingBackground {
+            background: "boat".to_string(),
+            scenes: vec!["b".to_string()],
+        }]
+    );
+}
+
+#[test]
+fn nemo_script_is_valid() {
+    let story = Story::from_json(include_str!("../src/bin/nemo/script.json")).unwrap();
+    assert_eq!(dialogue::validate(&story, "intro"), vec![]);
+}
+
+// The same playthrough the game does, so any broken path or dead end fails here first
+#[test]
+fn nemo_script_paths_all_end() {
+    let story = Story::from_json(include_str!("../src/bin/nemo/script.json")).unwrap();
+    let found = dialogue::explore(&Rc::new(story), "intro", 1000, 100_000).unwrap();
+    let failures: Vec<String> = found.failures.iter().map(|f| f.to_string()).collect();
+    assert_eq!(failures, Vec::<String>::new());
+    assert_eq!(found.cut_off, 0);
+    assert!(found.endings.contains_key("ending1"));
+    assert_eq!(found.shortest, vec!["intro", "ending1"]);
+}
+
+#[test]
+fn explore_finds_endings_loops_and_failures() {
+    let story = graph(&[
+        ("intro", "", &["a", "b", "gone"]),
+        ("a", "", &["end"]),
+        ("b", "", &["p"]),
+        ("p", "", &["q"]),
+        ("q", "", &["p", "end"]),
+        ("end", "", &[]),
+    ]);
+    let found = dialogue::explore(&Rc::new(story), "intro", 100, 100_000).unwrap();
+    assert_eq!(found.paths(), 2);
+    assert_eq!(found.endings.get("end"), Some(&2));
+    assert_eq!(found.shortest, vec!["intro", "a", "end"]);
+    assert_eq!(found.longest, vec!["intro", "b", "p", "q", "end"]);
+    assert_eq!(found.loops, vec![vec!["p".to_string(), "q".to_string()]]);
+    assert_eq!(found.failures.len(), 1);
+    assert_eq!(found.failures[0].path, vec!["intro"]);
+    assert_eq!(
+        found.failures[0].trouble,
+        Trouble::Error(DialogueError::UnknownScene("gone".to_string()))
+    );
+    assert_eq!(found.cut_off, 0);
+
+    assert!(dialogue::explore(&Rc::new(graph(&[])), "intro", 100, 100_000).is_err());
+}
+
+#[test]
+fn explore_stops_when_the_budget_runs_out() {
+    // each step has two ways to the next, so there are 2^30 paths
+    let names: Vec<String> = (0..=30).map(|i| format!("s{}", i)).collect();
+    let scenes: Vec<(&str, &str, Vec<&str>)> = (0..=30)
+        .map(|i| match names.get(i + 1) {
+            Some(next) => (names[i].as_str(), "", vec![next.as_str(), next.as_str()]),
+            None => (names[i].as_str(), "", vec![]),
+        })
+        .collect();
+    let scenes: Vec<(&str, &str, &[&str])> = scenes
+        .iter()
+        .map(|(name, speaker, targets)| (*name, *speaker, targets.as_slice()))
+        .collect();
+    let mut story = graph(&scenes);
+    story.scenes[0].scene_name = "intro".to_string();
+
+    let found = dialogue::explore(&Rc::new(story.clone()), "intro", 100, 1000).unwrap();
+    assert!(found.out_of_budget);
+    assert!(found.paths() > 0);
+    assert_eq!(found.cut_off, 0);
+
+    // plenty for a short one
+    story.scenes.truncate(4);
+    story.scenes[3].scene.next = Next::End;
+    let found = dialogue::explore(&Rc::new(story), "intro", 100, 1000).unwrap();
+    assert!(!found.out_of_budget);
+    assert_eq!(found.paths(), 8);
+}
+
+#[test]
+fn explore_finds_where_nothing_can_be_picked() {
+    let mut story = graph(&[
+        ("intro", "", &["locked"]),
+        ("locked", "", &["end"]),
+        ("end", "", &[]),
+    ]);
+    story.scenes[1].scene.next = Next::Choice(vec![Response::new("open", "end")
+        .with_condition(Expr::parse("1 > 2").unwrap(), WhenFalse::Disable)]);
+    let found = dialogue::explore(&Rc::new(story), "intro", 100, 100_000).unwrap();
+    assert_eq!(found.paths(), 0);
+    assert_eq!(found.failures[0].path, vec!["intro", "locked"]);
+    assert_eq!(found.failures[0].trouble, Trouble::Stuck);
+    assert_eq!(
+        found.failures[0].to_string(),
+        "intro > locked: nothing can be picked"
+    );
+}
+
+const TREASURE: &str = r#"{
+    "story_name": "treasure",
+    "variables": { "treasure": 0, "met_dory": false, "name": "Nemo" },
+    "scenes": [
+        {
+            "scene_name": "intro",
+            "scene": {
+                "name": "{name}",
+                "message": "{name} has {treasure} {{coins}}, {nope} and {treasure + 1} after a dig",
+                "responses": [
+                    { "response": "dig", "goto": "intro", "effects": [{ "add": "treasure", "by": 1 }] },
+                    { "response": "meet dory", "goto": "dory", "condition": "!met_dory" },
+                    {
+                        "response": "buy the boat ({treasure}/2)",
+                        "goto": "boat",
+                        "condition": "treasure >= 2 && met_dory",
+                        "when_false": "disable"
+                    }
+                ]
+            }
+        },
+        {
+            "scene_name": "dory",
+            "scene": {
+                "name": "Dory",
+                "message": "hi {name}",
+                "effects": [{ "set": "met_dory", "to": true }],
+                "next": "intro"
+            }
+        },
+        { "scene_name": "boat", "scene": { "name": "", "message": "the end" } }
+    ]
+}"#;
+
+fn int(n: i64) -> Value {
+    Value::Int(n)
+}
+
+#[test]
+fn expressions_evaluate() {
+    let mut vars = BTreeMap::new();
+    vars.insert("a".to_string(), int(3));
+    vars.insert("b".to_string(), Value::Bool(false));
+    vars.insert("s".to_string(), Value::Str("fish".to_string()));
+    let eval = |source: &str| Expr::parse(source).unwrap().eval(&vars);
+    assert_eq!(eval("1 + 2 - -a"), Ok(int(6)));
+    assert_eq!(eval("a >= 3 && !b"), Ok(Value::Bool(true)));
+    assert_eq!(eval("(a == 2) || s == \"fish\""), Ok(Value::Bool(true)));
+    assert_eq!(eval("s + \"y\""), Ok(Value::Str("fishy".to_string())));
+    // the right side isn't looked at once the left side decides
+    assert_eq!(eval("b && missing"), Ok(Value::Bool(false)));
+    assert!(eval("missing").is_err());
+    assert!(eval("a + b").is_err());
+
+    assert!(Expr::parse("a = 3").is_err());
+    assert!(Expr::parse("(a").is_err());
+    assert!(Expr::parse("a a").is_err());
+    assert!(Expr::parse("\"open").is_err());
+
+    let mut kinds = BTreeMap::new();
+    kinds.insert("a".to_string(), Kind::Int);
+    kinds.insert("b".to_string(), Kind::Bool);
+    let check = |source: &str| Expr::parse(source).unwrap().check(&kinds);
+    assert_eq!(check("a < 2 || b"), Ok(Kind::Bool));
+    assert!(check("b && a").is_err());
+    assert!(check("b && nope").is_err());
+}
+
+#[test]
+fn explore_cuts_off_paths_that_keep_changing() {
+    // digging adds treasure every time, so it never comes back round to the same place
+    let story = Rc::new(Story::from_json(TREASURE).unwrap());
+    let found = dialogue::explore(&story, "intro", 8, 100_000).unwrap();
+    assert!(found.cut_off > 0);
+    assert!(found.loops.is_empty());
+    assert!(found.failures.is_empty());
+    assert_eq!(found.endings.keys().collect::<Vec<_>>(), vec!["boat"]);
+    assert_eq!(found.shortest.len(), 6);
+    assert_eq!(found.longest.len(), 8);
+}
+
+#[test]
+fn interpolation_fills_in_variables() {
+    let story = Rc::new(Story::from_json(TREASURE).unwrap());
+    let runner = Runner::new(&story, "intro").unwrap();
+    assert_eq!(
+        runner.message(),
+        "Nemo has 0 {coins}, {nope} and 1 after a dig"
+    );
+}
+
+#[test]
+fn conditions_hide_and_disable_choices() {
+    let story = Rc::new(Story::from_json(TREASURE).unwrap());
+    let mut runner = Runner::new(&story, "intro").unwrap();
+    let shown = |runner: &Runner| -> Vec<(usize, String, bool)> {
+        runner
+            .choices()
+            .into_iter()
+            .map(|c| (c.index, c.text, c.enabled))
+            .collect()
+    };
+    assert_eq!(
+        shown(&runner),
+        vec![
+            (0, "dig".to_string(), true),
+            (1, "meet dory".to_string(), true),
+            (2, "buy the boat (0/2)".to_string(), false),
+        ]
+    );
+    assert_eq!(runner.choose(2), Err(DialogueError::Disabled(2)));
+    assert_eq!(runner.current(), "intro");
+
+    runner.choose(0).unwrap();
+    runner.choose(0).unwrap();
+    assert_eq!(runner.variable("treasure"), Some(&int(2)));
+    runner.choose(1).unwrap();
+    assert_eq!(runner.current(), "dory");
+    assert_eq!(runner.variable("met_dory"), Some(&Value::Bool(true)));
+    runner.advance().unwrap();
+
+    // meeting dory is hidden now, so the boat is the second choice
+    assert_eq!(
+        shown(&runner),
+        vec![
+            (0, "dig".to_string(), true),
+            (2, "buy the boat (2/2)".to_string(), true),
+        ]
+    );
+    runner.choose(1).unwrap();
+    assert!(runner.is_end());
+
+    runner.restart("intro").unwrap();
+    assert_eq!(runner.variable("treasure"), Some(&int(0)));
+    assert_eq!(runner.variable("met_dory"), Some(&Value::Bool(false)));
+}
+
+#[test]
+fn effects_that_fail_change_nothing() {
+    let story = Rc::new(
+        Story::from_json(
+            r#"{
+        "story_name": "broken",
+        "variables": { "coins": 0, "mood": "fine" },
+        "scenes": [
+            {
+                "scene_name": "intro",
+                "scene": {
+                    "name": "",
+                    "message": "pick",
+                    "responses": [
+                        {
+                            "response": "sulk",
+                            "goto": "sulk",
+                            "effects": [{ "add": "coins", "by": 1 }],
+                            "music": "sad",
+                            "sfx": "sigh"
+                        },
+                        { "response": "spend", "goto": "intro", "effects": [{ "add": "mood", "by": 1 }] }
+                    ]
+                }
+            },
+            {
+                "scene_name": "sulk",
+                "scene": {
+                    "name": "",
+                    "message": "hmph",
+                    "effects": [{ "add": "coins", "by": 1 }, { "add": "mood", "by": -1 }],
+                    "sfx": "stomp"
+                }
+            }
+        ]
+    }"#,
+        )
+        .unwrap(),
+    );
+    let mut runner = Runner::new(&story, "intro").unwrap();
+    let before = runner.variables().clone();
+    // the response's own effect fails
+    assert!(matches!(runner.choose(1), Err(DialogueError::Effect(_))));
+    // the response's effect works but the scene's second one doesn't
+    assert!(matches!(runner.choose(0), Err(DialogueError::Effect(_))));
+    assert!(matches!(runner.goto("sulk"), Err(DialogueError::Effect(_))));
+    assert_eq!(runner.variables(), &before);
+    assert_eq!(runner.current(), "intro");
+    assert!(runner.history().is_empty());
+    assert_eq!(runner.music(), None);
+    assert!(runner.take_sounds().is_empty());
+}
+
+#[test]
+fn effects_apply_to_variables() {
+    let mut vars = BTreeMap::new();
+    Effect::Add {
+        add: "n".to_string(),
+        by: -2,
+    }
+    .apply(&mut vars)
+    .unwrap();
+    assert_eq!(vars["n"], int(-2));
+    Effect::Set {
+        set: "n".to_string(),
+        to: Value::Str("x".to_string()),
+    }
+    .apply(&mut vars)
+    .unwrap();
+    assert!(Effect::Add {
+        add: "n".to_string(),
+        by: 1,
+    }
+    .apply(&mut vars)
+    .is_err());
+}
+
+#[test]
+fn variables_round_trip() {
+    let story = Story::from_json(TREASURE).unwrap();
+    assert_eq!(Story::from_json(&story.to_json()).unwrap(), story);
+    assert!(story
+        .to_json()
+        .contains(r#""condition": "treasure >= 2 && met_dory""#));
+    // a bad condition is an error when loading
+    assert!(Story::from_json(&TREASURE.replace("!met_dory", "met_dory =")).is_err());
+}
+
+#[test]
+fn validate_checks_variables() {
+    assert_eq!(
+        dialogue::validate(&Story::from_json(TREASURE).unwrap(), "intro"),
+        vec![Problem::BadExpression {
+            scene: "intro".to_string(),
+            error: "no variable named \"nope\"".to_string(),
+        }]
+    );
+
+    let broken = TREASURE
+        .replace(r#""condition": "!met_dory""#, r#""condition": "treasure""#)
+        .replace(r#""add": "treasure""#, r#""add": "met_dory""#)
+        .replace(r#""to": true"#, r#""to": 1"#);
+    let errors: Vec<String> = dialogue::validate(&Story::from_json(&broken).unwrap(), "intro")
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    assert_eq!(errors.len(), 4, "{:?}", errors);
+    assert!(errors[1].contains("\"met_dory\" is true or false, not a number"));
+    assert!(errors[2].contains("condition \"treasure\" is a number"));
+}
+
+#[test]
+fn load_reports_where_the_json_is_broken() {
+    let path = std::env::temp_dir().join(format!("dialogue-load-{}.json", std::process::id()));
+    fs::write(
+        &path,
+        "{\n    \"story_name\": \"x\",\n    \"scenes\": [ oops ]\n}",
+    )
+    .unwrap();
+    let err = Story::load(&path).unwrap_err();
+    assert!(err.to_string().starts_with("line 3 column 17: "), "{}", err);
+    assert!(matches!(
+        err,
+        LoadError::Parse {
+            line: 3,
+            column: 17,
+            ..
+        }
+    ));
+
+    fs::write(&path, STORY).unwrap();
+    assert_eq!(Story::load(&path).unwrap(), *story());
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(Story::load(&path), Err(LoadError::Io(_))));
+}
+
+#[test]
+fn reload_keeps_the_scene_and_variables() {
+    let story = Rc::new(Story::from_json(TREASURE).unwrap());
+    let mut runner = Runner::new(&story, "intro").unwrap();
+    runner.choose(0).unwrap();
+
+    // same scenes, new text and a new variable
+    let edited = TREASURE
+        .replace("has {treasure}", "now has {treasure}")
+        .replace(
+            r#""name": "Nemo" }"#,
+            r#""name": "Nemo", "found_boat": false }"#,
+        );
+    let edited = Rc::new(Story::from_json(&edited).unwrap());
+    assert_eq!(runner.reload(&edited, "intro"), Ok(true));
+    assert_eq!(runner.current(), "intro");
+    assert!(runner.message().starts_with("Nemo now has 1"));
+    assert_eq!(runner.variable("found_boat"), Some(&Value::Bool(false)));
+
+    // the scene is gone, so back to the fallback
+    runner.choose(1).unwrap();
+    let cut = Rc::new(graph(&[("intro", "", &[])]));
+    assert_eq!(runner.reload(&cut, "intro"), Ok(false));
+    assert_eq!(runner.current(), "intro");
+    assert_eq!(runner.variable("treasure"), Some(&int(1)));
+
+    // nowhere to go, so nothing changes
+    let empty = Rc::new(graph(&[("elsewhere", "", &[])]));
+    assert_eq!(
+        runner.reload(&empty, "intro"),
+        Err(DialogueError::UnknownScene("intro".to_string()))
+    );
+    assert!(Rc::ptr_eq(runner.story(), &cut));
+}
+
+#[test]
+fn restore_puts_the_runner_back_without_effects() {
+    let story = Rc::new(Story::from_json(TREASURE).unwrap());
+    let mut runner = Runner::new(&story, "intro").unwrap();
+    let mut saved = BTreeMap::new();
+    saved.insert("treasure".to_string(), int(7));
+
+    runner.restore("dory", saved.clone()).unwrap();
+    assert_eq!(runner.current(), "dory");
+    assert_eq!(runner.variable("treasure"), Some(&int(7)));
+    // dory's effect didn't happen, and what the save didn't have starts fresh
+    assert_eq!(runner.variable("met_dory"), Some(&Value::Bool(false)));
+    assert_eq!(
+        runner.variable("name"),
+        Some(&Value::Str("Nemo".to_string()))
+    );
+
+    assert_eq!(
+        runner.restore("gone", saved),
+        Err(DialogueError::UnknownScene("gone".to_string()))
+    );
+    assert_eq!(runner.current(), "dory");
+}
+
+#[test]
+fn history_remembers_what_was_shown_and_picked() {
+    let story = Rc::new(Story::from_json(TREASURE).unwrap());
+    let mut runner = Runner::new(&story, "intro").unwrap();
+    runner.choose(0).unwrap();
+    runner.choose(1).unwrap();
+    runner.advance().unwrap();
+
+    let seen: Vec<(&str, &str, Option<&str>)> = runner
+        .history()
+        .iter()
+        .map(|e| (e.scene.as_str(), e.speaker.as_str(), e.choice.as_deref()))
+        .collect();
+    assert_eq!(
+        seen,
+        vec![
+            ("intro", "{name}", Some("dig")),
+            ("intro", "{name}", Some("meet dory")),
+            ("dory", "Dory", None),
+        ]
+    );
+    // messages are kept as the player saw them
+    assert!(runner.history()[0].message.starts_with("Nemo has 0"));
+    assert!(runner.history()[1].message.starts_with("Nemo has 1"));
+
+    runner.restart("intro").unwrap();
+    assert!(runner.history().is_empty());
+}
+
+#[test]
+fn rollback_goes_back_to_a_choice() {
+    let story = Rc::new(Story::from_json(TREASURE).unwrap());
+    let mut runner = Runner::new(&story, "intro").unwrap();
+    runner.choose(0).unwrap();
+    runner.choose(1).unwrap();
+    runner.advance().unwrap();
+    assert_eq!(runner.variable("met_dory"), Some(&Value::Bool(true)));
+
+    // back to just before meeting dory, with the treasure already dug up
+    runner.rollback(1).unwrap();
+    assert_eq!(runner.current(), "intro");
+    assert_eq!(runner.variable("treasure"), Some(&int(1)));
+    assert_eq!(runner.variable("met_dory"), Some(&Value::Bool(false)));
+    assert_eq!(runner.history().len(), 1);
+
+    // a scene's own effects are part of how it was entered
+    runner.choose(1).unwrap();
+    runner.advance().unwrap();
+    runner.rollback(2).unwrap();
+    assert_eq!(runner.current(), "dory");
+    assert_eq!(runner.variable("met_dory"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn music_carries_on_and_sounds_queue_up() {
+    let story = Story {
+        story_name: "sounds".to_string(),
+        variables: BTreeMap::new(),
+        scenes: vec![
+            NamedScene {
+                scene_name: "intro".to_string(),
+                scene: Scene::new(
+                    "Nemo",
+                    "hi",
+                    Next::Choice(vec![
+                        Response::new("swim", "reef").with_sfx("splash.mp3"),
+                        Response::new("hush", "reef").with_music(""),
+                    ]),
+                )
+                .with_music("calm.mp3")
+                .with_sfx("bubbles.mp3"),
+            },
+            NamedScene {
+                scene_name: "reef".to_string(),
+                scene: Scene::new("Nemo", "the reef", Next::Continue("shark".to_string())),
+            },
+            NamedScene {
+                scene_name: "shark".to_string(),
+                scene: Scene::new("Bruce", "hello", Next::End)
+                    .with_music("scary.mp3")
+                    .with_sfx("growl.mp3"),
+            },
+        ],
+    };
+    assert_eq!(Story::from_json(&story.to_json()).unwrap(), story);
+    let story = Rc::new(story);
+
+    let mut runner = Runner::new(&story, "intro").unwrap();
+    assert_eq!(runner.music(), Some("calm.mp3"));
+    assert_eq!(runner.take_sounds(), vec!["bubbles.mp3".to_string()]);
+    assert!(runner.take_sounds().is_empty());
+
+    // a scene without music keeps what was playing
+    runner.choose(0).unwrap();
+    assert_eq!(runner.music(), Some("calm.mp3"));
+    runner.advance().unwrap();
+    assert_eq!(runner.music(), Some("scary.mp3"));
+    assert_eq!(
+        runner.take_sounds(),
+        vec!["splash.mp3".to_string(), "growl.mp3".to_string()]
+    );
+
+    // going back puts the music back too
+    runner.rollback(0).unwrap();
+    assert_eq!(runner.music(), Some("calm.mp3"));
+    runner.choose(1).unwrap();
+    assert_eq!(runner.music(), Some(""));
+
+    runner.restart("intro").unwrap();
+    assert_eq!(runner.take_sounds(), vec!["bubbles.mp3".to_string()]);
+
+    assert_eq!(
+        dialogue::missing_sounds(&story, |sound| sound != "growl.mp3"
+            && sound != "splash.mp3"),
+        vec![
+            Problem::MissingSound {
+                sound: "splash.mp3".to_string(),
+                scenes: vec!["intro".to_string()],
+            },
+            Problem::MissingSound {
+                sound: "growl.mp3".to_string(),
+                scenes: vec!["shark".to_string()],
+            },
+        ]
+    );
+}
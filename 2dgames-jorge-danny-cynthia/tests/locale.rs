@@ -0,0 +1,175 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::rc::Rc;
+
+use engine2d::dialogue::{self, NamedScene, Next, Problem, Response, Runner, Scene, Story};
+use engine2d::locale::{self, Coverage, StringTable, Strings};
+
+fn table(strings: &[(&str, &str)]) -> StringTable {
+    strings
+        .iter()
+        .map(|(id, text)| (id.to_string(), text.to_string()))
+        .collect()
+}
+
+#[test]
+fn substitute_fills_in_placeholders() {
+    let args: &[(&str, &dyn std::fmt::Display)] = &[("score", &12), ("name", &"nemo")];
+    assert_eq!(
+        locale::substitute("{name} got {score}, best {best}", args),
+        "nemo got 12, best {best}"
+    );
+    assert_eq!(locale::substitute("{{score}} }} {", args), "{score} } {");
+    assert_eq!(locale::substitute("", args), "");
+}
+
+#[test]
+fn strings_fall_back_and_show_gaps() {
+    let strings =
+        Strings::new("fr", table(&[("start", "appuyez sur entrée")])).with_fallback(table(&[
+            ("start", "press enter"),
+            ("score", "score: {score}"),
+        ]));
+    assert_eq!(strings.locale(), "fr");
+    assert_eq!(strings.get("start"), Some("appuyez sur entrée"));
+    assert_eq!(strings.format("score", &[("score", &3)]), "score: 3");
+    assert_eq!(strings.get("nope"), None);
+    assert_eq!(strings.text("nope"), "nope");
+}
+
+#[test]
+fn strings_load_from_a_dir() {
+    let dir = std::env::temp_dir().join(format!("locale-load-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("en.json"), r#"{ "a": "one", "b": "two" }"#).unwrap();
+    fs::write(dir.join("de.json"), r#"{ "a": "eins" }"#).unwrap();
+    fs::write(dir.join("bad.json"), r#"{ "a": 1 }"#).unwrap();
+    assert_eq!(locale::locales(&dir), vec!["bad", "de", "en"]);
+
+    let strings = Strings::load(&dir, "de", "en").unwrap();
+    assert_eq!(strings.text("a"), "eins");
+    assert_eq!(strings.text("b"), "two");
+    assert!(matches!(
+        Strings::load(&dir, "bad", "en"),
+        Err(locale::LocaleError::Parse(_))
+    ));
+    assert!(matches!(
+        Strings::load(&dir, "fr", "en"),
+        Err(locale::LocaleError::Io(_))
+    ));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn check_finds_missing_and_unused() {
+    let used: BTreeSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(
+        locale::check(&table(&[("a", "1"), ("c", "3")]), &used),
+        Coverage {
+            missing: vec!["b".to_string()],
+            unused: vec!["c".to_string()],
+        }
+    );
+}
+
+#[test]
+fn stories_use_string_ids() {
+    let story = Story {
+        story_name: "ids".to_string(),
+        variables: vec![("fish".to_string(), engine2d::dialogue::Value::Int(2))]
+            .into_iter()
+            .collect(),
+        scenes: vec![
+            NamedScene {
+                scene_name: "intro".to_string(),
+                scene: Scene::new(
+                    "Nemo",
+                    "hi",
+                    Next::Choice(vec![
+                        Response::new("", "end").with_response_id("intro.bye"),
+                        Response::new("stay", "intro").with_response_id("intro.stay"),
+                    ]),
+                )
+                .with_message_id("intro.hi"),
+            },
+            NamedScene {
+                scene_name: "end".to_string(),
+                scene: Scene::new("", "the end", Next::End),
+            },
+        ],
+    };
+    assert_eq!(
+        story.string_ids().into_iter().collect::<Vec<_>>(),
+        vec!["intro.bye", "intro.hi", "intro.stay"]
+    );
+    // a response with only an ID is still a choice, not a carry on
+    let story = Story::from_json(&story.to_json()).unwrap();
+    assert_eq!(story.scene("intro").unwrap().choices().len(), 2);
+
+    let mut runner = Runner::new(&Rc::new(story), "intro").unwrap();
+    // no strings, so the story's own text
+    assert_eq!(runner.message(), "hi");
+    runner.set_strings(Some(Rc::new(Strings::new(
+        "de",
+        table(&[
+            ("intro.hi", "hallo, {fish} fische"),
+            ("intro.bye", "tschüss"),
+        ]),
+    ))));
+    assert_eq!(runner.message(), "hallo, 2 fische");
+    let choices: Vec<String> = runner.choices().into_iter().map(|c| c.text).collect();
+    assert_eq!(choices, vec!["tschüss", "stay"]);
+}
+
+#[test]
+fn message_ids_with_nothing_behind_them_are_reported() {
+    let story = Story {
+        story_name: "ids".to_string(),
+        variables: Default::default(),
+        scenes: vec![
+            NamedScene {
+                scene_name: "intro".to_string(),
+                scene: Scene::new("Nemo", "", Next::Continue("middle".to_string()))
+                    .with_message_id("intro.hi"),
+            },
+            NamedScene {
+                scene_name: "middle".to_string(),
+                scene: Scene::new("", "", Next::Continue("end".to_string()))
+                    .with_message_id("middle.gone"),
+            },
+            NamedScene {
+                scene_name: "end".to_string(),
+                scene: Scene::new("", "the end", Next::End).with_message_id("end.gone"),
+            },
+        ],
+    };
+    let strings = Strings::new("en", table(&[("intro.hi", "hi")]));
+    assert_eq!(
+        dialogue::empty_messages(&story, |id| strings.get(id).is_some()),
+        vec![Problem::EmptyMessage {
+            scene: "middle".to_string(),
+            message_id: "middle.gone".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn requested_ids_are_found_in_source() {
+    let source = r#"
+        let title = state.strings.text("nemo.title");
+        let score = rsrc
+            .strings
+            .format("flappy.score", &[("score", &score)]);
+        if let Some(s) = self.strings.get( "typo.here" ) {}
+        let other = data.get("start");
+        let no = my_strings.text("not.this");
+        let tip = strings.text_width("nor.this");
+        let id = strings.text(ID);
+    "#;
+    assert_eq!(
+        locale::requested_ids(source)
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["flappy.score", "nemo.title", "typo.here"]
+    );
+}
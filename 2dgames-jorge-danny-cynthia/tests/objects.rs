@@ -0,0 +1,156 @@
+use engine2d::objects::{IRect, MovingRect, Rect, Vec2};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-5
+}
+
+#[test]
+fn vec2_operators() {
+    let a = Vec2::new(1.0, 2.0);
+    let b = Vec2::new(3.0, -4.0);
+    assert_eq!(a + b, Vec2::new(4.0, -2.0));
+    assert_eq!(a - b, Vec2::new(-2.0, 6.0));
+    assert_eq!(a * 2.0, Vec2::new(2.0, 4.0));
+    assert_eq!(2.0 * a, Vec2::new(2.0, 4.0));
+    assert_eq!(b / 2.0, Vec2::new(1.5, -2.0));
+    assert_eq!(-a, Vec2::new(-1.0, -2.0));
+
+    let mut c = a;
+    c += b;
+    assert_eq!(c, Vec2::new(4.0, -2.0));
+    c -= b;
+    assert_eq!(c, a);
+    c *= 3.0;
+    assert_eq!(c, Vec2::new(3.0, 6.0));
+    c /= 3.0;
+    assert_eq!(c, a);
+}
+
+#[test]
+fn vec2_length_dot_normalize() {
+    let v = Vec2::new(3.0, 4.0);
+    assert_eq!(v.length_squared(), 25.0);
+    assert_eq!(v.length(), 5.0);
+    assert_eq!(v.dot(Vec2::new(-4.0, 3.0)), 0.0);
+    assert_eq!(v.dot(Vec2::new(1.0, 1.0)), 7.0);
+    assert_eq!(Vec2::ZERO.distance(v), 5.0);
+
+    let n = v.normalize();
+    assert!(close(n.length(), 1.0));
+    assert!(close(n.x, 0.6) && close(n.y, 0.8));
+    // no NaNs from the zero vector
+    assert_eq!(Vec2::ZERO.normalize(), Vec2::ZERO);
+}
+
+#[test]
+fn vec2_lerp() {
+    let a = Vec2::new(0.0, 10.0);
+    let b = Vec2::new(10.0, 20.0);
+    assert_eq!(a.lerp(b, 0.0), a);
+    assert_eq!(a.lerp(b, 1.0), b);
+    assert_eq!(a.lerp(b, 0.5), Vec2::new(5.0, 15.0));
+    assert_eq!(a.lerp(b, 2.0), Vec2::new(20.0, 30.0));
+}
+
+#[test]
+fn defaults_are_zero() {
+    assert_eq!(Vec2::default(), Vec2::ZERO);
+    assert_eq!(Rect::default(), Rect::new(0.0, 0.0, 0.0, 0.0));
+    assert_eq!(IRect::default(), IRect::new(0, 0, 0, 0));
+    assert!(Rect::default().is_empty());
+}
+
+#[test]
+fn rect_edges_and_center() {
+    let r = Rect::new(10.0, 20.0, 30.0, 40.0);
+    assert_eq!(r.right(), 40.0);
+    assert_eq!(r.bottom(), 60.0);
+    assert_eq!(r.center(), Vec2::new(25.0, 40.0));
+    assert_eq!(r.pos(), Vec2::new(10.0, 20.0));
+    assert_eq!(r.size(), Vec2::new(30.0, 40.0));
+    assert_eq!(Rect::from_pos_size(r.pos(), r.size()), r);
+}
+
+#[test]
+fn rect_contains_point_is_half_open() {
+    let r = Rect::new(0.0, 0.0, 10.0, 10.0);
+    assert!(r.contains_point(Vec2::new(0.0, 0.0)));
+    assert!(r.contains_point(Vec2::new(9.99, 5.0)));
+    assert!(!r.contains_point(Vec2::new(10.0, 5.0)));
+    assert!(!r.contains_point(Vec2::new(5.0, 10.0)));
+    assert!(!r.contains_point(Vec2::new(-0.01, 5.0)));
+}
+
+#[test]
+fn rect_intersection() {
+    let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+    let b = Rect::new(5.0, 5.0, 10.0, 10.0);
+    assert!(a.intersects(&b) && b.intersects(&a));
+    assert_eq!(a.intersection(&b), Some(Rect::new(5.0, 5.0, 5.0, 5.0)));
+    assert_eq!(a.intersection(&b), b.intersection(&a));
+
+    // sharing an edge isn't overlapping
+    let c = Rect::new(10.0, 0.0, 5.0, 5.0);
+    assert!(!a.intersects(&c));
+    assert_eq!(a.intersection(&c), None);
+
+    // one inside the other
+    let d = Rect::new(2.0, 3.0, 1.0, 1.0);
+    assert_eq!(a.intersection(&d), Some(d));
+}
+
+#[test]
+fn rect_union_expand_translate() {
+    let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+    let b = Rect::new(20.0, -5.0, 5.0, 5.0);
+    assert_eq!(a.union(&b), Rect::new(0.0, -5.0, 25.0, 15.0));
+    assert_eq!(a.union(&b), b.union(&a));
+
+    assert_eq!(a.expand(2.0), Rect::new(-2.0, -2.0, 14.0, 14.0));
+    assert_eq!(a.expand(2.0).center(), a.center());
+    assert_eq!(a.expand(-5.0), Rect::new(5.0, 5.0, 0.0, 0.0));
+    assert!(a.expand(-5.0).is_empty());
+
+    assert_eq!(
+        a.translate(Vec2::new(3.0, -1.0)),
+        Rect::new(3.0, -1.0, 10.0, 10.0)
+    );
+}
+
+#[test]
+fn rect_round_out_covers_rect() {
+    let r = Rect::new(0.5, -0.5, 2.0, 1.2);
+    assert_eq!(r.round_out(), IRect::new(0, -1, 3, 2));
+    assert_eq!(
+        Rect::from(IRect::new(1, 2, 3, 4)),
+        Rect::new(1.0, 2.0, 3.0, 4.0)
+    );
+    assert_eq!(
+        Rect::from(IRect::new(1, 2, 3, 4)).round_out(),
+        IRect::new(1, 2, 3, 4)
+    );
+}
+
+#[test]
+fn irect_operations() {
+    let a = IRect::new(0, 0, 10, 10);
+    let b = IRect::new(5, -5, 10, 10);
+    assert_eq!(a.right(), 10);
+    assert_eq!(a.bottom(), 10);
+    assert!(a.contains_point(0, 0));
+    assert!(!a.contains_point(10, 0));
+    assert!(a.intersects(&b));
+    assert_eq!(a.intersection(&b), Some(IRect::new(5, 0, 5, 5)));
+    assert_eq!(a.intersection(&IRect::new(10, 10, 1, 1)), None);
+    assert_eq!(a.union(&b), IRect::new(0, -5, 15, 15));
+    assert_eq!(a.expand(1), IRect::new(-1, -1, 12, 12));
+    assert_eq!(a.translate(2, 3), IRect::new(2, 3, 10, 10));
+    assert!(IRect::new(0, 0, 0, 5).is_empty());
+}
+
+#[test]
+fn moving_rect_translate() {
+    let mut m = MovingRect::new(1.0, 2.0, 3.0, 4.0, Vec2::new(0.5, -1.0));
+    m.translate(-m.vel);
+    assert_eq!(m.as_rect(), Rect::new(0.5, 3.0, 3.0, 4.0));
+}
@@ -0,0 +1,94 @@
+use std::rc::Rc;
+
+use engine2d::objects::{Rect, DEPTH};
+use engine2d::panel::{DrawPanelExt, Fill, Insets, Panel};
+use engine2d::screen::Screen;
+use engine2d::texture::Texture;
+use image::{Rgba, RgbaImage};
+
+// Frame pixels are labelled with letters so the results read like pictures:
+// a source pixel's red channel is its letter, and untouched screen pixels are '.'
+fn texture(rows: &[&str]) -> Rc<Texture> {
+    let img = RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+        Rgba([rows[y as usize].as_bytes()[x as usize], 0, 0, 255])
+    });
+    Rc::new(Texture::new(img))
+}
+
+fn draw(panel: &Panel, r: Rect, w: usize, h: usize) -> Vec<String> {
+    let mut fb: Vec<u8> = [b'.', 0, 0, 255]
+        .iter()
+        .copied()
+        .cycle()
+        .take(w * h * DEPTH)
+        .collect();
+    Screen::wrap(&mut fb, w, h).draw_panel(panel, r);
+    fb.chunks_exact(w * DEPTH)
+        .map(|row| row.chunks_exact(DEPTH).map(|px| px[0] as char).collect())
+        .collect()
+}
+
+fn nine() -> Rc<Texture> {
+    texture(&["abbc", "deef", "dxyf", "ghhi"])
+}
+
+#[test]
+fn stretches_edges_and_center() {
+    let panel = Panel::new(&nine(), Rect::new(0.0, 0.0, 4.0, 4.0), Insets::uniform(1.0));
+    assert_eq!(
+        draw(&panel, Rect::new(1.0, 1.0, 6.0, 6.0), 8, 8),
+        [
+            "........", ".abbbbc.", ".deeeef.", ".deeeef.", ".dxxyyf.", ".dxxyyf.", ".ghhhhi.",
+            "........",
+        ]
+    );
+}
+
+#[test]
+fn tiles_edges_and_center() {
+    let panel = Panel::new(&nine(), Rect::new(0.0, 0.0, 4.0, 4.0), Insets::uniform(1.0))
+        .with_fill(Fill::Tile, Fill::Tile);
+    assert_eq!(
+        draw(&panel, Rect::new(0.0, 0.0, 7.0, 5.0), 7, 5),
+        ["abbbbbc", "deeeeef", "dxyxyxf", "deeeeef", "ghhhhhi"]
+    );
+}
+
+#[test]
+fn frame_can_be_part_of_a_texture() {
+    let tex = texture(&["zzzzz", "zabcz", "zdefz", "zghiz"]);
+    let panel = Panel::new(&tex, Rect::new(1.0, 1.0, 3.0, 3.0), Insets::uniform(1.0));
+    assert_eq!(
+        draw(&panel, Rect::new(0.0, 0.0, 5.0, 3.0), 5, 3),
+        ["abbbc", "deeef", "ghhhi"]
+    );
+    assert_eq!(
+        panel.content_rect(Rect::new(0.0, 0.0, 5.0, 3.0)),
+        Rect::new(1.0, 1.0, 3.0, 1.0)
+    );
+}
+
+#[test]
+fn small_boxes_cut_off_the_corners() {
+    let tex = texture(&["aabcc", "aabcc", "ddeff", "gghii", "gghii"]);
+    let panel = Panel::new(&tex, Rect::new(0.0, 0.0, 5.0, 5.0), Insets::uniform(2.0));
+    assert_eq!(panel.min_size().x, 4.0);
+    // each corner gets its share of what there is, keeping its outer side
+    assert_eq!(
+        draw(&panel, Rect::new(0.0, 0.0, 3.0, 2.0), 3, 2),
+        ["acc", "gii"]
+    );
+    assert!(draw(&panel, Rect::new(0.0, 0.0, 0.0, 0.0), 2, 2)
+        .iter()
+        .all(|row| row == ".."));
+}
+
+#[test]
+#[should_panic]
+fn insets_have_to_fit() {
+    Panel::new(
+        &nine(),
+        Rect::new(0.0, 0.0, 4.0, 4.0),
+        Insets::new(3.0, 0.0, 2.0, 0.0),
+    );
+}
@@ -0,0 +1,269 @@
+// Each test draws something and compares it to a golden image (see common/mod.rs)
+mod common;
+
+use engine2d::objects::{Color, Rect, Vec2, DEPTH};
+use engine2d::screen::{Gradient, LineCap, Screen};
+
+const W: usize = 32;
+const H: usize = 24;
+const BG: Color = [0, 0, 0, 255];
+const FG: Color = [255, 255, 255, 255];
+const RED: Color = [255, 0, 0, 255];
+
+fn golden<F: FnOnce(&mut Screen)>(name: &str, draw: F) {
+    let mut fb: Vec<u8> = BG.iter().copied().cycle().take(W * H * DEPTH).collect();
+    draw(&mut Screen::wrap(&mut fb, W, H));
+    common::golden_image(name, W, H, fb);
+}
+
+#[test]
+fn circles() {
+    golden("circles", |s| {
+        s.filled_circle((7, 7), 6, FG);
+        s.circle_lines((22, 9), 8, FG);
+        s.filled_circle((22, 9), 3, RED);
+        s.circle_lines((5, 19), 2, RED);
+        s.filled_circle((14, 20), 1, FG);
+    });
+}
+
+#[test]
+fn ellipses() {
+    golden("ellipses", |s| {
+        s.ellipse(Rect::new(1.0, 1.0, 18.0, 10.0), FG);
+        s.ellipse_lines(Rect::new(20.0, 2.0, 10.0, 20.0), RED);
+        s.ellipse_lines(Rect::new(2.0, 13.0, 14.0, 9.0), FG);
+    });
+}
+
+#[test]
+fn rounded_rects() {
+    golden("rounded_rects", |s| {
+        s.rounded_rect(Rect::new(1.0, 1.0, 14.0, 10.0), 4.0, FG);
+        s.rounded_rect_lines(Rect::new(17.0, 1.0, 14.0, 10.0), 4.0, RED);
+        // radius bigger than the rect can take turns it into a stadium
+        s.rounded_rect(Rect::new(1.0, 13.0, 20.0, 8.0), 100.0, RED);
+        s.rounded_rect_lines(Rect::new(23.0, 13.0, 8.0, 10.0), 0.0, FG);
+    });
+}
+
+#[test]
+fn polygons() {
+    golden("polygons", |s| {
+        // convex
+        s.polygon(
+            &[
+                Vec2::new(2.0, 2.0),
+                Vec2::new(12.0, 4.0),
+                Vec2::new(8.0, 12.0),
+            ],
+            FG,
+        );
+        // concave: an arrow head pointing right
+        s.polygon(
+            &[
+                Vec2::new(16.0, 2.0),
+                Vec2::new(30.0, 8.0),
+                Vec2::new(16.0, 14.0),
+                Vec2::new(21.0, 8.0),
+            ],
+            RED,
+        );
+        // self-intersecting star, the middle stays empty
+        let star: Vec<Vec2> = (0..5)
+            .map(|i| {
+                let a = i as f32 * 4.0 * std::f32::consts::PI / 5.0 - std::f32::consts::FRAC_PI_2;
+                Vec2::new(8.0 + 7.0 * a.cos(), 17.0 + 7.0 * a.sin())
+            })
+            .collect();
+        s.polygon(&star, FG);
+        s.polygon_lines(
+            &[
+                Vec2::new(18.0, 16.0),
+                Vec2::new(30.0, 16.0),
+                Vec2::new(24.0, 23.0),
+            ],
+            FG,
+        );
+    });
+}
+
+#[test]
+fn thick_lines() {
+    golden("thick_lines", |s| {
+        s.thick_line(
+            Vec2::new(4.0, 4.0),
+            Vec2::new(28.0, 4.0),
+            4.0,
+            LineCap::Butt,
+            FG,
+        );
+        s.thick_line(
+            Vec2::new(4.0, 11.0),
+            Vec2::new(28.0, 11.0),
+            4.0,
+            LineCap::Square,
+            RED,
+        );
+        s.thick_line(
+            Vec2::new(4.0, 18.0),
+            Vec2::new(28.0, 18.0),
+            5.0,
+            LineCap::Round,
+            FG,
+        );
+        s.thick_line(
+            Vec2::new(2.0, 22.0),
+            Vec2::new(12.0, 13.0),
+            2.0,
+            LineCap::Butt,
+            RED,
+        );
+        // no length, so only the caps show
+        s.thick_line(
+            Vec2::new(24.0, 22.0),
+            Vec2::new(24.0, 22.0),
+            2.0,
+            LineCap::Square,
+            RED,
+        );
+    });
+}
+
+// (x, y) of every pixel draw paints, in rows
+fn painted<F: FnOnce(&mut Screen)>(draw: F) -> Vec<(usize, usize)> {
+    let mut fb = vec![0; W * H * DEPTH];
+    draw(&mut Screen::wrap(&mut fb, W, H));
+    (0..W * H)
+        .filter(|i| fb[i * DEPTH..(i + 1) * DEPTH] != [0; DEPTH])
+        .map(|i| (i % W, i / W))
+        .collect()
+}
+
+fn cells(x: std::ops::Range<usize>, y: std::ops::Range<usize>) -> Vec<(usize, usize)> {
+    y.flat_map(|y| x.clone().map(move |x| (x, y))).collect()
+}
+
+#[test]
+fn shapes_cover_what_they_should() {
+    let disc: Vec<(usize, usize)> = cells(0..W, 0..H)
+        .into_iter()
+        .filter(|&(x, y)| (x as i32 - 7).pow(2) + (y as i32 - 7).pow(2) < 3 * 3)
+        .collect();
+    assert_eq!(painted(|s| s.filled_circle((7, 7), 3, FG)), disc);
+
+    let (from, to) = (Vec2::new(4.0, 11.0), Vec2::new(28.0, 11.0));
+    assert_eq!(
+        painted(|s| s.thick_line(from, to, 4.0, LineCap::Butt, FG)),
+        cells(4..28, 9..13)
+    );
+    // square caps stick out half the width at each end
+    assert_eq!(
+        painted(|s| s.thick_line(from, to, 4.0, LineCap::Square, FG)),
+        cells(2..30, 9..13)
+    );
+
+    let rounded = painted(|s| s.rounded_rect(Rect::new(1.0, 1.0, 14.0, 10.0), 4.0, FG));
+    assert!(rounded
+        .iter()
+        .all(|&(x, y)| (1..15).contains(&x) && (1..11).contains(&y)));
+    for corner in [(1, 1), (14, 1), (1, 10), (14, 10)].iter() {
+        assert!(!rounded.contains(corner));
+    }
+    for edge in [(8, 1), (1, 6), (14, 6), (8, 10)].iter() {
+        assert!(rounded.contains(edge));
+    }
+}
+
+#[test]
+fn zero_length_lines_are_just_their_caps() {
+    let p = Vec2::new(10.0, 10.0);
+    let line = |cap| painted(|s| s.thick_line(p, p, 4.0, cap, FG));
+    assert_eq!(line(LineCap::Square), cells(8..12, 8..12));
+    assert_eq!(line(LineCap::Butt), vec![]);
+    assert!(!line(LineCap::Round).is_empty());
+}
+
+#[test]
+fn gradients() {
+    golden("gradients", |s| {
+        s.gradient_rect(
+            Rect::new(0.0, 0.0, 32.0, 12.0),
+            [255, 0, 0, 255],
+            [0, 0, 255, 255],
+            Gradient::Horizontal,
+        );
+        s.gradient_rect(
+            Rect::new(4.0, 12.0, 24.0, 12.0),
+            [0, 0, 0, 255],
+            [0, 255, 0, 255],
+            Gradient::Vertical,
+        );
+    });
+}
+
+#[test]
+fn clipped_shapes() {
+    golden("clipped_shapes", |s| {
+        s.push_viewport(Rect::new(4.0, 4.0, 24.0, 16.0));
+        s.filled_circle((0, 0), 8, FG);
+        s.ellipse_lines(Rect::new(10.0, -6.0, 20.0, 16.0), RED);
+        s.rounded_rect(Rect::new(-4.0, 10.0, 20.0, 20.0), 6.0, RED);
+        s.thick_line(
+            Vec2::new(12.0, 8.0),
+            Vec2::new(40.0, 30.0),
+            3.0,
+            LineCap::Round,
+            FG,
+        );
+        s.pop_viewport();
+    });
+}
+
+// None of these are anywhere near fitting on screen; they just can't panic
+#[test]
+fn shapes_off_screen() {
+    let mut fb = vec![0; W * H * DEPTH];
+    let mut s = Screen::wrap(&mut fb, W, H);
+    let far = Vec2::new(-10_000.0, 50_000.0);
+    for &(x, y) in &[(-40, -40), (40, 30), (-3, 10), (31, -5), (i32::MIN / 2, 0)] {
+        s.filled_circle((x, y), 10, FG);
+        s.circle_lines((x, y), 10, FG);
+    }
+    s.filled_circle((16, 12), 100_000, FG);
+    s.ellipse(Rect::new(-50.0, -50.0, 200.0, 10.0), FG);
+    s.ellipse_lines(Rect::new(-1e9, -1e9, 2e9, 2e9), FG);
+    s.rounded_rect(Rect::new(30.0, 20.0, 50.0, 50.0), 10.0, FG);
+    s.rounded_rect_lines(Rect::new(-20.0, -20.0, 25.0, 25.0), 10.0, FG);
+    s.polygon(&[far, Vec2::new(16.0, 12.0), Vec2::new(1e6, -1e6)], FG);
+    s.polygon_lines(&[far, Vec2::new(16.0, 12.0), Vec2::new(100.0, -3.0)], FG);
+    s.thick_line(far, Vec2::new(40.0, 40.0), 9.0, LineCap::Round, FG);
+    s.line(Vec2::new(-5.5, 3.25), Vec2::new(40.7, 20.1), FG);
+    s.gradient_rect(
+        Rect::new(-10.0, -10.0, 100.0, 100.0),
+        BG,
+        FG,
+        Gradient::Vertical,
+    );
+    s.gradient_rect(
+        Rect::new(-10.0, -10.0, 100.0, 100.0),
+        BG,
+        FG,
+        Gradient::Horizontal,
+    );
+    // and the degenerate ones draw nothing rather than something odd
+    let mut fb = vec![0; W * H * DEPTH];
+    let mut s = Screen::wrap(&mut fb, W, H);
+    s.filled_circle((5, 5), 0, FG);
+    s.ellipse(Rect::new(5.0, 5.0, 0.0, 10.0), FG);
+    s.rounded_rect(Rect::new(5.0, 5.0, -4.0, 4.0), 2.0, FG);
+    s.polygon(&[Vec2::new(1.0, 1.0), Vec2::new(9.0, 9.0)], FG);
+    s.thick_line(
+        Vec2::new(1.0, 1.0),
+        Vec2::new(9.0, 9.0),
+        0.0,
+        LineCap::Round,
+        FG,
+    );
+    assert!(fb.iter().all(|&b| b == 0));
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::PathBuf;
+
+use engine2d::save::{SaveData, SaveDir, SaveError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// A fresh folder for each test
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("save-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Scores {
+    scores: Vec<u32>,
+}
+
+impl SaveData for Scores {
+    const VERSION: u32 = 1;
+}
+
+// Version 1 had `best`, version 2 a list, and version 3 the list plus who set each score
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Table {
+    scores: Vec<(String, u32)>,
+}
+
+impl SaveData for Table {
+    const VERSION: u32 = 3;
+
+    fn migrate(from: u32, data: Value) -> Result<Value, String> {
+        match from {
+            1 => Ok(json!({ "scores": [data["best"]] })),
+            2 => {
+                let scores = data["scores"].as_array().ok_or("no scores")?;
+                let named: Vec<Value> = scores.iter().map(|s| json!(["?", s])).collect();
+                Ok(json!({ "scores": named }))
+            }
+            _ => Err(format!("no version {}", from)),
+        }
+    }
+}
+
+#[test]
+fn slots_round_trip() {
+    let path = dir("round-trip");
+    let saves = SaveDir::new(&path);
+    assert_eq!(saves.read::<Scores>("scores").unwrap(), None);
+    assert_eq!(saves.slots(), Vec::<String>::new());
+
+    let scores = Scores {
+        scores: vec![9, 4, 1],
+    };
+    saves.write("scores", &scores).unwrap();
+    saves.write("other", &Scores { scores: vec![] }).unwrap();
+    assert_eq!(saves.read::<Scores>("scores").unwrap(), Some(scores));
+    // nothing is left over from writing
+    assert_eq!(saves.slots(), vec!["other", "scores"]);
+    assert_eq!(fs::read_dir(&path).unwrap().count(), 2);
+
+    let saved: Value = serde_json::from_slice(&fs::read(saves.path("scores")).unwrap()).unwrap();
+    assert_eq!(
+        saved,
+        json!({ "version": 1, "data": { "scores": [9, 4, 1] } })
+    );
+
+    saves.delete("scores").unwrap();
+    saves.delete("scores").unwrap();
+    assert_eq!(saves.slots(), vec!["other"]);
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn old_saves_are_migrated_a_step_at_a_time() {
+    let path = dir("migrate");
+    let saves = SaveDir::new(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    fs::write(
+        saves.path("v1"),
+        r#"{ "version": 1, "data": { "best": 12 } }"#,
+    )
+    .unwrap();
+    fs::write(
+        saves.path("v2"),
+        r#"{ "version": 2, "data": { "scores": [5, 3] } }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        saves.read::<Table>("v1").unwrap().unwrap().scores,
+        vec![("?".to_string(), 12)]
+    );
+    assert_eq!(
+        saves.read::<Table>("v2").unwrap().unwrap().scores,
+        vec![("?".to_string(), 5), ("?".to_string(), 3)]
+    );
+
+    // without a migrate, old versions can't be read
+    fs::write(
+        saves.path("v0"),
+        r#"{ "version": 0, "data": { "scores": [] } }"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        saves.read::<Scores>("v0"),
+        Err(SaveError::Migrate(_))
+    ));
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn bad_saves_are_errors() {
+    let path = dir("bad");
+    let saves = SaveDir::new(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    fs::write(
+        saves.path("new"),
+        r#"{ "version": 2, "data": { "scores": [] } }"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        saves.read::<Scores>("new"),
+        Err(SaveError::TooNew {
+            found: 2,
+            current: 1
+        })
+    ));
+    fs::write(saves.path("torn"), r#"{ "version": 1, "da"#).unwrap();
+    assert!(matches!(
+        saves.read::<Scores>("torn"),
+        Err(SaveError::Format(_))
+    ));
+    fs::write(
+        saves.path("wrong"),
+        r#"{ "version": 1, "data": { "best": 1 } }"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        saves.read::<Scores>("wrong"),
+        Err(SaveError::Format(_))
+    ));
+    fs::remove_dir_all(&path).unwrap();
+}
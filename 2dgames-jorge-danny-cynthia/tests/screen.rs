@@ -0,0 +1,181 @@
+use engine2d::objects::{Color, IRect, PixelFormat, Rect, Vec2, DEPTH, PIXEL_FORMAT};
+use engine2d::screen::{Letterbox, Screen};
+use engine2d::texture::Texture;
+use image::{Rgba, RgbaImage};
+
+const W: usize = 16;
+const H: usize = 12;
+const RED: Color = [255, 0, 0, 255];
+const BLACK: Color = [0, 0, 0, 255];
+
+fn pixel(fb: &[u8], x: usize, y: usize) -> Color {
+    let idx = (y * W + x) * DEPTH;
+    [fb[idx], fb[idx + 1], fb[idx + 2], fb[idx + 3]]
+}
+
+// (x, y) of every pixel that isn't black
+fn painted(fb: &[u8]) -> Vec<(usize, usize)> {
+    (0..H)
+        .flat_map(|y| (0..W).map(move |x| (x, y)))
+        .filter(|&(x, y)| pixel(fb, x, y) != BLACK)
+        .collect()
+}
+
+fn cells(x: std::ops::Range<usize>, y: std::ops::Range<usize>) -> Vec<(usize, usize)> {
+    y.flat_map(|y| x.clone().map(move |x| (x, y))).collect()
+}
+
+fn black() -> Vec<u8> {
+    BLACK.iter().copied().cycle().take(W * H * DEPTH).collect()
+}
+
+#[test]
+fn clip_limits_rect_and_clear() {
+    let mut fb = black();
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    screen.push_clip(Rect::new(2.0, 3.0, 4.0, 2.0));
+    screen.rect(Rect::new(0.0, 0.0, 16.0, 12.0), RED);
+    screen.pop_clip();
+    assert_eq!(painted(&fb), cells(2..6, 3..5));
+
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    screen.push_clip(Rect::new(2.0, 3.0, 1.0, 1.0));
+    screen.clear(BLACK);
+    let mut expected = cells(2..6, 3..5);
+    expected.retain(|&p| p != (2, 3));
+    assert_eq!(painted(&fb), expected);
+}
+
+#[test]
+fn nested_clips_intersect_and_pop_back() {
+    let mut fb = black();
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    screen.push_clip(Rect::new(0.0, 0.0, 8.0, 8.0));
+    screen.push_clip(Rect::new(4.0, 4.0, 8.0, 8.0));
+    assert_eq!(screen.clip_rect(), Rect::new(4.0, 4.0, 4.0, 4.0));
+    screen.push_clip(Rect::new(10.0, 10.0, 2.0, 2.0));
+    assert!(screen.clip_rect().is_empty());
+    screen.rect(Rect::new(0.0, 0.0, 16.0, 12.0), RED);
+    screen.pop_clip();
+    screen.pop_clip();
+    screen.rect(Rect::new(6.0, 6.0, 4.0, 4.0), RED);
+    screen.pop_clip();
+    assert_eq!(screen.clip_rect(), Rect::new(0.0, 0.0, 16.0, 12.0));
+    assert_eq!(painted(&fb), cells(6..8, 6..8));
+}
+
+#[test]
+fn viewport_moves_origin_and_clips() {
+    let mut fb = black();
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    screen.push_viewport(Rect::new(8.0, 4.0, 4.0, 4.0));
+    assert_eq!(screen.origin(), Vec2::new(8.0, 4.0));
+    assert_eq!(screen.clip_rect(), Rect::new(0.0, 0.0, 4.0, 4.0));
+    screen.rect(Rect::new(-1.0, 2.0, 3.0, 10.0), RED);
+    screen.draw_at(RED, 3, 0);
+    // off the viewport's right edge
+    screen.draw_at(RED, 4, 0);
+    screen.pop_viewport();
+    assert_eq!(screen.origin(), Vec2::ZERO);
+    let mut expected = vec![(11, 4)];
+    expected.extend(cells(8..10, 6..8));
+    assert_eq!(painted(&fb), expected);
+}
+
+#[test]
+fn nested_viewports_add_up() {
+    let mut fb = black();
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    screen.push_viewport(Rect::new(2.0, 2.0, 10.0, 10.0));
+    screen.push_viewport(Rect::new(3.0, 1.0, 2.0, 2.0));
+    assert_eq!(screen.origin(), Vec2::new(5.0, 3.0));
+    screen.line(Vec2::new(-5.0, 0.0), Vec2::new(10.0, 0.0), RED);
+    screen.pop_viewport();
+    screen.pop_viewport();
+    assert_eq!(painted(&fb), cells(5..7, 3..4));
+}
+
+#[test]
+fn bitblt_respects_clip_and_viewport() {
+    let tex = Texture::new(RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255])));
+    let frame = Rect::new(0.0, 0.0, 4.0, 4.0);
+    let mut fb = black();
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    screen.push_viewport(Rect::new(4.0, 4.0, 6.0, 6.0));
+    screen.push_clip(Rect::new(1.0, 1.0, 2.0, 10.0));
+    screen.bitblt(&tex, frame, Vec2::new(-1.0, 0.0));
+    screen.pop_clip();
+    screen.pop_viewport();
+    assert_eq!(painted(&fb), cells(5..7, 5..8));
+}
+
+#[test]
+#[should_panic]
+fn pops_must_match_pushes() {
+    let mut fb = black();
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    screen.push_viewport(Rect::new(0.0, 0.0, 4.0, 4.0));
+    screen.pop_clip();
+}
+
+#[test]
+fn letterbox_scales_by_whole_numbers_and_centers() {
+    // 3x wide but only 2x tall, so 2x with bars either side
+    let lb = Letterbox::new((160, 120), (500, 260));
+    assert_eq!(lb.scale, 2);
+    assert_eq!(lb.viewport, IRect::new(90, 10, 320, 240));
+
+    // an exact fit has no bars
+    let lb = Letterbox::new((160, 120), (640, 480));
+    assert_eq!(lb.scale, 4);
+    assert_eq!(lb.viewport, IRect::new(0, 0, 640, 480));
+
+    // a window smaller than the framebuffer still gets 1x, hanging off both sides
+    let lb = Letterbox::new((160, 120), (100, 100));
+    assert_eq!(lb.scale, 1);
+    assert_eq!(lb.viewport, IRect::new(-30, -10, 160, 120));
+}
+
+#[test]
+fn letterbox_maps_between_window_and_framebuffer() {
+    let lb = Letterbox::new((160, 120), (500, 260));
+    assert_eq!(lb.window_to_logical(90.0, 10.0), Some(Vec2::new(0.0, 0.0)));
+    assert_eq!(
+        lb.window_to_logical(251.0, 131.0),
+        Some(Vec2::new(80.5, 60.5))
+    );
+    assert_eq!(
+        lb.logical_to_window(Vec2::new(80.5, 60.5)),
+        Vec2::new(251.0, 131.0)
+    );
+    assert_eq!(lb.logical_to_window(Vec2::ZERO), Vec2::new(90.0, 10.0));
+
+    // the bars, and just past the framebuffer's right and bottom edges
+    assert_eq!(lb.window_to_logical(89.0, 100.0), None);
+    assert_eq!(lb.window_to_logical(450.0, 100.0), None);
+    assert_eq!(lb.window_to_logical(200.0, 5.0), None);
+    assert_eq!(lb.window_to_logical(410.0, 100.0), None);
+    assert_eq!(lb.window_to_logical(200.0, 250.0), None);
+    assert_eq!(
+        lb.window_to_logical(409.0, 249.0),
+        Some(Vec2::new(159.5, 119.5))
+    );
+}
+
+#[test]
+fn screens_use_the_one_pixel_format() {
+    assert_eq!(PIXEL_FORMAT, PixelFormat::Rgba8888);
+    assert_eq!(PixelFormat::Rgba8888.depth(), 4);
+    assert_eq!(DEPTH, PIXEL_FORMAT.depth());
+    let mut fb = black();
+    let screen = Screen::wrap(&mut fb, W, H);
+    assert_eq!(screen.format(), PIXEL_FORMAT);
+    assert_eq!(screen.size(), (W, H));
+}
+
+#[test]
+#[should_panic(expected = "framebuffer isn't 16x12 pixels")]
+fn wrap_checks_the_framebuffer_size() {
+    let mut fb = vec![0; W * H * 3];
+    Screen::wrap(&mut fb, W, H);
+}
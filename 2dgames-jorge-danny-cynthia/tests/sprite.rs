@@ -0,0 +1,97 @@
+use std::f32::consts::FRAC_PI_2;
+use std::rc::Rc;
+
+use engine2d::animation::{Animation, AnimationData, Frame, Playback};
+use engine2d::objects::{Rect, Vec2};
+use engine2d::screen::Transform;
+use engine2d::sprite::Sprite;
+use engine2d::texture::Texture;
+use image::RgbaImage;
+
+fn still(frame: Frame) -> Animation {
+    Animation::new(&Rc::new(AnimationData {
+        frames: vec![frame],
+        playback: Playback::Loop,
+    }))
+}
+
+// A 16x16 body at (100, 100) holding a 4x2 stick by one end. The stick is turned a quarter
+// turn clockwise and doubled about that end, so it points down from the hand at (12, 5).
+fn body_and_stick(transform: Transform) -> Sprite {
+    let image = Rc::new(Texture::new(RgbaImage::new(20, 16)));
+    let body =
+        Frame::new(Rect::new(0.0, 0.0, 16.0, 16.0), 1.0).with_anchor("hand", Vec2::new(12.0, 4.0));
+    let mut body = Sprite::new(&image, still(body), Vec2::new(100.0, 100.0));
+    body.transform = transform;
+    let mut stick = Sprite::new(
+        &image,
+        still(Frame::new(Rect::new(16.0, 0.0, 4.0, 2.0), 1.0)),
+        Vec2::ZERO,
+    );
+    stick.transform = Transform {
+        rotation: FRAC_PI_2,
+        scale: Vec2::new(2.0, 2.0),
+        pivot: Vec2::new(0.0, 1.0),
+        ..Transform::IDENTITY
+    };
+    body.attach("hand", 0, stick);
+    body
+}
+
+// Where the held end and the far end of the stick end up on screen
+fn stick_ends(body: &Sprite) -> (Vec2, Vec2) {
+    let mut blits = vec![];
+    body.for_each_blit(|_, frame, position, transform| blits.push((frame, position, *transform)));
+    assert_eq!(blits.len(), 2);
+    let (frame, position, transform) = blits[1];
+    let size = Vec2::new(frame.w, frame.h);
+    (
+        position + transform.apply(Vec2::new(0.0, 1.0), size),
+        position + transform.apply(Vec2::new(4.0, 1.0), size),
+    )
+}
+
+fn assert_near(actual: Vec2, expected: (f32, f32)) {
+    assert!(
+        (actual.x - expected.0).abs() < 1e-3 && (actual.y - expected.1).abs() < 1e-3,
+        "{:?} isn't near {:?}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn children_turn_about_their_own_pivot() {
+    // on its own the stick hangs straight down from the hand
+    let (held, far) = stick_ends(&body_and_stick(Transform::IDENTITY));
+    assert_near(held, (112.0, 105.0));
+    assert_near(far, (112.0, 113.0));
+
+    // the body turned a quarter turn about its middle swings the hand round to (111, 112),
+    // and the stick with it, so it points left
+    let turned = Transform {
+        rotation: FRAC_PI_2,
+        pivot: Vec2::new(8.0, 8.0),
+        ..Transform::IDENTITY
+    };
+    let (held, far) = stick_ends(&body_and_stick(turned));
+    assert_near(held, (111.0, 112.0));
+    assert_near(far, (103.0, 112.0));
+
+    // mirrored first, the hand is on the other side, at (111, 104) once turned; the stick
+    // still points down before the turn, so it still ends up pointing left
+    let (held, far) = stick_ends(&body_and_stick(Transform {
+        flip_x: true,
+        ..turned
+    }));
+    assert_near(held, (111.0, 104.0));
+    assert_near(far, (103.0, 104.0));
+
+    // a bigger body carries the hand further from its pivot, and makes the stick bigger
+    let (held, far) = stick_ends(&body_and_stick(Transform {
+        scale: Vec2::new(2.0, 2.0),
+        ..turned
+    }));
+    assert_near(held, (114.0, 116.0));
+    assert_near(far, (98.0, 116.0));
+}
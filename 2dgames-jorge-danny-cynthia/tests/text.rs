@@ -0,0 +1,205 @@
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use engine2d::dialogue::{self, NamedScene, Next, Response, Scene, Story};
+use engine2d::locale::Strings;
+use engine2d::objects::{Color, Rect, Vec2};
+use engine2d::screen::Screen;
+use engine2d::text::{DrawTextExt, TextInfo};
+use engine2d::texture::Texture;
+use image::{Rgba, RgbaImage};
+
+// 8x8 solid glyphs for a handful of characters
+fn font() -> TextInfo {
+    font_of(" abcdefghijklmnopqrstuvwxyz", [255, 255, 255, 255])
+}
+
+// 8x8 solid glyphs in color for each of chars
+fn font_of(chars: &str, color: Color) -> TextInfo {
+    let image = Rc::new(Texture::new(RgbaImage::from_pixel(
+        8 * chars.chars().count() as u32,
+        8,
+        Rgba(color),
+    )));
+    let info: Vec<(char, Rect)> = chars
+        .chars()
+        .enumerate()
+        .map(|(i, ch)| (ch, Rect::new(8.0 * i as f32, 0.0, 8.0, 8.0)))
+        .collect();
+    TextInfo::new(&image, &info)
+}
+
+#[test]
+fn wrap_breaks_between_words() {
+    let font = font();
+    let text = "one two  three four";
+    assert_eq!(
+        font.wrap(text, 64.0),
+        vec![
+            (0, "one two ".to_string()),
+            (9, "three ".to_string()),
+            (15, "four ".to_string()),
+        ]
+    );
+    // too wide for any line, so it gets its own after an empty one
+    assert_eq!(
+        font.wrap("enormous", 32.0),
+        vec![(0, String::new()), (0, "enormous ".to_string())]
+    );
+}
+
+#[test]
+fn draw_text_in_rect_stops_where_wrap_says() {
+    let font = font();
+    let mut frame = vec![0; 64 * 64 * 4];
+    let mut screen = Screen::wrap(&mut frame, 64, 64);
+    let text = "one two  three four";
+    // room for two lines
+    assert_eq!(
+        screen.draw_text_in_rect(text, Rect::new(0.0, 0.0, 64.0, 16.0), &font, false),
+        Some(font.wrap(text, 64.0)[2].0)
+    );
+    assert_eq!(
+        screen.draw_text_in_rect(text, Rect::new(0.0, 0.0, 64.0, 24.0), &font, false),
+        None
+    );
+}
+
+#[test]
+fn pages_pick_up_where_the_last_one_stopped() {
+    let font = font();
+    let mut frame = vec![0; 64 * 64 * 4];
+    let mut screen = Screen::wrap(&mut frame, 64, 64);
+    let text = "one two  three four";
+    // room for one line, so each page is a line
+    let one_line = Rect::new(0.0, 0.0, 64.0, 8.0);
+    let mut pages = vec![];
+    let mut from = 0;
+    while let Some(next) = screen.draw_text_page(text, from, one_line, &font) {
+        pages.push(&text[from..next]);
+        from = next;
+    }
+    pages.push(&text[from..]);
+    assert_eq!(pages, vec!["one two  ", "three ", "four"]);
+}
+
+fn chars(s: &str) -> BTreeSet<char> {
+    s.chars().collect()
+}
+
+// The color of the middle of the first glyph drawn from text
+fn first_glyph_color(text: &str, font: &TextInfo) -> Color {
+    let mut frame = vec![0; 16 * 8 * 4];
+    let mut screen = Screen::wrap(&mut frame, 16, 8);
+    screen.draw_text_at_pos(text, Vec2::ZERO, font);
+    let i = (4 * 16 + 4) * 4;
+    [frame[i], frame[i + 1], frame[i + 2], frame[i + 3]]
+}
+
+#[test]
+fn fallback_fonts_chain() {
+    let white = [255, 255, 255, 255];
+    let red = [255, 0, 0, 255];
+    let blue = [0, 0, 255, 255];
+    let mut last = font_of("c", blue);
+    // the same character in two fallbacks comes from the first
+    last.add_fallback_font(&Rc::new(font_of("bd", [0, 255, 0, 255])));
+    let mut middle = font_of("b", red);
+    middle.add_fallback_font(&Rc::new(last));
+    let mut font = font_of(" a", white);
+    font.add_fallback_font(&Rc::new(middle));
+
+    assert!(font.supports('a') && font.supports('b') && font.supports('c'));
+    assert!(font.supports('d') && !font.supports('e'));
+    assert_eq!(font.missing_glyphs("a bad cafe\n"), chars("ef"));
+    assert_eq!(first_glyph_color("a", &font), white);
+    assert_eq!(first_glyph_color("b", &font), red);
+    assert_eq!(first_glyph_color("c", &font), blue);
+}
+
+#[test]
+fn the_fallback_glyph_stands_in_for_missing_characters() {
+    let mut font = font_of(" ?", [255, 255, 255, 255]);
+    // nothing drawn, and no room taken up
+    assert_eq!(first_glyph_color("é", &font), [0, 0, 0, 0]);
+    assert!(!font.set_fallback_glyph('!'));
+    assert!(font.set_fallback_glyph('?'));
+    assert_eq!(first_glyph_color("é", &font), [255, 255, 255, 255]);
+    // still counted as missing, since it's only a stand-in
+    assert!(!font.supports('é'));
+    assert_eq!(font.missing_glyphs("? é"), chars("é"));
+}
+
+#[test]
+fn missing_glyphs_are_reported_per_scene() {
+    let scene = |name: &str, speaker: &str, message: &str, next| NamedScene {
+        scene_name: name.to_string(),
+        scene: Scene::new(speaker, message, next),
+    };
+    let story = Story {
+        story_name: "glyphs".to_string(),
+        variables: Default::default(),
+        scenes: vec![
+            scene(
+                "intro",
+                "nemo",
+                "hi",
+                Next::Choice(vec![Response::new("café", "end")]),
+            ),
+            scene(
+                "fine",
+                "dory",
+                "just keep swimming",
+                Next::Continue("end".to_string()),
+            ),
+            scene("end", "Marlin", "the end!", Next::End),
+        ],
+    };
+    let missing = dialogue::missing_glyphs(&story, &font(), |_| None);
+    assert_eq!(
+        missing.into_iter().collect::<Vec<_>>(),
+        vec![
+            ("end".to_string(), chars("M!")),
+            ("intro".to_string(), chars("é")),
+        ]
+    );
+}
+
+#[test]
+fn missing_glyphs_are_found_in_localized_text_too() {
+    let story = Story {
+        story_name: "glyphs".to_string(),
+        variables: Default::default(),
+        scenes: vec![
+            NamedScene {
+                scene_name: "intro".to_string(),
+                scene: Scene::new(
+                    "nemo",
+                    "hi",
+                    Next::Choice(vec![
+                        Response::new("bye", "end").with_response_id("intro.bye")
+                    ]),
+                )
+                .with_message_id("intro.hi"),
+            },
+            NamedScene {
+                scene_name: "end".to_string(),
+                scene: Scene::new("", "the end", Next::End).with_message_id("end.gone"),
+            },
+        ],
+    };
+    let strings = Strings::new(
+        "de",
+        [("intro.hi", "grüß dich"), ("intro.bye", "tschüss")]
+            .iter()
+            .map(|(id, text)| (id.to_string(), text.to_string()))
+            .collect(),
+    );
+    // the story's own text is all fine, and end.gone looks up to nothing
+    assert!(dialogue::missing_glyphs(&story, &font(), |_| None).is_empty());
+    let missing = dialogue::missing_glyphs(&story, &font(), |id| strings.get(id));
+    assert_eq!(
+        missing.into_iter().collect::<Vec<_>>(),
+        vec![("intro".to_string(), chars("üß"))]
+    );
+}
@@ -0,0 +1,134 @@
+// Each test imports tests/twee/<name>.twee and checks the story and problems it comes out as
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use engine2d::dialogue::{self, NamedScene, Next, Response, Scene, Story, TweeProblem};
+
+fn import(name: &str) -> (Story, Vec<TweeProblem>) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "twee", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("twee");
+    let source = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("can't open {}: {}", path.display(), e));
+    dialogue::import_twee(&source)
+}
+
+fn scene(scene_name: &str, speaker: &str, message: &str, links: &[(&str, &str)]) -> NamedScene {
+    let next = if links.is_empty() {
+        Next::End
+    } else {
+        Next::Choice(
+            links
+                .iter()
+                .map(|(text, goto)| Response::new(text, goto))
+                .collect(),
+        )
+    };
+    NamedScene {
+        scene_name: scene_name.to_string(),
+        scene: Scene::new(speaker, message, next),
+    }
+}
+
+#[test]
+fn plain_passages_and_links_come_over() {
+    let (story, problems) = import("basic");
+    assert_eq!(problems, vec![]);
+    assert_eq!(
+        story,
+        Story {
+            story_name: "The Treasure".to_string(),
+            variables: Default::default(),
+            scenes: vec![
+                // the link in the sentence leaves its text, the one on its own line goes
+                scene(
+                    "intro",
+                    "Nemo",
+                    "Where should we look, the reef or the wreck?",
+                    &[("the reef", "the reef"), ("The wreck", "wreck")],
+                ),
+                scene(
+                    "the reef",
+                    "Dory",
+                    "Just keep swimming!",
+                    &[("Swim on", "wreck"), ("Back", "Go home")],
+                ),
+                // braces are only text in Twine
+                scene(
+                    "wreck",
+                    "Nemo",
+                    "A map! It says {{north}}.",
+                    &[("Follow it", "end")],
+                ),
+                scene("Go home", "", "Marlin's waiting.", &[("intro", "intro")]),
+                scene("end", "", "The end.", &[]),
+            ],
+        }
+    );
+    assert_eq!(dialogue::validate(&story, "intro"), vec![]);
+
+    let found = dialogue::explore(&Rc::new(story), "intro", 100, 100_000).unwrap();
+    assert!(found.failures.is_empty());
+    assert_eq!(found.endings.keys().collect::<Vec<_>>(), vec!["end"]);
+}
+
+#[test]
+fn what_nemo_cant_do_is_reported() {
+    let (story, problems) = import("harlowe");
+    assert_eq!(
+        story,
+        Story {
+            story_name: "Markup".to_string(),
+            variables: Default::default(),
+            scenes: vec![
+                scene(
+                    "Start",
+                    "Nemo",
+                    "(set: $gold to 5)You have $gold coins. <b>Shiny!</b>",
+                    &[("Spend it", "Shop"), ("Door {{1}}", "Door {1}")],
+                ),
+                scene(
+                    "Shop",
+                    "Marlin",
+                    "<<if $gold gt 0>>Buy something.<</if>>",
+                    &[("Start", "Start")],
+                ),
+                // "(which is old)" is just words
+                scene("Door {1}", "", "The door (which is old) creaks.", &[]),
+            ],
+        }
+    );
+
+    let markup = |passage: &str, markup: &str| TweeProblem::Markup {
+        passage: passage.to_string(),
+        markup: markup.to_string(),
+    };
+    assert_eq!(
+        problems,
+        vec![
+            TweeProblem::Start("Start".to_string()),
+            TweeProblem::SkippedPassage("Story Stylesheet".to_string()),
+            TweeProblem::SkippedPassage("StoryInit".to_string()),
+            TweeProblem::ExtraTags {
+                passage: "Start".to_string(),
+                tags: vec!["excited".to_string()],
+            },
+            TweeProblem::LineBreaks("Start".to_string()),
+            markup("Start", "(set:"),
+            markup("Start", "$gold"),
+            markup("Start", "$gold"),
+            markup("Start", "<b>"),
+            markup("Start", "</b>"),
+            TweeProblem::Setter {
+                passage: "Start".to_string(),
+                setter: "$gold to 0".to_string(),
+            },
+            markup("Shop", "<<if"),
+            markup("Shop", "$gold"),
+            markup("Shop", "<</if"),
+            TweeProblem::DuplicatePassage("Shop".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,284 @@
+use std::rc::Rc;
+
+use engine2d::objects::{Rect, Vec2, DEPTH};
+use engine2d::screen::Screen;
+use engine2d::text::TextInfo;
+use engine2d::texture::Texture;
+use engine2d::ui::{Response, Theme, Ui, UiFrame, UiInput};
+use image::{Rgba, RgbaImage};
+
+const W: usize = 200;
+const H: usize = 120;
+const AREA: Rect = Rect {
+    x: 10.0,
+    y: 10.0,
+    w: 180.0,
+    h: 100.0,
+};
+
+// 8x8 solid glyphs for a handful of characters
+fn font() -> TextInfo {
+    let chars = " abcdefghijklmnopqrstuvwxyz";
+    let image = Rc::new(Texture::new(RgbaImage::from_pixel(
+        8 * chars.len() as u32,
+        8,
+        Rgba([255, 255, 255, 255]),
+    )));
+    let info: Vec<(char, Rect)> = chars
+        .chars()
+        .enumerate()
+        .map(|(i, ch)| (ch, Rect::new(8.0 * i as f32, 0.0, 8.0, 8.0)))
+        .collect();
+    TextInfo::new(&image, &info)
+}
+
+// Runs one frame of widgets against a blank screen
+fn frame<T, F>(ui: &mut Ui, input: &UiInput, widgets: F) -> T
+where
+    F: FnOnce(&mut UiFrame) -> T,
+{
+    let mut fb = vec![0; W * H * DEPTH];
+    let mut screen = Screen::wrap(&mut fb, W, H);
+    let font = font();
+    let mut frame = ui.begin(&mut screen, &font, input, AREA);
+    widgets(&mut frame)
+}
+
+fn three_buttons(f: &mut UiFrame) -> [Response; 3] {
+    [f.button("one"), f.button("two"), f.button("three")]
+}
+
+fn keys(down: bool, up: bool, activate: bool) -> UiInput {
+    UiInput {
+        down,
+        up,
+        activate,
+        ..UiInput::default()
+    }
+}
+
+#[test]
+fn rows_stack_down_the_area() {
+    let mut ui = Ui::new(Theme {
+        row_height: 16.0,
+        spacing: 2.0,
+        ..Theme::default()
+    });
+    let rects = frame(&mut ui, &UiInput::default(), |f| {
+        let label = f.label("title");
+        f.space(10.0);
+        let button = f.button("go").rect;
+        (label, button, f.next_rect())
+    });
+    assert_eq!(rects.0, Rect::new(10.0, 10.0, 180.0, 16.0));
+    assert_eq!(rects.1, Rect::new(10.0, 38.0, 180.0, 16.0));
+    assert_eq!(rects.2, Rect::new(10.0, 56.0, 180.0, 16.0));
+}
+
+#[test]
+fn keyboard_focus_wraps_and_activates() {
+    let mut ui = Ui::default();
+    let r = frame(&mut ui, &UiInput::default(), three_buttons);
+    assert!(r[0].focused && !r[1].focused);
+    let r = frame(&mut ui, &keys(false, true, false), three_buttons);
+    assert!(r[2].focused);
+    let r = frame(&mut ui, &keys(true, false, false), three_buttons);
+    assert!(r[0].focused);
+    let r = frame(&mut ui, &keys(true, false, true), three_buttons);
+    assert!(r[1].focused && r[1].activated);
+    assert!(!r[0].activated && !r[2].activated);
+    assert_eq!(ui.focus(), 1);
+}
+
+#[test]
+fn menu_reports_the_pressed_item() {
+    let mut ui = Ui::default();
+    let items = ["yes", "no"];
+    assert_eq!(
+        frame(&mut ui, &UiInput::default(), |f| f.menu(&items)),
+        None
+    );
+    let pressed = frame(&mut ui, &keys(true, false, true), |f| f.menu(&items));
+    assert_eq!(pressed, Some(1));
+}
+
+#[test]
+fn focus_stays_in_range_when_widgets_go_away() {
+    let mut ui = Ui::default();
+    ui.set_focus(2);
+    frame(&mut ui, &UiInput::default(), three_buttons);
+    assert_eq!(ui.focus(), 2);
+    frame(&mut ui, &UiInput::default(), |f| f.button("only"));
+    assert_eq!(ui.focus(), 0);
+}
+
+#[test]
+fn pointer_focuses_and_clicks() {
+    let mut ui = Ui::default();
+    let over_third = Vec2::new(50.0, 10.0 + 2.0 * 24.0 + 5.0);
+    let hover = UiInput {
+        pointer: Some(over_third),
+        ..UiInput::default()
+    };
+    let r = frame(&mut ui, &hover, three_buttons);
+    assert!(r[2].focused && !r[2].activated);
+    // a pointer that stays put doesn't take focus back from the keyboard
+    let r = frame(
+        &mut ui,
+        &UiInput {
+            up: true,
+            ..hover.clone()
+        },
+        three_buttons,
+    );
+    assert!(r[1].focused);
+    let click = UiInput {
+        pointer: Some(Vec2::new(50.0, 12.0)),
+        clicked: true,
+        pointer_held: true,
+        ..UiInput::default()
+    };
+    let r = frame(&mut ui, &click, three_buttons);
+    assert!(r[0].focused && r[0].activated);
+}
+
+#[test]
+fn checkbox_toggles() {
+    let mut ui = Ui::default();
+    let mut on = false;
+    let r = frame(&mut ui, &UiInput::default(), |f| {
+        f.checkbox("sound", &mut on)
+    });
+    assert!(!on && !r.changed);
+    let r = frame(&mut ui, &keys(false, false, true), |f| {
+        f.checkbox("sound", &mut on)
+    });
+    assert!(on && r.changed);
+}
+
+#[test]
+fn slider_steps_clamps_and_drags() {
+    let mut ui = Ui::default();
+    let mut volume = 0.5;
+    let right = UiInput {
+        right: true,
+        ..UiInput::default()
+    };
+    let r = frame(&mut ui, &right, |f| {
+        f.slider("vol", &mut volume, 0.0, 1.0, 0.25)
+    });
+    assert!(r.changed);
+    assert_eq!(volume, 0.75);
+    frame(&mut ui, &right, |f| {
+        f.slider("vol", &mut volume, 0.0, 1.0, 0.25)
+    });
+    frame(&mut ui, &right, |f| {
+        f.slider("vol", &mut volume, 0.0, 1.0, 0.25)
+    });
+    assert_eq!(volume, 1.0);
+
+    // the track is the right half of the row: x 100 to 186
+    let track_y = 15.0;
+    let press = UiInput {
+        pointer: Some(Vec2::new(100.0, track_y)),
+        clicked: true,
+        pointer_held: true,
+        ..UiInput::default()
+    };
+    frame(&mut ui, &press, |f| {
+        f.slider("vol", &mut volume, 0.0, 1.0, 0.25)
+    });
+    assert_eq!(volume, 0.0);
+    // still dragging with the pointer off the row
+    let drag = UiInput {
+        pointer: Some(Vec2::new(143.0, 80.0)),
+        pointer_held: true,
+        ..UiInput::default()
+    };
+    frame(&mut ui, &drag, |f| {
+        f.slider("vol", &mut volume, 0.0, 1.0, 0.25)
+    });
+    assert_eq!(volume, 0.5);
+    let released = UiInput {
+        pointer: Some(Vec2::new(186.0, 80.0)),
+        ..UiInput::default()
+    };
+    frame(&mut ui, &released, |f| {
+        f.slider("vol", &mut volume, 0.0, 1.0, 0.25)
+    });
+    assert_eq!(volume, 0.5);
+}
+
+#[test]
+fn text_input_types_into_the_focused_field() {
+    let mut ui = Ui::default();
+    let mut name = String::from("ab");
+    let mut other = String::new();
+    let typing = UiInput {
+        text: "cd?e".to_string(),
+        backspaces: 1,
+        ..UiInput::default()
+    };
+    let r = frame(&mut ui, &typing, |f| {
+        (
+            f.text_input("name", &mut name, 4),
+            f.text_input("other", &mut other, 4),
+        )
+    });
+    // the backspace goes first, '?' isn't in the font, and it stops at four characters
+    assert_eq!(name, "acde");
+    assert_eq!(other, "");
+    assert!(r.0.changed && !r.0.activated);
+
+    // the activate key doesn't submit a text field, enter does
+    let r = frame(&mut ui, &keys(false, false, true), |f| {
+        f.text_input("name", &mut name, 4)
+    });
+    assert!(!r.activated);
+    let enter = UiInput {
+        text: "\n".to_string(),
+        ..UiInput::default()
+    };
+    let r = frame(&mut ui, &enter, |f| f.text_input("name", &mut name, 4));
+    assert!(r.activated && !r.changed);
+    assert_eq!(name, "acde");
+}
+
+#[test]
+fn wrapped_rows_grow_to_fit_their_text() {
+    let mut ui = Ui::default();
+    // 20 characters fit beside the marker
+    let rects = frame(&mut ui, &keys(false, false, true), |f| {
+        let short = f.wrapped_button("short");
+        let long = f.wrapped_button("one two three four five six seven eight nine");
+        let label = f.wrapped_label("one two three four five");
+        (short, long, label, f.next_rect())
+    });
+    assert_eq!(rects.0.rect, Rect::new(10.0, 10.0, 180.0, 20.0));
+    assert!(rects.0.activated);
+    assert_eq!(rects.1.rect, Rect::new(10.0, 34.0, 180.0, 36.0));
+    assert!(!rects.1.focused);
+    assert_eq!(rects.2, Rect::new(10.0, 74.0, 180.0, 28.0));
+    assert_eq!(rects.3.y, 106.0);
+}
+
+#[test]
+fn idle_input_ignores_where_the_pointer_is() {
+    let resting = UiInput {
+        pointer: Some(Vec2::new(5.0, 5.0)),
+        ..UiInput::default()
+    };
+    assert!(UiInput::default().is_idle());
+    assert!(resting.is_idle());
+    assert!(!keys(true, false, false).is_idle());
+    assert!(!UiInput {
+        clicked: true,
+        ..resting.clone()
+    }
+    .is_idle());
+    assert!(!UiInput {
+        text: "a".to_string(),
+        ..UiInput::default()
+    }
+    .is_idle());
+}
@@ -0,0 +1,29 @@
+use std::fs::{self, File};
+use std::time::{Duration, SystemTime};
+
+use engine2d::watch::FileWatcher;
+
+#[test]
+fn watcher_sees_each_change_once() {
+    let path = std::env::temp_dir().join(format!("watch-{}.txt", std::process::id()));
+    fs::write(&path, "one").unwrap();
+    let mut watcher = FileWatcher::new(&path).with_interval(Duration::from_secs(3600));
+    assert!(!watcher.check_now());
+
+    // file systems can be coarse about times, so set one that's clearly different
+    let later = SystemTime::now() + Duration::from_secs(10);
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    // not time to look yet
+    assert!(!watcher.changed());
+    assert!(watcher.check_now());
+    assert!(!watcher.check_now());
+
+    fs::remove_file(&path).unwrap();
+    assert!(watcher.check_now());
+    assert!(!watcher.check_now());
+}